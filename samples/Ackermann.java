@@ -0,0 +1,22 @@
+public class Ackermann {
+    public static void main(String[] args) {
+        // expected: 1, 3, 7, 29, 253
+        System.out.println(ackermann(0, 0));
+        System.out.println(ackermann(1, 1));
+        System.out.println(ackermann(2, 2));
+        System.out.println(ackermann(3, 2));
+        // recurses about 255 frames deep
+        System.out.println(ackermann(3, 5));
+    }
+
+    // the recursion is as deep as the result, so it stresses the call depth more than the time
+    public static int ackermann(int m, int n) {
+        if (m == 0) {
+            return n + 1;
+        }
+        if (n == 0) {
+            return ackermann(m - 1, 1);
+        }
+        return ackermann(m - 1, ackermann(m, n - 1));
+    }
+}
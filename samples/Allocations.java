@@ -0,0 +1,47 @@
+import java.util.TreeMap;
+
+public class Allocations {
+    public static int[] kept;
+
+    public static int sum(int[] values) {
+        int total = 0;
+        for (int i = 0; i < values.length; i++) {
+            total += values[i];
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        // these stay alive while many short-lived objects are collected around them
+        TreeMap<Integer, String> map = new TreeMap<>((a, b) -> b - a);
+        StringBuilder log = new StringBuilder();
+        int[] previous = new int[1];
+
+        // without a garbage collector this would take hundreds of megabytes
+        for (int i = 0; i < 50000; i++) {
+            int[] values = new int[1000];
+            values[0] = i;
+            values[999] = previous[0];
+            previous = values;
+            if (i % 10000 == 0) {
+                map.put(i, new StringBuilder().append("step ").append(i).toString());
+                log.append(i).append(';');
+                kept = values;
+            }
+        }
+
+        System.out.println(map);
+        System.out.println(log);
+        System.out.println(sum(previous));
+        System.out.println(kept[0]);
+        System.out.println(map.get(20000));
+        System.out.println(Integer.valueOf(40000).equals(map.firstKey()));
+
+        // the identity hash code of an object does not change when the collector moves it
+        int hash = System.identityHashCode(log);
+        System.gc();
+        System.out.println(hash == System.identityHashCode(log));
+        System.out.println(hash == System.identityHashCode(map));
+        System.out.println(System.identityHashCode(null));
+    }
+}
@@ -69,6 +69,26 @@ public class Arithmetic {
         System.out.println(rem(100, -3));
         System.out.println(rem(-100, -3));
 
+        // the second value from the top is the left operand, with 10 pushed before 3
+        System.out.println(sub(10, 3));
+        System.out.println(mul(10, 3));
+        System.out.println(div(10, 3));
+        System.out.println(rem(10, 3));
+
+        // the minimum divided by -1 overflows, the remainder is 0
+        System.out.println(div(Integer.MIN_VALUE, -1));
+        System.out.println(rem(Integer.MIN_VALUE, -1));
+        try {
+            System.out.println(div(1, 0));
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.out.println(rem(1, 0));
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+
         System.out.println(neg(0));
         System.out.println(neg(1));
         System.out.println(neg(-10));
@@ -76,6 +96,13 @@ public class Arithmetic {
         System.out.println(neg(-1000));
         System.out.println(neg((1 << 31) - 1));
         System.out.println(neg(1 << 31));
+        // negating the minimum wraps around to itself
+        System.out.println(neg(Integer.MIN_VALUE) == Integer.MIN_VALUE);
+        System.out.println(neg(Integer.MIN_VALUE));
+        System.out.println(neg(Integer.MAX_VALUE));
+        System.out.println(neg(neg(Integer.MIN_VALUE)));
+        System.out.println(neg(Integer.MIN_VALUE + 1));
+        System.out.println(neg(neg(0)));
     }
 
     public static int add(int a, int b) {
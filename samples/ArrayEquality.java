@@ -0,0 +1,58 @@
+import java.util.Arrays;
+
+public class ArrayEquality {
+    public static void main(String[] args) {
+        // arrays of primitives are equal when their lengths and elements are
+        int[] numbers = {3, 1, 2};
+        System.out.println(Arrays.equals(numbers, new int[] {3, 1, 2}));
+        System.out.println(Arrays.equals(numbers, new int[] {3, 1}));
+        System.out.println(Arrays.equals(numbers, new int[] {3, 1, 4}));
+        System.out.println(Arrays.equals(new byte[] {1, -1}, new byte[] {1, -1}));
+
+        // two nulls are equal, a null and an array are not
+        int[] missing = null;
+        System.out.println(Arrays.equals(missing, null));
+        System.out.println(Arrays.equals(numbers, missing));
+
+        // the elements of an array of objects are compared with their equals
+        String[] words = {"a", "b", null};
+        System.out.println(Arrays.equals(words, new String[] {"a", new StringBuilder("b").toString(), null}));
+        System.out.println(Arrays.equals(words, new String[] {"a", "b", "c"}));
+        Object[] points = {new Point(1, 2), new Point(3, 4)};
+        System.out.println(Arrays.equals(points, new Object[] {new Point(1, 2), new Point(3, 4)}));
+        System.out.println(Arrays.equals(points, new Object[] {new Point(1, 2), new Point(4, 3)}));
+        // a class which does not override equals compares by identity
+        Object mark = new Mark();
+        System.out.println(Arrays.equals(new Object[] {mark}, new Object[] {mark}));
+        System.out.println(Arrays.equals(new Object[] {mark}, new Object[] {new Mark()}));
+
+        // equals compares nested arrays by identity, deepEquals by their elements
+        Object[] nested = {new int[] {1, 2}, new Object[] {"x", new int[] {3}}};
+        Object[] same = {new int[] {1, 2}, new Object[] {"x", new int[] {3}}};
+        Object[] different = {new int[] {1, 2}, new Object[] {"x", new int[] {4}}};
+        System.out.println(Arrays.equals(nested, same));
+        System.out.println(Arrays.deepEquals(nested, same));
+        System.out.println(Arrays.deepEquals(nested, different));
+        System.out.println(Arrays.deepEquals(nested, null));
+    }
+}
+
+class Point {
+    private final int x;
+    private final int y;
+
+    Point(int x, int y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    @Override
+    public boolean equals(Object other) {
+        // only points are compared in the sample
+        Point point = (Point) other;
+        return x == point.x && y == point.y;
+    }
+}
+
+class Mark {
+}
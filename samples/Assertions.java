@@ -0,0 +1,35 @@
+public class Assertions {
+    static int checked(int x) {
+        // assertions are disabled by default, so this never throws
+        assert x > 0 : "x must be positive";
+        return x;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(checked(-1));
+
+        try {
+            throw new AssertionError("boom");
+        } catch (AssertionError e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            throw new AssertionError(42);
+        } catch (Error e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            throw new AssertionError();
+        } catch (Throwable e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            throw new AssertionError(true);
+        } catch (AssertionError e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
@@ -0,0 +1,58 @@
+import java.util.concurrent.atomic.AtomicBoolean;
+import java.util.concurrent.atomic.AtomicInteger;
+import java.util.concurrent.atomic.AtomicLong;
+import java.util.stream.IntStream;
+
+public class Atomics {
+    public static void main(String[] args) {
+        AtomicInteger counter = new AtomicInteger(5);
+        System.out.println(counter.get());
+        System.out.println(counter.getAndIncrement());
+        System.out.println(counter.incrementAndGet());
+        System.out.println(counter.getAndAdd(10));
+        System.out.println(counter.addAndGet(-20));
+        counter.set(42);
+        System.out.println(counter.get());
+        System.out.println(counter.compareAndSet(41, 0));
+        System.out.println(counter.compareAndSet(42, 7));
+        System.out.println(counter.get());
+
+        // overflow wraps around like int
+        AtomicInteger max = new AtomicInteger(2147483647);
+        System.out.println(max.incrementAndGet());
+
+        // a counter captured by lambdas
+        AtomicInteger sum = new AtomicInteger();
+        IntStream.of(new int[] {1, 2, 3, 4}).forEach(i -> sum.addAndGet(i));
+        System.out.println(sum.get());
+        Runnable tick = () -> sum.getAndIncrement();
+        tick.run();
+        tick.run();
+        System.out.println(sum.get());
+
+        AtomicLong total = new AtomicLong(5000000000L);
+        System.out.println(total.get());
+        System.out.println(total.getAndIncrement());
+        System.out.println(total.decrementAndGet());
+        System.out.println(total.addAndGet(-6000000000L));
+        System.out.println(total.getAndAdd(3));
+        System.out.println(total.compareAndSet(5, 1));
+        System.out.println(total.compareAndSet(-999999997, 1L << 40));
+        System.out.println(total.getAndSet(7));
+        total.set(9);
+        System.out.println(total.longValue());
+        AtomicLong wrapped = new AtomicLong(Long.MAX_VALUE);
+        System.out.println(wrapped.incrementAndGet());
+        System.out.println(new AtomicLong().getAndDecrement());
+
+        // a flag captured by a lambda
+        AtomicBoolean seen = new AtomicBoolean();
+        System.out.println(seen.get());
+        IntStream.of(new int[] {1, 2, 3}).forEach(i -> seen.set(i == 2 || seen.get()));
+        System.out.println(seen.get());
+        System.out.println(seen.compareAndSet(false, false));
+        System.out.println(seen.compareAndSet(true, false));
+        System.out.println(seen.getAndSet(true));
+        System.out.println(new AtomicBoolean(true).get());
+    }
+}
@@ -0,0 +1,67 @@
+import java.util.Base64;
+
+public class Base64s {
+    public static byte[] bytes(int count, int first) {
+        byte[] bytes = new byte[count];
+        for (int i = 0; i < count; i++) {
+            bytes[i] = (byte) (first + 37 * i);
+        }
+        return bytes;
+    }
+
+    public static void print(byte[] bytes) {
+        StringBuilder builder = new StringBuilder();
+        for (int i = 0; i < bytes.length; i++) {
+            builder.append(bytes[i]).append(' ');
+        }
+        System.out.println(builder);
+    }
+
+    public static void tryDecode(Base64.Decoder decoder, String encoded) {
+        try {
+            print(decoder.decode(encoded));
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    public static void main(String[] args) {
+        Base64.Encoder encoder = Base64.getEncoder();
+        System.out.println(encoder.encodeToString(bytes(0, 0)));
+        System.out.println(encoder.encodeToString(bytes(1, 77)));
+        System.out.println(encoder.encodeToString(bytes(2, 77)));
+        System.out.println(encoder.encodeToString(bytes(3, 77)));
+        System.out.println(encoder.encodeToString(bytes(10, 250)));
+        System.out.println(encoder.withoutPadding().encodeToString(bytes(10, 250)));
+        System.out.println(encoder.encode(bytes(2, 1)).length);
+
+        // the URL alphabet ends with - and _ instead of + and /
+        System.out.println(Base64.getUrlEncoder().encodeToString(bytes(10, 250)));
+        // MIME breaks lines after 76 characters
+        System.out.println(Base64.getMimeEncoder().encodeToString(bytes(60, 3)));
+
+        Base64.Decoder decoder = Base64.getDecoder();
+        tryDecode(decoder, "TQ==");
+        tryDecode(decoder, "TXI=");
+        tryDecode(decoder, "TXJ3");
+        tryDecode(decoder, "TXI");
+        tryDecode(decoder, "");
+        tryDecode(decoder, encoder.encodeToString(bytes(10, 250)));
+        tryDecode(Base64.getUrlDecoder(), Base64.getUrlEncoder().encodeToString(bytes(10, 250)));
+        tryDecode(Base64.getMimeDecoder(), Base64.getMimeEncoder().encodeToString(bytes(60, 3)));
+
+        // invalid input
+        tryDecode(decoder, "T");
+        tryDecode(decoder, "TQ-=");
+        tryDecode(decoder, "T===");
+        tryDecode(decoder, "TQ=");
+        tryDecode(decoder, "TQ=x");
+        tryDecode(decoder, "TXI=TXI=");
+        tryDecode(decoder, "TXJ3T");
+        tryDecode(decoder, "=");
+        tryDecode(decoder, "T\u00e9Q=");
+        tryDecode(Base64.getUrlDecoder(), "+/8=");
+        tryDecode(Base64.getMimeDecoder(), "T Q = = !");
+        tryDecode(Base64.getMimeDecoder(), "TQ==x");
+    }
+}
@@ -0,0 +1,82 @@
+import java.math.BigDecimal;
+import java.math.RoundingMode;
+
+public class BigDecimals {
+    public static void main(String[] args) {
+        BigDecimal price = new BigDecimal("19.99");
+        BigDecimal quantity = BigDecimal.valueOf(3L);
+        BigDecimal rate = new BigDecimal("0.0825");
+        BigDecimal subtotal = price.multiply(quantity);
+        System.out.println(subtotal);
+        BigDecimal tax = subtotal.multiply(rate).setScale(2, RoundingMode.HALF_UP);
+        System.out.println(tax);
+        System.out.println(subtotal.add(tax));
+        System.out.println(subtotal.subtract(new BigDecimal("100")));
+
+        // 0.1 + 0.2 is exact
+        System.out.println(BigDecimal.valueOf(0.1).add(BigDecimal.valueOf(0.2)));
+        System.out.println(BigDecimal.valueOf(1e10));
+        System.out.println(BigDecimal.valueOf(12345L, 3));
+
+        // rounding modes
+        System.out.println(new BigDecimal("2.5").setScale(0, RoundingMode.HALF_EVEN));
+        System.out.println(new BigDecimal("3.5").setScale(0, RoundingMode.HALF_EVEN));
+        System.out.println(new BigDecimal("-2.5").setScale(0, RoundingMode.HALF_UP));
+        System.out.println(new BigDecimal("-2.5").setScale(0, RoundingMode.HALF_DOWN));
+        System.out.println(new BigDecimal("-2.1").setScale(0, RoundingMode.CEILING));
+        System.out.println(new BigDecimal("-2.1").setScale(0, RoundingMode.FLOOR));
+        System.out.println(new BigDecimal("2.1").setScale(0, RoundingMode.UP));
+        System.out.println(new BigDecimal("2.9").setScale(0, RoundingMode.DOWN));
+        System.out.println(new BigDecimal("1.5").setScale(3));
+
+        // division
+        BigDecimal one = BigDecimal.ONE;
+        BigDecimal three = new BigDecimal("3");
+        System.out.println(one.divide(three, 10, RoundingMode.HALF_UP));
+        System.out.println(one.divide(three, 5, RoundingMode.DOWN));
+        System.out.println(new BigDecimal("-2").divide(three, 4, RoundingMode.HALF_EVEN));
+        System.out.println(new BigDecimal("10.00").divide(three, RoundingMode.CEILING));
+        System.out.println(one.divide(new BigDecimal("8")));
+        System.out.println(new BigDecimal("100").divide(new BigDecimal("0.5")));
+        System.out.println(new BigDecimal("1.00").divide(new BigDecimal("4")));
+
+        // comparison and formatting
+        System.out.println(new BigDecimal("2.0").compareTo(new BigDecimal("2.00")));
+        System.out.println(new BigDecimal("2.0").equals(new BigDecimal("2.00")));
+        System.out.println(new BigDecimal("-1.5").compareTo(BigDecimal.ZERO));
+        System.out.println(new BigDecimal("1.5E-10"));
+        System.out.println(new BigDecimal("1.5E-10").toPlainString());
+        System.out.println(new BigDecimal("12E3"));
+        System.out.println(new BigDecimal("12E3").toPlainString());
+        System.out.println(new BigDecimal("0.000001"));
+        System.out.println(new BigDecimal("0.0000001"));
+        System.out.println(new BigDecimal("-0.00"));
+        System.out.println(new BigDecimal("123.4500").scale());
+        System.out.println(new BigDecimal("-7.25").doubleValue());
+
+        try {
+            one.divide(three);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            one.divide(BigDecimal.ZERO);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new BigDecimal("1.25").setScale(1);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new BigDecimal("1.2x");
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+
+        // the constants are the same object every time they are read
+        System.out.println(BigDecimal.ONE == BigDecimal.ONE);
+        System.out.println(RoundingMode.HALF_UP == RoundingMode.HALF_UP);
+    }
+}
@@ -0,0 +1,81 @@
+import java.math.BigInteger;
+
+public class BigIntegers {
+    public static void main(String[] args) {
+        BigInteger a = new BigInteger("123456789012345678901234567890");
+        BigInteger b = BigInteger.valueOf(-987654321987L);
+        System.out.println(a.add(b));
+        System.out.println(a.subtract(b));
+        System.out.println(a.multiply(b));
+        System.out.println(a.divide(b));
+        System.out.println(a.remainder(b));
+        System.out.println(b.mod(BigInteger.valueOf(1000L)));
+        System.out.println(b.remainder(BigInteger.valueOf(1000L)));
+        System.out.println(BigInteger.TWO.pow(200));
+        System.out.println(a.gcd(new BigInteger("9876543210")));
+        System.out.println(a.compareTo(b));
+        System.out.println(b.compareTo(a));
+        System.out.println(a.compareTo(new BigInteger("+123456789012345678901234567890")));
+        System.out.println(a.equals(new BigInteger("123456789012345678901234567890")));
+        System.out.println(a.negate().abs().equals(a));
+        System.out.println(b.signum());
+        System.out.println(a.max(b));
+        System.out.println(a.min(b));
+
+        // conversions keep the low bits like Java
+        System.out.println(a.intValue());
+        System.out.println(a.longValue());
+        System.out.println(b.intValue());
+        System.out.println(b.longValue());
+        System.out.println(a.toString(16));
+        System.out.println(b.toString(2));
+        System.out.println(new BigInteger("-ff", 16));
+        System.out.println(new BigInteger("-0"));
+
+        // squaring repeatedly grows fast
+        BigInteger x = BigInteger.TEN;
+        for (int i = 0; i < 5; i++) {
+            x = x.multiply(x).add(BigInteger.ONE);
+        }
+        System.out.println(x);
+        System.out.println(x.divide(BigInteger.TEN.pow(20)));
+
+        System.out.println(BigInteger.valueOf(2147483647L).isProbablePrime(20));
+        System.out.println(BigInteger.valueOf(2147483649L).isProbablePrime(20));
+        System.out.println(new BigInteger("170141183460469231731687303715884105727").isProbablePrime(20));
+        System.out.println(new BigInteger("170141183460469231731687303715884105729").isProbablePrime(20));
+        System.out.println(BigInteger.ONE.isProbablePrime(20));
+        System.out.println(BigInteger.valueOf(-7L).isProbablePrime(20));
+
+        try {
+            a.divide(BigInteger.ZERO);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            a.mod(b);
+        } catch (ArithmeticException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new BigInteger("12x");
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+        // the radix is checked before the digits
+        try {
+            new BigInteger("ff", 40);
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new BigInteger("", -2);
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(new BigInteger("zz", 36));
+
+        // the constants are the same object every time they are read
+        System.out.println(BigInteger.ZERO == BigInteger.ZERO);
+    }
+}
@@ -0,0 +1,31 @@
+public class Bits {
+    public static void main(String[] args) {
+        int[] values = {0, 1, -1, 12, -12, 0x80000000, 0x7fffffff, 0x12345678};
+        for (int i = 0; i < values.length; i++) {
+            int value = values[i];
+            System.out.println(value);
+            System.out.println(Integer.bitCount(value));
+            System.out.println(Integer.highestOneBit(value));
+            System.out.println(Integer.lowestOneBit(value));
+            System.out.println(Integer.numberOfLeadingZeros(value));
+            System.out.println(Integer.numberOfTrailingZeros(value));
+            System.out.println(Integer.reverse(value));
+            System.out.println(Integer.reverseBytes(value));
+            System.out.println(Integer.signum(value));
+        }
+
+        // the counts of a long are ints
+        System.out.println(Long.bitCount(Long.MAX_VALUE));
+        System.out.println(Long.bitCount(-1L));
+        System.out.println(Long.bitCount(0L));
+        System.out.println(Long.bitCount(0x123456789abcdefL));
+        System.out.println(Long.numberOfLeadingZeros(1L));
+        System.out.println(Long.numberOfLeadingZeros(0L));
+        System.out.println(Long.numberOfLeadingZeros(Long.MIN_VALUE));
+        System.out.println(Long.numberOfLeadingZeros(1L << 40));
+        System.out.println(Long.numberOfTrailingZeros(1L << 40));
+        System.out.println(Long.numberOfTrailingZeros(0L));
+        System.out.println(Long.numberOfTrailingZeros(Long.MIN_VALUE));
+        System.out.println(Long.numberOfTrailingZeros(0x100000000L));
+    }
+}
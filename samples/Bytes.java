@@ -0,0 +1,31 @@
+public class Bytes {
+    public static int sum(byte[] bytes) {
+        int total = 0;
+        for (int i = 0; i < bytes.length; i++) {
+            total += bytes[i];
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        byte[] bytes = new byte[4];
+        System.out.println(bytes.length);
+        System.out.println(bytes[0]);
+
+        // only the low 8 bits are stored, and they are sign-extended when loaded
+        int value = 200;
+        bytes[0] = (byte) value;
+        System.out.println(bytes[0]);
+        bytes[1] = (byte) 300;
+        System.out.println(bytes[1]);
+
+        // incrementing the largest byte wraps around
+        bytes[2] = 127;
+        bytes[2]++;
+        System.out.println(bytes[2]);
+        bytes[3] = (byte) -129;
+        System.out.println(bytes[3]);
+
+        System.out.println(sum(bytes));
+    }
+}
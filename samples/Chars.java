@@ -0,0 +1,30 @@
+public class Chars {
+    public static void main(String[] args) {
+        System.out.println(Character.isDigit('5'));
+        System.out.println(Character.isDigit('x'));
+        System.out.println(Character.isLetter('q'));
+        System.out.println(Character.isLetter('%'));
+        System.out.println(Character.isWhitespace(' '));
+        System.out.println(Character.isWhitespace('\t'));
+        System.out.println(Character.isWhitespace('_'));
+
+        System.out.println(Character.toLowerCase('A'));
+        System.out.println(Character.toLowerCase('z'));
+        System.out.println(Character.toUpperCase('b'));
+        System.out.println(Character.toUpperCase('7'));
+
+        System.out.println(Character.getNumericValue('0'));
+        System.out.println(Character.getNumericValue('9'));
+        System.out.println(Character.getNumericValue('A'));
+        System.out.println(Character.getNumericValue('f'));
+        System.out.println(Character.getNumericValue('-'));
+
+        int sum = 0;
+        for (int c = '0'; c <= '9'; c++) {
+            if (Character.isDigit(c)) {
+                sum += Character.getNumericValue(c);
+            }
+        }
+        System.out.println(sum);
+    }
+}
@@ -0,0 +1,50 @@
+import java.util.zip.Adler32;
+import java.util.zip.CRC32;
+import java.util.zip.Checksum;
+
+public class Checksums {
+    public static byte[] letters(int count) {
+        byte[] bytes = new byte[count];
+        for (int i = 0; i < count; i++) {
+            bytes[i] = (byte) ('a' + i % 26);
+        }
+        return bytes;
+    }
+
+    public static void main(String[] args) {
+        CRC32 crc = new CRC32();
+        System.out.println(crc.getValue());
+        crc.update(letters(3));
+        System.out.println(crc.getValue());
+
+        // updating in parts gives the checksum of the whole
+        crc.reset();
+        crc.update(letters(10), 0, 4);
+        crc.update('e');
+        System.out.println(crc.getValue());
+        crc.reset();
+        crc.update(letters(5));
+        System.out.println(crc.getValue());
+
+        Adler32 adler = new Adler32();
+        System.out.println(adler.getValue());
+        adler.update(letters(3));
+        System.out.println(adler.getValue());
+        adler.update(letters(1000), 10, 990);
+        System.out.println(adler.getValue());
+        adler.reset();
+        System.out.println(adler.getValue());
+
+        // bytes above 127 are negative in Java, but checksummed unsigned
+        Checksum checksum = new CRC32();
+        checksum.update(200);
+        checksum.update(-1);
+        System.out.println(checksum.getValue());
+
+        try {
+            crc.update(letters(4), 2, 3);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println("out of bounds");
+        }
+    }
+}
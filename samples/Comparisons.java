@@ -0,0 +1,121 @@
+public class Comparisons {
+    public static void main(String[] args) {
+        // every pair in both orders, including equal and extreme values
+        compare(1, 2);
+        compare(2, 1);
+        compare(3, 3);
+        compare(-5, 5);
+        compare(5, -5);
+        compare(Integer.MIN_VALUE, Integer.MAX_VALUE);
+        compare(Integer.MAX_VALUE, Integer.MIN_VALUE);
+        compare(Integer.MIN_VALUE, Integer.MIN_VALUE);
+        compare(0, -1);
+
+        compareToZero(0);
+        compareToZero(-1);
+        compareToZero(1);
+        compareToZero(Integer.MIN_VALUE);
+        compareToZero(Integer.MAX_VALUE);
+
+        // backward branches counting down to and up to zero
+        int steps = 0;
+        for (int i = 10; i > 0; i -= 3) {
+            steps++;
+        }
+        for (int i = -10; i < 0; i += 4) {
+            steps++;
+        }
+        int n = 6;
+        while (n != 0) {
+            n--;
+            steps++;
+        }
+        System.out.println(steps);
+
+        int[] array = new int[1];
+        compareReferences(array, array);
+        compareReferences(array, new int[1]);
+        compareReferences(array, null);
+        compareReferences(null, null);
+    }
+
+    // reference equality compiles to if_acmp, a comparison with null to ifnull and ifnonnull
+    public static void compareReferences(int[] a, int[] b) {
+        System.out.print(a == b ? "same " : "different ");
+        System.out.print(a != b ? "ne " : "eq ");
+        if (a == null) {
+            System.out.print("a null ");
+        }
+        if (b != null) {
+            System.out.print("b set");
+        }
+        System.out.println();
+    }
+
+    // comparisons with zero compile to the single operand if instructions
+    public static void compareToZero(int a) {
+        if (a < 0) {
+            System.out.print("negative ");
+        } else if (a == 0) {
+            System.out.print("zero ");
+        } else {
+            System.out.print("positive ");
+        }
+        System.out.print(a <= 0 ? "le " : "gt ");
+        System.out.print(a >= 0 ? "ge " : "lt ");
+        System.out.println(a != 0 ? "ne" : "eq");
+    }
+
+    // javac negates the condition of an if, so each branch runs the opposite instruction
+    public static void compare(int a, int b) {
+        if (a < b) {
+            System.out.print("lt ");
+        }
+        if (a <= b) {
+            System.out.print("le ");
+        }
+        if (a > b) {
+            System.out.print("gt ");
+        }
+        if (a >= b) {
+            System.out.print("ge ");
+        }
+        if (a == b) {
+            System.out.print("eq ");
+        }
+        if (a != b) {
+            System.out.print("ne ");
+        }
+        // a do-while loop branches back on the condition as written
+        int count = 0;
+        do {
+            count++;
+        } while (a < b && count < 2);
+        System.out.print(count);
+        count = 0;
+        do {
+            count++;
+        } while (a <= b && count < 2);
+        System.out.print(count);
+        count = 0;
+        do {
+            count++;
+        } while (a > b && count < 2);
+        System.out.print(count);
+        count = 0;
+        do {
+            count++;
+        } while (a >= b && count < 2);
+        System.out.print(count);
+        count = 0;
+        do {
+            count++;
+        } while (a == b && count < 2);
+        System.out.print(count);
+        count = 0;
+        do {
+            count++;
+        } while (a != b && count < 2);
+        System.out.println(count);
+    }
+}
@@ -0,0 +1,23 @@
+public class Constants {
+    static float ratio = 0.75f;
+
+    public static void main(String[] args) {
+        // float constants are pushed by ldc
+        System.out.println(3.14f);
+        System.out.println(-0.1f);
+        System.out.println(1.0e10f);
+        System.out.println(1.0e-5f);
+        System.out.println(Float.MAX_VALUE);
+        System.out.println(ratio);
+
+        // long and double constants are pushed by ldc2_w
+        System.out.println(1234567890123L);
+        System.out.println(-9223372036854775808L);
+        System.out.println(2.718281828459045);
+        System.out.println(-1.5e300);
+
+        // constants after a long or double are found at the right index
+        System.out.println(100000);
+        System.out.println("done");
+    }
+}
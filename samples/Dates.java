@@ -0,0 +1,70 @@
+import java.time.DateTimeException;
+import java.time.LocalDate;
+import java.util.Date;
+
+public class Dates {
+    public static void main(String[] args) {
+        LocalDate date = LocalDate.of(2024, 2, 28);
+        System.out.println(date);
+        System.out.println(date.plusDays(1));
+        System.out.println(date.plusDays(2));
+        System.out.println(date.minusDays(59));
+        System.out.println(date.plusDays(100000));
+        System.out.println(date.getYear());
+        System.out.println(date.getMonthValue());
+        System.out.println(date.getDayOfMonth());
+        System.out.println(date.getDayOfYear());
+        System.out.println(date.isLeapYear());
+        System.out.println(date.lengthOfMonth());
+        System.out.println(date.toEpochDay());
+        System.out.println(LocalDate.ofEpochDay(0));
+        System.out.println(LocalDate.ofEpochDay(-1));
+        System.out.println(LocalDate.of(-5, 1, 1));
+        System.out.println(LocalDate.of(12345, 1, 1));
+        System.out.println(LocalDate.of(1900, 2, 1).lengthOfMonth());
+        System.out.println(LocalDate.of(2000, 2, 1).lengthOfMonth());
+
+        LocalDate other = LocalDate.of(2024, 3, 1);
+        System.out.println(date.isBefore(other));
+        System.out.println(date.isAfter(other));
+        System.out.println(date.plusDays(2).isEqual(other));
+        System.out.println(date.plusDays(2).equals(other));
+        System.out.println(date.compareTo(other) < 0);
+
+        try {
+            LocalDate.of(2023, 13, 1);
+        } catch (DateTimeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            LocalDate.of(2023, 2, 29);
+        } catch (DateTimeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            LocalDate.of(2024, 4, 31);
+        } catch (DateTimeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            LocalDate.of(2024, 4, 32);
+        } catch (DateTimeException e) {
+            System.out.println(e.getMessage());
+        }
+
+        Date epoch = new Date(0L);
+        Date later = new Date(1709164800123L);
+        System.out.println(epoch);
+        System.out.println(later);
+        System.out.println(new Date(-1L));
+        System.out.println(later.getTime());
+        System.out.println(later.getYear());
+        System.out.println(later.getMonth());
+        System.out.println(later.getDate());
+        System.out.println(later.getDay());
+        System.out.println(epoch.before(later));
+        System.out.println(epoch.after(later));
+        System.out.println(later.compareTo(epoch));
+        System.out.println(epoch.equals(new Date(0L)));
+    }
+}
@@ -0,0 +1,122 @@
+public class Dispatch {
+    public static void main(String[] args) {
+        // the methods run are those of the runtime class, not of the static type
+        Animal animal = new Animal(1);
+        Animal dog = new Dog(4);
+        Animal puppy = new Puppy(4);
+        System.out.println(animal.sound());
+        System.out.println(dog.sound());
+        System.out.println(puppy.sound());
+
+        // a method which is not overridden is inherited from the superclass
+        System.out.println(dog.legs());
+        System.out.println(puppy.legs());
+
+        // a method of the superclass calls the override through this
+        System.out.println(animal.describe());
+        System.out.println(puppy.describe());
+
+        // super calls the method of the superclass
+        System.out.println(puppy.weight());
+
+        Dog rex = new Dog(3);
+        rex.setLegs(4);
+        System.out.println(rex.legs());
+
+        // a method no class declares is the default method of an interface,
+        // the one of the most specific interface if several declare it
+        System.out.println(new Hiker().move());
+        System.out.println(new Athlete().move());
+        System.out.println(new Sprinter().move());
+        System.out.println(new Jogger().move());
+    }
+}
+
+interface Walker {
+    default String move() {
+        return "walk";
+    }
+}
+
+interface Runner extends Walker {
+    default String move() {
+        return "run";
+    }
+}
+
+class Hiker implements Walker {
+}
+
+class Athlete implements Walker, Runner {
+}
+
+class Sprinter extends Hiker implements Runner {
+}
+
+class Jogger implements Runner {
+    public String move() {
+        return Runner.super.move().substring(1);
+    }
+}
+
+class Animal {
+    private int legs;
+
+    Animal(int legs) {
+        this.legs = legs;
+    }
+
+    String sound() {
+        return "...";
+    }
+
+    int legs() {
+        return legs;
+    }
+
+    void setLegs(int legs) {
+        this.legs = legs;
+    }
+
+    int weight() {
+        return 10;
+    }
+
+    String describe() {
+        return sound();
+    }
+}
+
+class Dog extends Animal {
+    Dog(int legs) {
+        super(legs);
+    }
+
+    @Override
+    String sound() {
+        return "Woof";
+    }
+
+    @Override
+    int weight() {
+        return super.weight() * 3;
+    }
+}
+
+class Puppy extends Dog {
+    private int age = 1;
+
+    Puppy(int legs) {
+        super(legs);
+    }
+
+    @Override
+    String sound() {
+        return "Yip";
+    }
+
+    @Override
+    int weight() {
+        return super.weight() / 2 + age;
+    }
+}
@@ -0,0 +1,31 @@
+import java.math.BigInteger;
+
+public class Duplicates {
+    static double first;
+    static double second;
+    static long big;
+    static long bigger;
+
+    public static void main(String[] args) {
+        // a compound assignment to an array element duplicates the array and the index
+        int[] values = new int[3];
+        values[1] += 5;
+        values[1] *= 3;
+        values[2]++;
+        System.out.println(values[0]);
+        System.out.println(values[1]);
+        System.out.println(values[2]);
+
+        // a chained assignment duplicates a double or a long
+        first = second = 2.5;
+        System.out.println(first);
+        System.out.println(second);
+        big = bigger = 1L << 40;
+        System.out.println(big);
+        System.out.println(bigger);
+
+        // a discarded long result is popped at once
+        BigInteger.valueOf(42).longValue();
+        System.out.println(BigInteger.valueOf(42).longValue());
+    }
+}
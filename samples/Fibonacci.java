@@ -0,0 +1,33 @@
+public class Fibonacci {
+    public static void main(String[] args) {
+        // expected: 0, 1, 1, 55, 6765, 75025
+        System.out.println(fib(0));
+        System.out.println(fib(1));
+        System.out.println(fib(2));
+        System.out.println(fib(10));
+        System.out.println(fib(20));
+        System.out.println(fib(25));
+
+        // the iterative version wraps around like the int it returns, expected: -980107325
+        System.out.println(fibLoop(100));
+    }
+
+    // doubly recursive, each call is an invokestatic
+    public static int fib(int n) {
+        if (n < 2) {
+            return n;
+        }
+        return fib(n - 1) + fib(n - 2);
+    }
+
+    public static int fibLoop(int n) {
+        int a = 0;
+        int b = 1;
+        for (int i = 0; i < n; i++) {
+            int next = a + b;
+            a = b;
+            b = next;
+        }
+        return a;
+    }
+}
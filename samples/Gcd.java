@@ -0,0 +1,30 @@
+public class Gcd {
+    public static void main(String[] args) {
+        // expected: 6, 1, 12, 7, 1, 1073741824
+        System.out.println(gcd(48, 18));
+        System.out.println(gcd(17, 5));
+        System.out.println(gcd(0, 12));
+        System.out.println(gcd(-21, 14));
+        System.out.println(gcd(832040, 514229));
+        System.out.println(gcd(1 << 30, 0));
+
+        // a checksum over many pairs, expected: 31080
+        int sum = 0;
+        for (int a = 1; a <= 100; a++) {
+            for (int b = 1; b <= 100; b++) {
+                sum += gcd(a, b);
+            }
+        }
+        System.out.println(sum);
+    }
+
+    // Euclid's algorithm as a loop of irem
+    public static int gcd(int a, int b) {
+        while (b != 0) {
+            int r = a % b;
+            a = b;
+            b = r;
+        }
+        return a < 0 ? -a : a;
+    }
+}
@@ -0,0 +1,33 @@
+public class Increments {
+    public static void main(String[] args) {
+        // iinc by 1
+        int sum = 0;
+        for (int i = 0; i < 5; i++) {
+            sum += i;
+        }
+        System.out.println(sum);
+
+        // iinc by -1 in a countdown loop
+        for (int i = 3; i > 0; i--) {
+            System.out.println(i);
+        }
+
+        // the largest and smallest constants fitting into iinc
+        int x = 0;
+        x += 127;
+        System.out.println(x);
+        x -= 128;
+        System.out.println(x);
+
+        // increments wrap around like any int addition
+        int max = Integer.MAX_VALUE;
+        max++;
+        System.out.println(max);
+        int min = Integer.MIN_VALUE;
+        min--;
+        System.out.println(min);
+        int near = Integer.MAX_VALUE - 10;
+        near += 100;
+        System.out.println(near);
+    }
+}
@@ -0,0 +1,35 @@
+import java.io.IOException;
+import java.io.InputStream;
+
+public class Input {
+    public static void main(String[] args) throws IOException {
+        InputStream in = System.in;
+
+        // count the bytes and lines of stdin, reading a byte at a time
+        int bytes = 0;
+        int lines = 0;
+        int first = in.read();
+        int next = first;
+        while (next != -1) {
+            bytes++;
+            if (next == '\n') {
+                lines++;
+            }
+            next = in.read();
+        }
+        System.out.println(first);
+        System.out.println(bytes);
+        System.out.println(lines);
+
+        // nothing is left once the end was reached
+        byte[] buffer = new byte[4];
+        System.out.println(in.read(buffer));
+        System.out.println(in.read(buffer, 1, 3));
+        System.out.println(in.read(buffer, 4, 0));
+        try {
+            in.read(buffer, 2, 5);
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
@@ -0,0 +1,85 @@
+public class IntArrays {
+    public static int[] create(int size) {
+        return new int[size];
+    }
+
+    public static void main(String[] args) {
+        System.out.println(create(3).length);
+        System.out.println(create(0).length);
+
+        // a negative size throws, with the size as the message
+        try {
+            int[] array = new int[-1];
+            System.out.println(array.length);
+        } catch (NegativeArraySizeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            create(-42);
+        } catch (NegativeArraySizeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            byte[] bytes = new byte[Integer.MIN_VALUE];
+            System.out.println(bytes.length);
+        } catch (RuntimeException e) {
+            System.out.println(e.getMessage());
+        }
+
+        // a clone is a new array with the same elements
+        int[] original = {1, 2, 3};
+        int[] copy = original.clone();
+        copy[0] = 10;
+        System.out.println(original[0]);
+        System.out.println(copy[0]);
+        System.out.println(copy.length);
+        System.out.println(copy == original);
+        Object object = copy;
+        int[] cast = (int[]) object;
+        System.out.println(cast[0]);
+
+        // an array of references is cloned too
+        String[] arguments = args.clone();
+        System.out.println(arguments.length == args.length);
+        System.out.println(arguments == args);
+
+        byte[] bytes = {7, 8};
+        System.out.println(bytes.clone()[1]);
+
+        // an index out of bounds throws, with the index and the length in the message
+        try {
+            System.out.println(original[5]);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            original[-1] = 4;
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            bytes[2] = 9;
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.out.println(arguments[arguments.length]);
+        } catch (ArrayIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(original[2]);
+
+        // a null array throws NullPointerException
+        int[] missing = null;
+        try {
+            System.out.println(missing[0]);
+        } catch (NullPointerException e) {
+            System.out.println("null array");
+        }
+        try {
+            System.out.println(missing.length);
+        } catch (NullPointerException e) {
+            System.out.println("null length");
+        }
+    }
+}
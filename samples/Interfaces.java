@@ -0,0 +1,75 @@
+public class Interfaces {
+    public static void main(String[] args) {
+        System.out.println(Shape.triangle());
+        System.out.println(Shape.SQUARE);
+
+        // a lambda implements the abstract method, the default method has its own code
+        Shape pentagon = () -> 5;
+        System.out.println(pentagon.sides());
+        System.out.println(pentagon.corners());
+        System.out.println(pentagon.isPolygon());
+
+        Shape circle = () -> 0;
+        System.out.println(circle.isPolygon());
+
+        // a class implementing the interface inherits its default methods,
+        // which call the methods of the class
+        Shape square = new Square();
+        System.out.println(square.corners());
+        System.out.println(square.isPolygon());
+        Shape hexagon = new Hexagon();
+        System.out.println(hexagon.corners());
+        System.out.println(hexagon.isPolygon());
+
+        // a default method of a superinterface is inherited too
+        Solid prism = new Prism();
+        System.out.println(prism.faces());
+        System.out.println(prism.corners());
+    }
+}
+
+class Square implements Shape {
+    public int sides() {
+        return 4;
+    }
+}
+
+class Hexagon extends Square {
+    public int sides() {
+        return 6;
+    }
+
+    public int corners() {
+        return 2 * sides();
+    }
+}
+
+interface Solid extends Shape {
+    default int faces() {
+        return sides() + 2;
+    }
+}
+
+class Prism implements Solid {
+    public int sides() {
+        return 3;
+    }
+}
+
+interface Shape {
+    int SQUARE = 4;
+
+    int sides();
+
+    default int corners() {
+        return sides();
+    }
+
+    default boolean isPolygon() {
+        return sides() >= Shape.triangle();
+    }
+
+    static int triangle() {
+        return 3;
+    }
+}
@@ -0,0 +1,17 @@
+public class Interning {
+    public static void main(String[] args) {
+        String literal = "jajajvm";
+        String built = new StringBuilder("jaja").append("jvm").toString();
+        System.out.println(built == literal);
+        System.out.println(built.intern() == literal);
+        System.out.println(literal.intern() == literal);
+
+        // the first interned copy becomes the canonical one
+        String first = new StringBuilder("not a ").append("literal").toString();
+        String second = new StringBuilder("not a ").append("literal").toString();
+        System.out.println(first == second);
+        System.out.println(first.intern() == first);
+        System.out.println(second.intern() == first);
+        System.out.println(second.intern());
+    }
+}
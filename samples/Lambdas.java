@@ -0,0 +1,44 @@
+import java.util.Optional;
+import java.util.function.BiFunction;
+import java.util.function.Consumer;
+import java.util.function.Function;
+import java.util.function.IntPredicate;
+import java.util.function.IntUnaryOperator;
+import java.util.function.Supplier;
+import java.util.stream.IntStream;
+
+public class Lambdas {
+    public static void main(String[] args) {
+        Runnable hello = () -> System.out.println("hello from a lambda");
+        hello.run();
+
+        Supplier<String> supplier = () -> "supplied";
+        System.out.println(supplier.get());
+
+        Consumer<String> consumer = s -> System.out.println(s);
+        consumer.accept("consumed");
+
+        Function<String, String> identity = s -> s;
+        System.out.println(identity.apply("applied"));
+
+        BiFunction<String, String, String> second = (a, b) -> b;
+        System.out.println(second.apply("first", "second"));
+
+        IntPredicate even = x -> x % 2 == 0;
+        System.out.println(even.test(4));
+        System.out.println(even.test(7));
+
+        int offset = 100;
+        IntUnaryOperator shift = x -> x + offset;
+        System.out.println(shift.applyAsInt(5));
+
+        IntStream.of(1, 2, 3, 4, 5, 6).filter(x -> x % 2 == 0).map(x -> x * x).forEach(x -> System.out.println(x));
+        System.out.println(IntStream.of(1, 2, 3, 4, 5, 6).filter(x -> x > 3).sum());
+        System.out.println("lambda".chars().filter(c -> c == 'a').count());
+
+        System.out.println(Optional.empty().orElseGet(() -> "fallback"));
+        System.out.println(Optional.of("kept").orElseGet(() -> "fallback"));
+        Optional.of("present").ifPresent(s -> System.out.println(s));
+        Optional.empty().ifPresent(s -> System.out.println("never"));
+    }
+}
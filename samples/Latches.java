@@ -0,0 +1,36 @@
+import java.util.concurrent.CountDownLatch;
+import java.util.concurrent.atomic.AtomicInteger;
+
+public class Latches {
+    public static void main(String[] args) throws InterruptedException {
+        CountDownLatch ready = new CountDownLatch(2);
+        AtomicInteger total = new AtomicInteger();
+        Thread first = new Thread(() -> {
+            total.addAndGet(3);
+            ready.countDown();
+        });
+        Thread second = new Thread(() -> {
+            total.addAndGet(4);
+            ready.countDown();
+        });
+        System.out.println(ready.getCount());
+        first.start();
+        second.start();
+
+        // both workers have counted down once await returns
+        ready.await();
+        System.out.println(ready.getCount());
+        System.out.println(total.get());
+
+        // the count never goes below zero
+        ready.countDown();
+        System.out.println(ready.getCount());
+        ready.await();
+
+        try {
+            new CountDownLatch(-1);
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
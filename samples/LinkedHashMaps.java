@@ -0,0 +1,105 @@
+import java.util.LinkedHashMap;
+import java.util.Map;
+import java.util.TreeMap;
+
+public class LinkedHashMaps {
+    public static void main(String[] args) {
+        // the entries keep the order they were inserted in
+        LinkedHashMap<String, Integer> map = new LinkedHashMap<>();
+        map.put("pear", 3);
+        map.put("apple", 1);
+        map.put("fig", 7);
+        map.put("banana", 2);
+        System.out.println(map);
+        // replacing a value keeps the position of the entry
+        System.out.println(map.put("pear", 4));
+        System.out.println(map.put("cherry", 5));
+        System.out.println(map);
+        System.out.println(map.size());
+        System.out.println(map.get("fig"));
+        System.out.println(map.get("kiwi"));
+        System.out.println(map.getOrDefault("kiwi", 0));
+        System.out.println(map.containsKey("apple"));
+        System.out.println(map.remove("apple"));
+        System.out.println(map.remove("apple"));
+        System.out.println(map.keySet());
+        System.out.println(map.values());
+        System.out.println(map.entrySet());
+
+        int total = 0;
+        for (Map.Entry<String, Integer> entry : map.entrySet()) {
+            System.out.println(entry.getKey());
+            total += entry.getValue();
+        }
+        System.out.println(total);
+
+        // a key which was removed goes to the end when it is put again
+        map.remove("pear");
+        map.put("pear", 1);
+        System.out.println(map);
+
+        // keys are compared with equals, and null is a key like any other
+        Map<Object, String> keys = new LinkedHashMap<>();
+        keys.put(new Key(1), "one");
+        keys.put(new Key(2), "two");
+        keys.put(null, "none");
+        System.out.println(keys.get(new Key(2)));
+        System.out.println(keys.put(new Key(1), "uno"));
+        System.out.println(keys.get(null));
+        System.out.println(keys.size());
+
+        // in access order, get and put move the entry to the end, as in an LRU cache
+        LinkedHashMap<Integer, String> recent = new LinkedHashMap<>(16, 0.75f, true);
+        recent.put(1, "one");
+        recent.put(2, "two");
+        recent.put(3, "three");
+        recent.get(1);
+        System.out.println(recent.keySet());
+        recent.put(2, "TWO");
+        System.out.println(recent);
+        recent.containsKey(3);
+        System.out.println(recent.keySet());
+
+        // a copy keeps the order of the map it copies
+        TreeMap<String, Integer> sorted = new TreeMap<>();
+        sorted.put("b", 2);
+        sorted.put("a", 1);
+        LinkedHashMap<String, Integer> copy = new LinkedHashMap<>(sorted);
+        copy.put("0", 0);
+        System.out.println(copy);
+
+        map.clear();
+        System.out.println(map.isEmpty());
+        System.out.println(map);
+
+        try {
+            new LinkedHashMap<String, String>(-1);
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new LinkedHashMap<String, String>(4, -1.5f);
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
+
+class Key {
+    private final int id;
+
+    Key(int id) {
+        this.id = id;
+    }
+
+    @Override
+    public boolean equals(Object other) {
+        // the map only holds Keys and null, which equals is not called with
+        return ((Key) other).id == id;
+    }
+
+    @Override
+    public int hashCode() {
+        return id;
+    }
+}
@@ -0,0 +1,22 @@
+public class Literals {
+    static final String GREETING = "hello";
+    static String name = "world";
+
+    public static void main(String[] args) {
+        // equal string literals are the same object
+        String a = "hello";
+        String b = "hello";
+        System.out.println(a == b);
+        System.out.println(a == GREETING);
+        System.out.println(name == "world");
+        System.out.println(a == "world");
+        System.out.println(a != name);
+
+        // the literal is shared across classes
+        System.out.println(Other.WORLD == name);
+    }
+}
+
+class Other {
+    static String WORLD = "world";
+}
@@ -0,0 +1,59 @@
+public class Monitors {
+    public static int counter;
+
+    public static synchronized int increment() {
+        counter++;
+        return counter;
+    }
+
+    public static synchronized int incrementTwice() {
+        // re-entering a monitor that is held does not block
+        increment();
+        return increment();
+    }
+
+    public static synchronized void fail() {
+        throw new IllegalStateException("failed while synchronized");
+    }
+
+    public static void main(String[] args) {
+        System.out.println(increment());
+        System.out.println(incrementTwice());
+
+        StringBuilder lock = new StringBuilder();
+        synchronized (lock) {
+            synchronized (lock) {
+                lock.append("nested");
+            }
+            System.out.println(lock);
+        }
+
+        try {
+            synchronized (lock) {
+                throw new IllegalArgumentException("thrown inside");
+            }
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            fail();
+        } catch (IllegalStateException e) {
+            System.out.println(e.getMessage());
+        }
+        System.out.println(increment());
+
+        synchronized (Monitors.class) {
+            System.out.println(Monitors.class == Monitors.class);
+        }
+
+        StringBuilder missing = null;
+        try {
+            synchronized (missing) {
+                System.out.println("unreachable");
+            }
+        } catch (NullPointerException e) {
+            System.out.println("null monitor");
+        }
+    }
+}
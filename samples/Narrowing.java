@@ -0,0 +1,33 @@
+public class Narrowing {
+    public static byte toByte(int value) {
+        return (byte) value;
+    }
+
+    public static short toShort(int value) {
+        return (short) value;
+    }
+
+    public static char toChar(int value) {
+        return (char) value;
+    }
+
+    public static boolean isOdd(int value) {
+        return value % 2 != 0;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(toByte(300));
+        System.out.println(toByte(200));
+        System.out.println(toByte(-129));
+        System.out.println(toShort(40000));
+        System.out.println(toShort(-32769));
+        System.out.println((int) toChar(-1));
+        System.out.println(toChar(65 + 65536));
+        System.out.println(isOdd(7));
+        System.out.println(isOdd(-4));
+
+        // the narrowed value is what the caller computes with
+        int sum = toByte(300) + toShort(40000) + toChar(66);
+        System.out.println(sum);
+    }
+}
@@ -0,0 +1,21 @@
+import java.util.Optional;
+
+public class Optionals {
+    public static void main(String[] args) {
+        Optional<String> name = Optional.of("jajajvm");
+        System.out.println(name.isPresent());
+        System.out.println(name.isEmpty());
+        System.out.println(name.get());
+        String value = name.orElse("unknown");
+        System.out.println(value);
+
+        Optional<String> empty = Optional.empty();
+        System.out.println(empty.isPresent());
+        System.out.println(empty.isEmpty());
+        System.out.println(empty.orElse("unknown"));
+
+        System.out.println(Optional.ofNullable(null).isPresent());
+        System.out.println(Optional.ofNullable("smile").get());
+        System.out.println(Optional.ofNullable(null).orElse(null));
+    }
+}
@@ -0,0 +1,34 @@
+public class Overloads {
+    public static void main(String[] args) {
+        // the overload is chosen by the types of the arguments at compile time
+        System.out.println(scale(7));
+        System.out.println(scale(7, 3));
+        System.out.println(describe("seven", 7));
+        System.out.println(isEven(7));
+        System.out.println(initial("Overloads"));
+    }
+
+    static int scale(int value) {
+        return value * 2;
+    }
+
+    static int scale(int value, int factor) {
+        return value * factor;
+    }
+
+    static String describe(String name, int value) {
+        return new StringBuilder(name).append(" is ").append(value).toString();
+    }
+
+    static boolean isEven(int value) {
+        return value % 2 == 0;
+    }
+
+    static char initial(String name) {
+        return name.charAt(0);
+    }
+
+    static void greet(String name) {
+        System.out.println(new StringBuilder("Hello, ").append(name).toString());
+    }
+}
@@ -0,0 +1,52 @@
+import java.util.Random;
+
+public class Randoms {
+    public static void main(String[] args) {
+        // a seeded generator gives the same values as java's
+        Random random = new Random(42);
+        System.out.println(random.nextInt());
+        System.out.println(random.nextInt(10));
+        System.out.println(random.nextInt(16));
+        System.out.println(random.nextInt(1000000007));
+        System.out.println(random.nextLong());
+        System.out.println(random.nextDouble());
+        System.out.println(random.nextBoolean());
+        System.out.println(random.nextGaussian());
+        System.out.println(random.nextGaussian());
+        System.out.println(random.nextGaussian());
+
+        byte[] bytes = new byte[7];
+        random.nextBytes(bytes);
+        for (int i = 0; i < bytes.length; i++) {
+            System.out.println(bytes[i]);
+        }
+
+        // the same seed gives the same sequence
+        Random first = new Random(-7);
+        Random second = new Random(-7);
+        int same = 0;
+        for (int i = 0; i < 100; i++) {
+            if (first.nextInt(100) == second.nextInt(100)) {
+                same++;
+            }
+        }
+        System.out.println(same);
+
+        // an unseeded generator still respects the bound
+        Random unseeded = new Random();
+        boolean inBounds = true;
+        for (int i = 0; i < 100; i++) {
+            int value = unseeded.nextInt(6);
+            if (value < 0 || value >= 6) {
+                inBounds = false;
+            }
+        }
+        System.out.println(inBounds);
+
+        try {
+            random.nextInt(0);
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
@@ -0,0 +1,31 @@
+import java.lang.ref.WeakReference;
+
+public class References {
+    public static StringBuilder kept;
+
+    public static void main(String[] args) {
+        StringBuilder strong = new StringBuilder().append("strong");
+        WeakReference<StringBuilder> weak = new WeakReference<>(strong);
+        System.out.println(weak.get());
+        System.out.println(weak.refersTo(strong));
+        System.gc();
+        System.out.println(weak.get());
+
+        // once nothing else references the object it may be collected
+        strong = null;
+        System.gc();
+        System.out.println(weak.get());
+        System.out.println(weak.refersTo(null));
+
+        // a static field keeps its object alive
+        kept = new StringBuilder().append("kept");
+        WeakReference<StringBuilder> static_ = new WeakReference<>(kept);
+        System.gc();
+        System.out.println(static_.get());
+
+        WeakReference<StringBuilder> cleared = new WeakReference<>(kept);
+        cleared.clear();
+        System.out.println(cleared.get());
+        System.out.println(cleared.enqueue());
+    }
+}
@@ -0,0 +1,96 @@
+public class Reflection {
+    public static void main(String[] args) throws Exception {
+        Class<?> integer = Class.forName("java.lang.Integer");
+        System.out.println(integer.getName());
+        System.out.println(integer == Integer.class);
+
+        // loading a class of the program initializes it
+        System.out.println("before");
+        Class<?> plugin = Class.forName("Plugin");
+        System.out.println(plugin.getName());
+        System.out.println(plugin == Plugin.class);
+        Class.forName("Plugin");
+        System.out.println(Plugin.loaded);
+
+        // ldc pushes the class of a class literal
+        System.out.println(Tally.class.getName());
+        System.out.println(Tally.class == Tally.class);
+        System.out.println(int[].class.getName());
+        System.out.println(String[].class.getName());
+
+        try {
+            Class.forName("com.example.Missing");
+        } catch (ClassNotFoundException e) {
+            System.out.println(e.getMessage());
+        }
+
+        // newInstance runs the constructor taking no arguments
+        Tally tally = (Tally) Class.forName("Tally").newInstance();
+        tally.increment();
+        System.out.println(tally.count());
+        StringBuilder builder = StringBuilder.class.newInstance();
+        System.out.println(builder.append("built").toString());
+
+        try {
+            Figure.class.newInstance();
+        } catch (InstantiationException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            Task.class.newInstance();
+        } catch (InstantiationException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            Sized.class.newInstance();
+        } catch (InstantiationException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            Singleton.class.newInstance();
+        } catch (IllegalAccessException e) {
+            System.out.println("IllegalAccessException");
+        }
+    }
+}
+
+class Tally {
+    private int count;
+
+    Tally() {
+        count = 10;
+    }
+
+    void increment() {
+        count++;
+    }
+
+    int count() {
+        return count;
+    }
+}
+
+abstract class Figure {
+}
+
+interface Task {
+}
+
+class Sized {
+    Sized(int size) {
+    }
+}
+
+class Singleton {
+    private Singleton() {
+    }
+}
+
+class Plugin {
+    static int loaded = 0;
+
+    static {
+        loaded++;
+        System.out.println("Plugin initialized");
+    }
+}
@@ -0,0 +1,77 @@
+import java.util.regex.Matcher;
+import java.util.regex.Pattern;
+import java.util.regex.PatternSyntaxException;
+
+public class Regexes {
+    public static void main(String[] args) {
+        Pattern date = Pattern.compile("(\\d{4})-(\\d{2})-(\\d{2})");
+        Matcher m = date.matcher("from 2021-03-04 to 2022-12-31.");
+        while (m.find()) {
+            System.out.println(m.group());
+            System.out.println(m.group(1));
+            System.out.println(m.group(3));
+            System.out.println(m.start());
+            System.out.println(m.end());
+        }
+        System.out.println(m.groupCount());
+
+        // greedy and lazy repetitions
+        Matcher tags = Pattern.compile("<.+?>").matcher("<a><b></b></a>");
+        while (tags.find()) {
+            System.out.println(tags.group());
+        }
+        Matcher greedy = Pattern.compile("<.+>").matcher("<a><b></b></a>");
+        System.out.println(greedy.find());
+        System.out.println(greedy.group());
+
+        // alternation, classes and optional groups
+        Matcher words = Pattern.compile("\\b(cat|dog)(s)?\\b").matcher("cats and dogs, a dog, hotdogs");
+        while (words.find()) {
+            System.out.println(words.group());
+            System.out.println(words.group(2));
+        }
+        Matcher ids = Pattern.compile("[A-Za-z_][\\w]*").matcher("x1 = _tmp + 42 * y;");
+        while (ids.find()) {
+            System.out.println(ids.group());
+        }
+        Matcher negated = Pattern.compile("[^aeiou\\s]+").matcher("regular expressions");
+        while (negated.find()) {
+            System.out.println(negated.group());
+        }
+
+        // empty matches advance by one character
+        Matcher empty = Pattern.compile("a*").matcher("baab");
+        while (empty.find()) {
+            System.out.println(empty.start());
+            System.out.println(empty.end());
+        }
+
+        // whole-input matching
+        System.out.println(Pattern.matches("[0-9]+", "12345"));
+        System.out.println(Pattern.matches("[0-9]+", "123a45"));
+        System.out.println(Pattern.matches("(ab)+|c", "ababab"));
+        System.out.println(Pattern.matches("^a.c$", "abc"));
+        System.out.println(Pattern.matches("x{2,3}", "xxxx"));
+        System.out.println(Pattern.matches("x{2,}", "xxxx"));
+        System.out.println(Pattern.matches("(?:a|b)*c", "abbac"));
+        System.out.println(Pattern.matches("\\d+\\.\\d*", "3.14"));
+        System.out.println(date.pattern());
+
+        try {
+            m.group();
+        } catch (IllegalStateException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            Pattern.compile("(unclosed");
+        } catch (IllegalArgumentException e) {
+            System.out.println("invalid");
+        }
+        try {
+            // the minimum of a repetition may not exceed its maximum
+            Pattern.compile("a{2,1}");
+        } catch (PatternSyntaxException e) {
+            System.out.println("invalid range");
+        }
+    }
+}
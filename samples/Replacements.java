@@ -0,0 +1,38 @@
+public class Replacements {
+    public static void main(String[] args) {
+        System.out.println("banana".replace('a', 'o'));
+        System.out.println("banana".replace('x', 'o'));
+        System.out.println("banana".replace("an", "AN"));
+        System.out.println("a.b.c".replace(".", "::"));
+        System.out.println("abc".replace("", "-"));
+        System.out.println("aaaa".replace("aa", "b"));
+        System.out.println("banana".replace(new StringBuilder("na"), "NA"));
+
+        // replaceAll and replaceFirst take a regular expression
+        System.out.println("a.b.c".replaceAll(".", "x"));
+        System.out.println("a1b22c333".replaceAll("\\d+", "#"));
+        System.out.println("abc".replaceAll("x*", "-"));
+        System.out.println("a1b22c333".replaceAll("(\\d)(\\d*)", "<$2|$1>"));
+        System.out.println("a1b2".replaceAll("(\\d)", "$10"));
+        System.out.println("John Smith".replaceAll("(\\w+) (\\w+)", "$2, $1"));
+        System.out.println("cost".replaceAll("cost", "\\$5"));
+        System.out.println("aaa".replaceFirst("a", "b"));
+        System.out.println("no match".replaceAll("z", "$"));
+
+        tryReplacement("$");
+        tryReplacement("$x");
+        tryReplacement("\\");
+        tryReplacement("$9");
+        tryReplacement("${a}");
+    }
+
+    static void tryReplacement(String replacement) {
+        try {
+            System.out.println("abc".replaceAll("(b)", replacement));
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
@@ -1,6 +1,41 @@
 public class Return {
     public static void main(String[] args) {
         System.out.println(topla(11, 4));
+
+        // the returned value lands on top of what the caller already pushed
+        System.out.println(1 + topla(1, 2) * 2);
+        System.out.println(topla(topla(1, 1), topla(2, 2)) - topla(0, 1));
+
+        // a discarded value and a void call leave the caller's stack as it was
+        int before = 7;
+        topla(3, 4);
+        nothing(5);
+        System.out.println(before + topla(0, 0));
+
+        // arguments land in the locals in the order they were pushed
+        System.out.println(digits(1, 2, 3));
+        System.out.println(digits(topla(0, 1), 0, -7));
+        System.out.println(repeat("ja", 3));
+        System.out.println(repeat(repeat("ab", 2), 2));
+    }
+
+    public static int digits(int a, int b, int c) {
+        return a * 100 + b * 10 + c;
+    }
+
+    public static String repeat(String s, int times) {
+        StringBuilder builder = new StringBuilder();
+        for (int i = 0; i < times; i++) {
+            builder.append(s);
+        }
+        return builder.toString();
+    }
+
+    public static void nothing(int a) {
+        if (a > 0) {
+            return;
+        }
+        System.out.println(a);
     }
 
     public static int topla(int a, int b) {
@@ -0,0 +1,55 @@
+public class StackOverflow {
+    static int depth;
+
+    static int recurse(int n) {
+        depth = n;
+        return recurse(n + 1) + 1;
+    }
+
+    static int catchError() {
+        try {
+            return recurse(0);
+        } catch (StackOverflowError e) {
+            return -1;
+        }
+    }
+
+    static int catchThrowable() {
+        try {
+            return recurse(0);
+        } catch (Throwable e) {
+            return -2;
+        }
+    }
+
+    static int notCaught() {
+        try {
+            return 1;
+        } catch (StackOverflowError e) {
+            return -3;
+        }
+    }
+
+    public static void main(String[] args) {
+        System.out.println(catchError());
+        System.out.println(catchThrowable());
+        System.out.println(notCaught());
+        System.out.println(depth > 100);
+
+        try {
+            recurse(0);
+        } catch (Error e) {
+            System.out.println(e.getMessage());
+        }
+
+        try {
+            try {
+                recurse(0);
+            } finally {
+                System.out.println("finally");
+            }
+        } catch (StackOverflowError e) {
+            System.out.println("rethrown");
+        }
+    }
+}
@@ -0,0 +1,11 @@
+public class StandardStreams {
+    public static void main(String[] args) {
+        System.out.println("to stdout");
+        System.err.println("to stderr");
+        System.err.print(42);
+        System.err.println();
+        System.out.print('x');
+        System.out.println(1.5);
+        System.err.println(System.out == System.out);
+    }
+}
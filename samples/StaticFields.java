@@ -0,0 +1,121 @@
+public class StaticFields {
+    static int counter;
+    static int[] history = new int[3];
+
+    public static void main(String[] args) {
+        System.out.println(Config.LIMIT);
+        System.out.println(Config.NAME);
+        System.out.println(Config.scale(7));
+        System.out.println(Config.initialized);
+
+        for (int i = 0; i < 3; i++) {
+            counter += Config.LIMIT;
+            history[i] = counter;
+        }
+        System.out.println(counter);
+        System.out.println(history[2]);
+
+        Config.LIMIT = 5;
+        System.out.println(Config.scale(7));
+        System.out.println(Counter.next());
+        System.out.println(Counter.next());
+        System.out.println(Counter.count);
+
+        // initializing a class gives its long and double constants their ConstantValue
+        System.out.println(Limits.count);
+        System.out.println(Limits.BIG);
+        System.out.println(Limits.HALF);
+
+        // a field inherited from a superclass or an interface is the same field,
+        // and reading it through the subclass does not initialize the subclass
+        System.out.println(Child.shared);
+        Child.shared = 9;
+        System.out.println(Parent.shared);
+        System.out.println(Child.SIZES[1]);
+        System.out.println(Child.initialized);
+
+        // a static initializer which throws leaves its class unusable
+        try {
+            System.out.println(Broken.value);
+        } catch (ExceptionInInitializerError e) {
+            System.out.println(e.getMessage());
+            System.out.println(e.getCause().getMessage());
+        }
+        try {
+            System.out.println(Broken.value);
+        } catch (NoClassDefFoundError e) {
+            System.out.println(e.getMessage());
+        }
+        // errors are thrown as they are
+        try {
+            System.out.println(Fatal.value);
+        } catch (AssertionError e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
+
+class Config {
+    public static int LIMIT = compute();
+    public static final String NAME = "config";
+    public static boolean initialized;
+
+    static {
+        initialized = true;
+    }
+
+    static int compute() {
+        return 6 * 7;
+    }
+
+    static int scale(int value) {
+        return value * LIMIT;
+    }
+}
+
+class Counter {
+    static int count = 100;
+
+    static int next() {
+        count++;
+        return count;
+    }
+}
+
+class Limits {
+    static final long BIG = 1L << 40;
+    static final double HALF = 0.5;
+    static int count = 2;
+}
+
+interface Defaults {
+    int[] SIZES = {1, 2, 3};
+}
+
+class Parent {
+    static int shared = 5;
+}
+
+class Child extends Parent implements Defaults {
+    static boolean initialized = true;
+
+    static {
+        System.out.println("Child initialized");
+    }
+}
+
+class Broken {
+    static int value = divide(1, 0);
+
+    static int divide(int a, int b) {
+        return a / b;
+    }
+}
+
+class Fatal {
+    static int value = fail();
+
+    static int fail() {
+        throw new AssertionError("fatal");
+    }
+}
@@ -0,0 +1,43 @@
+import java.util.Arrays;
+import java.util.stream.IntStream;
+
+public class Streams {
+    public static void main(String[] args) {
+        System.out.println("jajajvm".chars().count());
+        System.out.println("".chars().count());
+
+        int[] chars = "jvm".chars().toArray();
+        System.out.println(chars.length);
+        for (int i = 0; i < chars.length; i++) {
+            System.out.println(chars[i]);
+        }
+
+        System.out.println(IntStream.of(42).count());
+        int[] values = IntStream.of(1, 2, 3, 4).toArray();
+        int sum = 0;
+        for (int i = 0; i < values.length; i++) {
+            sum += values[i];
+        }
+        System.out.println(sum);
+
+        int[] numbers = {7, -3, 12, 0, 5, 12, -8};
+        int total = 0;
+        for (int i = 0; i < numbers.length; i++) {
+            total += numbers[i];
+        }
+        System.out.println(Arrays.stream(numbers).sum() == total);
+        System.out.println(Arrays.stream(numbers).sum());
+        System.out.println(Arrays.stream(numbers).count());
+        System.out.println(Arrays.stream(numbers).min().getAsInt());
+        System.out.println(Arrays.stream(numbers).max().getAsInt());
+        System.out.println(Arrays.stream(numbers).average().getAsDouble());
+        System.out.println(IntStream.of(1, 2).average().getAsDouble());
+        System.out.println(IntStream.of(100000, 100000, 100000).average().getAsDouble());
+
+        int[] empty = {};
+        System.out.println(Arrays.stream(empty).sum());
+        System.out.println(Arrays.stream(empty).min().isPresent());
+        System.out.println(Arrays.stream(empty).max().orElse(-1));
+        System.out.println(Arrays.stream(empty).average().isPresent());
+    }
+}
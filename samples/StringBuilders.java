@@ -0,0 +1,51 @@
+public class StringBuilders {
+    public static void main(String[] args) {
+        StringBuilder builder = new StringBuilder();
+        builder.append("count: ").append(42).append(' ').append(true).append(2.5);
+        System.out.println(builder);
+        System.out.println(builder.length());
+        System.out.println(builder.indexOf("42"));
+        System.out.println(builder.indexOf("43"));
+
+        // replace every vowel in place
+        StringBuilder word = new StringBuilder("character");
+        for (int i = 0; i < word.length(); i++) {
+            char c = word.charAt(i);
+            if (c == 'a' || c == 'e') {
+                word.setCharAt(i, '*');
+            }
+        }
+        System.out.println(word);
+        word.deleteCharAt(0).deleteCharAt(word.length() - 1);
+        System.out.println(word);
+        word.insert(0, "[").insert(word.length(), ']').insert(1, 7);
+        System.out.println(word.toString());
+
+        StringBuilder text = new StringBuilder("racecar");
+        boolean palindrome = true;
+        for (int i = 0, j = text.length() - 1; i < j; i++, j--) {
+            if (text.charAt(i) != text.charAt(j)) {
+                palindrome = false;
+            }
+        }
+        System.out.println(palindrome);
+        System.out.println(new StringBuilder("stressed").reverse());
+        System.out.println(new StringBuilder(16).length());
+
+        try {
+            word.charAt(20);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            word.setCharAt(-1, 'x');
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            word.insert(99, "x");
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
@@ -0,0 +1,60 @@
+public class Strings {
+    public static void main(String[] args) {
+        String s = "hello";
+        System.out.println(s.length());
+        System.out.println(s.charAt(0));
+        System.out.println(s.charAt(4));
+        System.out.println(s.substring(1));
+        System.out.println(s.substring(1, 3));
+        System.out.println(s.substring(5).length());
+        System.out.println(s.indexOf('l'));
+        System.out.println(s.indexOf("lo"));
+        System.out.println(s.indexOf('l', 3));
+        System.out.println(s.indexOf("z"));
+
+        // a character outside ASCII is still one code unit
+        String accented = "caf\u00e9!";
+        System.out.println(accented.length());
+        System.out.println((int) accented.charAt(3));
+        System.out.println(accented.indexOf('!'));
+        System.out.println(accented.indexOf(0xE9));
+        System.out.println(accented.substring(3).length());
+
+        // a search starting outside the string does not throw
+        System.out.println(s.indexOf("l", -5));
+        System.out.println(s.indexOf("l", 10));
+        System.out.println(s.indexOf("", 10));
+
+        // bad indexes throw, with the messages of java
+        try {
+            s.charAt(-1);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            s.charAt(5);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            s.substring(2, 6);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            s.substring(3, 2);
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            s.substring(-1);
+        } catch (RuntimeException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            new StringBuilder(s).charAt(7);
+        } catch (StringIndexOutOfBoundsException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
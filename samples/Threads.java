@@ -0,0 +1,33 @@
+import java.util.concurrent.atomic.AtomicInteger;
+
+public class Threads {
+    public static void main(String[] args) throws InterruptedException {
+        AtomicInteger counter = new AtomicInteger();
+        Thread first = new Thread(() -> counter.addAndGet(10));
+        Thread second = new Thread(() -> counter.addAndGet(5), "adder");
+        System.out.println(first.getName());
+        System.out.println(second.getName());
+        first.start();
+        second.start();
+        first.join();
+        second.join();
+        System.out.println(counter.get());
+        System.out.println(first.isAlive());
+
+        // run executes the target on the calling thread
+        Thread direct = new Thread(() -> System.out.println("run directly"));
+        direct.run();
+        direct.setName("renamed");
+        System.out.println(direct.getName());
+
+        try {
+            first.start();
+        } catch (IllegalThreadStateException e) {
+            System.out.println("started twice");
+        }
+
+        Thread.sleep(1);
+        System.out.println(new Thread("named").getName());
+        System.out.println(new Thread(() -> {}).getName());
+    }
+}
@@ -0,0 +1,29 @@
+public class Transcendentals {
+    public static void main(String[] args) {
+        // the constants are inlined by javac
+        System.out.println(Math.PI);
+        System.out.println(Math.E);
+
+        System.out.println(Math.log(Math.E));
+        System.out.println(Math.log(10.0));
+        System.out.println(Math.log10(1000.0));
+        System.out.println(Math.log10(2.0));
+        System.out.println(Math.exp(1.0));
+        System.out.println(Math.exp(-2.5));
+        System.out.println(Math.sin(Math.PI / 6));
+        System.out.println(Math.cos(Math.PI / 3));
+        System.out.println(Math.tan(Math.PI / 4));
+        System.out.println(Math.sin(0.0));
+        System.out.println(Math.cos(0.0));
+        System.out.println(Math.atan2(1.0, 1.0));
+        System.out.println(Math.atan2(-1.0, -1.0));
+        System.out.println(Math.atan2(0.0, -2.0));
+
+        // the logarithm of 0 is -Infinity and that of a negative number NaN
+        System.out.println(Math.log(0.0));
+        System.out.println(Math.log(-1.0));
+        System.out.println(Math.log10(0.0));
+        System.out.println(Math.exp(1000.0));
+        System.out.println(Math.exp(-1000.0));
+    }
+}
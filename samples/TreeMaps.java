@@ -0,0 +1,72 @@
+import java.util.Map;
+import java.util.NoSuchElementException;
+import java.util.SortedMap;
+import java.util.TreeMap;
+
+public class TreeMaps {
+    public static void main(String[] args) {
+        TreeMap<String, Integer> map = new TreeMap<>();
+        map.put("pear", 3);
+        map.put("apple", 1);
+        map.put("fig", 7);
+        map.put("banana", 2);
+        System.out.println(map);
+        System.out.println(map.put("fig", 4));
+        System.out.println(map.put("cherry", 5));
+        System.out.println(map.size());
+        System.out.println(map.get("fig"));
+        System.out.println(map.get("kiwi"));
+        System.out.println(map.containsKey("apple"));
+        System.out.println(map.firstKey());
+        System.out.println(map.lastKey());
+        System.out.println(map.headMap("cherry"));
+        System.out.println(map.tailMap("cherry"));
+        System.out.println(map.subMap("b", "g"));
+        System.out.println(map.subMap("b", "g").size());
+        System.out.println(map.remove("apple"));
+        System.out.println(map.remove("apple"));
+        System.out.println(map.keySet());
+        System.out.println(map.values());
+        System.out.println(map.entrySet());
+
+        int total = 0;
+        for (Map.Entry<String, Integer> entry : map.entrySet()) {
+            System.out.println(entry.getKey());
+            total += entry.getValue();
+        }
+        System.out.println(total);
+
+        // descending order through a comparator
+        Map<Integer, String> descending = new TreeMap<>((a, b) -> b - a);
+        descending.put(1, "one");
+        descending.put(10, "ten");
+        descending.put(5, "five");
+        System.out.println(descending);
+        for (Integer key : descending.keySet()) {
+            System.out.println(key.intValue() * 2);
+        }
+
+        SortedMap<Integer, String> empty = new TreeMap<>();
+        System.out.println(empty.isEmpty());
+        try {
+            empty.get(null);
+        } catch (NullPointerException e) {
+            System.out.println("null key");
+        }
+        try {
+            empty.firstKey();
+        } catch (NoSuchElementException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            map.subMap("z", "a");
+        } catch (IllegalArgumentException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            map.put(null, 0);
+        } catch (NullPointerException e) {
+            System.out.println("null key");
+        }
+    }
+}
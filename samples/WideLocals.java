@@ -0,0 +1,47 @@
+public class WideLocals {
+    public static void main(String[] args) {
+        // longs and doubles take two local slots, floats one
+        long big = 1L << 40;
+        double half = 0.5;
+        float third = 0.33f;
+        long copy = big;
+        for (int i = 0; i < 3; i++) {
+            double scaled = half;
+            copy = big;
+            if (i == 0) {
+                System.out.println(scaled);
+            } else if (i == 1) {
+                System.out.println(copy);
+            } else {
+                System.out.println(third);
+            }
+        }
+
+        // the slots of a long are reused by an int once its scope ends
+        try {
+            long inner = copy;
+            System.out.println(inner);
+            throw new RuntimeException();
+        } catch (RuntimeException e) {
+            int reused = 7;
+            System.out.println(reused);
+        }
+
+        // an int on each side of a long keeps its own slot
+        int before = 1;
+        long between = big;
+        int after = 2;
+        System.out.println(before + after);
+        System.out.println(between);
+        show(big, half, 9);
+    }
+
+    // the parameters fill the first locals, two slots for a long or double
+    static void show(long l, double d, int i) {
+        long copied = l;
+        double alsoCopied = d;
+        System.out.println(copied);
+        System.out.println(alsoCopied);
+        System.out.println(i);
+    }
+}
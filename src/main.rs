@@ -1,6 +1,10 @@
 #![allow(non_upper_case_globals)]
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 pub struct ClassHeader {
     pub magic: u32,
@@ -12,6 +16,14 @@ pub struct ClassInfo {
     pub access_flags: u16,
     pub this_calss: u16,
     pub super_class: u16,
+    pub interfaces: Vec<u16>,
+}
+
+pub struct FieldInfo {
+    pub access_flags: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes_count: u16,
 }
 
 pub struct MethodInfo {
@@ -26,48 +38,1185 @@ pub struct AttributeInfo {
     pub attribute_length: u32,
 }
 
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Code {
     pub max_stack: u16,
     pub max_locals: u16,
     pub code_length: u32,
     pub code: Vec<u8>,
+    pub exception_table: Vec<ExceptionHandler>,
+    /// The offsets of the frames of the StackMapTable attribute, `None` if there is none
+    pub stack_map_table: Option<Vec<usize>>,
+}
+
+impl Code {
+    /// List the raw bytes of the code in hex, 16 per line after their offset,
+    /// e.g. "0000: 03 3c 1b 05 a2 00 0a".
+    pub fn hex_dump(&self) -> String {
+        self.code
+            .chunks(16)
+            .enumerate()
+            .map(|(line, bytes)| {
+                let bytes: Vec<_> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("{:04x}: {}\n", line * 16, bytes.join(" "))
+            })
+            .collect()
+    }
+}
+
+/// An entry of the exception table of a method.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExceptionHandler {
+    /// The range of instructions covered by the handler, `end_pc` is exclusive
+    pub start_pc: u16,
+    pub end_pc: u16,
+    /// The start of the handler
+    pub handler_pc: u16,
+    /// The constant pool index of the caught class, 0 catches everything
+    pub catch_type: u16,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Field {
+    pub access_flags: u16,
+    pub name: String,
+    pub descriptor: String,
+    /// The constant pool index of the initial value of a static field, from its ConstantValue
+    pub constant_value: Option<u16>,
 }
 
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Method {
+    pub access_flags: u16,
     pub name: String,
     pub descriptor: String,
-    pub code: Code,
+    /// The bytecode of the method, `None` for abstract and native methods
+    pub code: Option<Code>,
+}
+
+impl Method {
+    /// Get the number of local variables of the method, 0 if it has no code.
+    pub fn max_locals(&self) -> usize {
+        self.code
+            .as_ref()
+            .map_or(0, |code| code.max_locals as usize)
+    }
 }
 
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConstantPool {
     Utf8(String), // 1
     Integer {
         bytes: i32,
     }, // 3
+    Float {
+        bytes: u32,
+    }, // 4
+    Long {
+        high_bytes: u32,
+        low_bytes: u32,
+    }, // 5
+    Double {
+        high_bytes: u32,
+        low_bytes: u32,
+    }, // 6
+    /// The unusable entry following a Long or Double, which take two entries
+    Unusable,
     Class {
         starting_index: u16,
     }, // 7
+    String {
+        string_index: u16,
+    }, // 8
     MethodOrFieldRef {
+        /// 9 for a Fieldref, 10 for a Methodref, 11 for an InterfaceMethodref
+        tag: u8,
         class_index: u16,
         name_and_type_index: u16,
-    }, // 9 || 10
+    }, // 9 || 10 || 11
     NameAndType {
         name_index: u16,
         descriptor_index: u16,
     }, // 12
+    MethodHandle {
+        reference_kind: u8,
+        reference_index: u16,
+    }, // 15
+    MethodType {
+        descriptor_index: u16,
+    }, // 16
+    InvokeDynamic {
+        bootstrap_method_attr_index: u16,
+        name_and_type_index: u16,
+    }, // 18
+}
+
+/// An entry of the BootstrapMethods attribute, used by invokedynamic.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BootstrapMethod {
+    /// The constant pool index of the MethodHandle to the bootstrap method
+    pub method_ref: u16,
+    /// The constant pool indexes of the static arguments
+    pub arguments: Vec<u16>,
 }
 
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassFile {
+    /// The version of the class file format, e.g. 52 for Java 8
+    pub major_version: u16,
+    pub access_flags: u16,
     pub constant_pool: Vec<ConstantPool>,
+    /// The constant pool index of the class itself
+    pub this_class: u16,
+    /// The constant pool index of the superclass, 0 for `java/lang/Object` itself
+    pub super_class: u16,
+    /// The constant pool indexes of the interfaces the class implements,
+    /// or of those an interface extends
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<Field>,
     pub method: Vec<Method>,
+    pub bootstrap_methods: Vec<BootstrapMethod>,
+}
+
+impl ClassFile {
+    /// Get the name the class file declares for its class, which `get_class` checks resolves.
+    ///
+    /// # Return
+    /// the internal name of the class, e.g. "com/example/Main"
+    pub fn name(&self) -> &str {
+        self.class_name(self.this_class)
+            .expect("The name of a parsed class resolves")
+    }
+
+    /// Whether the class file declares an interface rather than a class.
+    pub fn is_interface(&self) -> bool {
+        self.access_flags & ACC_INTERFACE != 0
+    }
+
+    /// Iterate over the internal names of the interfaces of the class, see `interfaces`.
+    pub fn interface_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.interfaces.iter().map(move |&index| {
+            self.class_name(index)
+                .expect("The interfaces of a parsed class resolve")
+        })
+    }
+
+    /// Iterate over the constant pool with the 1-based index of every entry.
+    /// The unusable entries following Long and Double constants are skipped.
+    pub fn constant_pool_iter(&self) -> impl Iterator<Item = (u16, &ConstantPool)> + '_ {
+        self.constant_pool
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !matches!(entry, ConstantPool::Unusable))
+            .map(|(index, entry)| (index as u16 + 1, entry))
+    }
+
+    /// Resolve the name of a Class constant.
+    ///
+    /// # Parameters
+    /// * `index` - the constant pool index of the class
+    /// # Return
+    /// the internal name of the class, e.g. "java/lang/Object";
+    /// `None` if an index is out of range or refers to the wrong kind of constant
+    pub fn class_name(&self, index: u16) -> Option<&str> {
+        let constant = |index: u16| self.constant_pool.get((index as usize).checked_sub(1)?);
+        match *constant(index)? {
+            ConstantPool::Class { starting_index } => match constant(starting_index)? {
+                ConstantPool::Utf8(s) => Some(s.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// List the classes the class refers to, e.g. to load its dependencies ahead of time.
+    /// An array class is listed as the class of its elements, arrays of primitives are left out.
+    ///
+    /// # Return
+    /// the internal names of the classes, once each in constant pool order,
+    /// without the class itself
+    pub fn referenced_classes(&self) -> Vec<String> {
+        let own_name = Some(self.name());
+        let mut seen = HashSet::new();
+        self.constant_pool_iter()
+            .filter(|(_, entry)| matches!(entry, ConstantPool::Class { .. }))
+            .filter_map(|(index, _)| self.class_name(index))
+            .filter_map(|name| {
+                let element = name.trim_start_matches('[');
+                if element.len() == name.len() {
+                    Some(name)
+                } else {
+                    element.strip_prefix('L')?.strip_suffix(';')
+                }
+            })
+            .filter(|&name| Some(name) != own_name && seen.insert(name))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Resolve the names of a Methodref, InterfaceMethodref or Fieldref constant.
+    ///
+    /// # Parameters
+    /// * `index` - the constant pool index of the reference
+    /// # Return
+    /// the class, name and descriptor of the member;
+    /// `None` if an index is out of range or refers to the wrong kind of constant
+    pub fn resolve_method_ref(&self, index: u16) -> Option<ResolvedRef<'_>> {
+        let constant = |index: u16| self.constant_pool.get((index as usize).checked_sub(1)?);
+        let utf8 = |index: u16| match constant(index)? {
+            ConstantPool::Utf8(s) => Some(s.as_str()),
+            _ => None,
+        };
+        let (class_index, name_and_type_index) = match *constant(index)? {
+            ConstantPool::MethodOrFieldRef {
+                class_index,
+                name_and_type_index,
+                ..
+            } => (class_index, name_and_type_index),
+            _ => return None,
+        };
+        let class = self.class_name(class_index)?;
+        match *constant(name_and_type_index)? {
+            ConstantPool::NameAndType {
+                name_index,
+                descriptor_index,
+            } => Some(ResolvedRef {
+                class,
+                name: utf8(name_index)?,
+                descriptor: utf8(descriptor_index)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A method or field reference of the constant pool with its names looked up.
+#[derive(Debug, PartialEq)]
+pub struct ResolvedRef<'a> {
+    /// The internal name of the class declaring the member, e.g. "java/lang/Character"
+    pub class: &'a str,
+    /// The name of the member, e.g. "isDigit"
+    pub name: &'a str,
+    /// The descriptor of the member, e.g. "(C)Z"
+    pub descriptor: &'a str,
+}
+
+/// Rewrites of parsed class files, applied before they are loaded.
+pub struct ClassFileTransformer;
+
+impl ClassFileTransformer {
+    /// Rename the classes a class file refers to, e.g. to shade a dependency.
+    /// The names of Class constants and the class names inside descriptors are rewritten,
+    /// while other Utf8 constants such as string literals are left unchanged:
+    /// a Utf8 constant shared by both is copied to the end of the constant pool.
+    ///
+    /// # Parameters
+    /// * `class` - the class file to rewrite
+    /// * `mapping` - the old and new names of classes or packages, e.g. "com/example" to
+    ///   "shaded/example", which renames every class under com/example; dots are accepted too
+    pub fn remap_class_names(class: &mut ClassFile, mapping: &HashMap<String, String>) {
+        let mapping: Vec<_> = mapping
+            .iter()
+            .map(|(from, to)| (from.replace('.', "/"), to.replace('.', "/")))
+            .collect();
+        let remap_name = |name: &str| -> Option<String> {
+            mapping
+                .iter()
+                .find_map(|(from, to)| match name.strip_prefix(from.as_str()) {
+                    Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                        Some(format!("{}{}", to, rest))
+                    }
+                    _ => None,
+                })
+        };
+        // rewrite the names between 'L' and ';', e.g. "(Lcom/example/A;[I)V"
+        let remap_descriptor = |descriptor: &str| -> Option<String> {
+            let mut remapped = String::new();
+            let mut rest = descriptor;
+            let mut changed = false;
+            while let Some(start) = rest.find('L') {
+                let end = start + rest[start..].find(';')?;
+                remapped.push_str(&rest[..=start]);
+                match remap_name(&rest[start + 1..end]) {
+                    Some(name) => {
+                        remapped.push_str(&name);
+                        changed = true;
+                    }
+                    None => remapped.push_str(&rest[start + 1..end]),
+                }
+                rest = &rest[end..];
+            }
+            remapped.push_str(rest);
+            Some(remapped).filter(|_| changed)
+        };
+
+        // the Utf8 constants holding class names and descriptors, and those holding anything else
+        let mut names = HashMap::new();
+        let mut others = HashSet::new();
+        for entry in &class.constant_pool {
+            match *entry {
+                // array classes are named by their descriptor, e.g. "[Lcom/example/A;"
+                ConstantPool::Class { starting_index } => {
+                    names.insert(starting_index, false);
+                }
+                ConstantPool::NameAndType {
+                    name_index,
+                    descriptor_index,
+                } => {
+                    others.insert(name_index);
+                    names.insert(descriptor_index, true);
+                }
+                ConstantPool::MethodType { descriptor_index } => {
+                    names.insert(descriptor_index, true);
+                }
+                ConstantPool::String { string_index } => {
+                    others.insert(string_index);
+                }
+                _ => {}
+            }
+        }
+
+        let mut moved = HashMap::new();
+        for (index, is_descriptor) in names {
+            let old = match class.constant_pool.get((index as usize).wrapping_sub(1)) {
+                Some(ConstantPool::Utf8(old)) => old,
+                _ => continue,
+            };
+            let new = if is_descriptor || old.starts_with('[') {
+                remap_descriptor(old)
+            } else {
+                remap_name(old)
+            };
+            if let Some(new) = new {
+                if others.contains(&index) {
+                    class.constant_pool.push(ConstantPool::Utf8(new));
+                    moved.insert(index, class.constant_pool.len() as u16);
+                } else {
+                    class.constant_pool[index as usize - 1] = ConstantPool::Utf8(new);
+                }
+            }
+        }
+        for entry in &mut class.constant_pool {
+            match entry {
+                ConstantPool::Class {
+                    starting_index: index,
+                }
+                | ConstantPool::NameAndType {
+                    descriptor_index: index,
+                    ..
+                }
+                | ConstantPool::MethodType {
+                    descriptor_index: index,
+                } => {
+                    if let Some(new) = moved.get(index) {
+                        *index = *new;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // the descriptors of the members were looked up while parsing
+        for field in &mut class.fields {
+            if let Some(descriptor) = remap_descriptor(&field.descriptor) {
+                field.descriptor = descriptor;
+            }
+        }
+        for method in &mut class.method {
+            if let Some(descriptor) = remap_descriptor(&method.descriptor) {
+                method.descriptor = descriptor;
+            }
+        }
+    }
+}
+
+/// Errors found while parsing a class file.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file could not be read, most likely because it ended too early
+    Io(std::io::Error),
+    /// The file does not start with 0xCAFEBABE
+    BadMagic(u32),
+    /// A constant pool entry has an unknown tag
+    UnsupportedTag(u8),
+    /// A constant pool index is out of range or refers to the wrong kind of constant
+    BadConstant(u16),
+    /// A Utf8 constant is not valid UTF-8
+    InvalidUtf8,
+    /// The method has no Code attribute
+    MissingCode(String),
+    /// The method has more than one Code attribute
+    DuplicateCode(String),
+    /// The class uses a feature this VM does not support
+    Unsupported(&'static str),
+    /// A frame of a StackMapTable attribute has a reserved type
+    InvalidStackMapFrame(u8),
+    /// A field or method descriptor is malformed, e.g. "(L)I"
+    InvalidDescriptor(String),
+    /// Another error, with the offset in the class file of the bytes it was found in
+    At {
+        offset: usize,
+        error: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Record where in the class file the error was found, unless that is already known.
+    ///
+    /// # Parameters
+    /// * `offset` - the offset in the class file of the bytes the error was found in
+    fn at(self, offset: usize) -> ParseError {
+        match self {
+            ParseError::At { .. } => self,
+            error => ParseError::At {
+                offset,
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(error: std::io::Error) -> ParseError {
+        ParseError::Io(error)
+    }
+}
+
+/// Errors that abort the execution of a program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeError {
+    /// The program was still running when its deadline passed
+    Timeout,
+    /// The method ran `breakpoint`, `impdep1` or `impdep2`, which are reserved for
+    /// debuggers and JVM implementations and never appear in valid class files
+    ReservedOpcode { opcode: u8, pc: usize },
+    /// The method ran past the end of its code without returning or throwing.
+    /// `last_pc` is the instruction run last, 0 if the code is empty.
+    FellOffMethodEnd { last_pc: usize },
+    /// The program waits for something no other thread can do, e.g. `await` on a
+    /// `CountDownLatch` whose count is not zero once the started threads have finished
+    DeadlockDetected,
+    /// A Java exception was thrown, e.g. "java/util/NoSuchElementException".
+    /// `cause` is the exception which caused it, e.g. the one a static initializer
+    /// threw for an `ExceptionInInitializerError`.
+    Exception {
+        class: String,
+        message: Option<String>,
+        cause: Option<Box<RuntimeError>>,
+    },
+}
+
+impl RuntimeError {
+    /// Create the error of a thrown Java exception.
+    ///
+    /// # Parameters
+    /// * `class` - the internal name of the exception class
+    /// * `message` - the detail message of the exception
+    pub fn exception(class: &str, message: &str) -> RuntimeError {
+        RuntimeError::Exception {
+            class: class.to_string(),
+            message: Some(message.to_string()),
+            cause: None,
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    /// Format the error like java reports it, e.g.
+    /// "java.lang.ArithmeticException: / by zero" for an exception
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::Timeout => write!(f, "Execution timed out"),
+            RuntimeError::ReservedOpcode { opcode, pc } => {
+                write!(f, "Reserved opcode {} at pc {}", opcode, pc)
+            }
+            RuntimeError::FellOffMethodEnd { last_pc } => {
+                write!(f, "Fell off the end of the method after pc {}", last_pc)
+            }
+            RuntimeError::DeadlockDetected => write!(f, "Deadlock detected"),
+            RuntimeError::Exception { class, message, .. } => {
+                write!(f, "{}", class.replace('/', "."))?;
+                match message {
+                    Some(message) => write!(f, ": {}", message),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// How a method called from outside the program finished.
+#[derive(Debug, PartialEq)]
+pub enum ExecutionResult {
+    /// The method returned an int or a reference
+    Value(i32),
+    /// The method returned void
+    Void,
+    /// The method threw an exception it did not catch, e.g. "java/lang/ArithmeticException"
+    Thrown {
+        class: String,
+        message: Option<String>,
+        cause: Option<Box<RuntimeError>>,
+    },
+}
+
+impl ExecutionResult {
+    /// Tell apart how a method finished from what `execute` returned.
+    ///
+    /// # Return
+    /// how the method finished, or the error that aborted it without a Java exception
+    pub fn from_execution(
+        result: Result<Option<i32>, RuntimeError>,
+    ) -> Result<ExecutionResult, RuntimeError> {
+        match result {
+            Ok(Some(value)) => Ok(ExecutionResult::Value(value)),
+            Ok(None) => Ok(ExecutionResult::Void),
+            Err(RuntimeError::Exception {
+                class,
+                message,
+                cause,
+            }) => Ok(ExecutionResult::Thrown {
+                class,
+                message,
+                cause,
+            }),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// An object allocated by the running program.
+pub enum HeapObject {
+    String(String),
+    /// A `java.lang.StringBuilder`, as characters so that they can be changed in place
+    StringBuilder(Vec<char>),
+    IntArray(Vec<i32>),
+    ByteArray(Vec<i8>),
+    /// An array of references, e.g. the `String[]` passed to `main`
+    ReferenceArray(Vec<i32>),
+    IntStream(Vec<i32>),
+    Optional(Option<i32>),
+    OptionalInt(Option<i32>),
+    OptionalDouble(Option<f64>),
+    AtomicInteger(i32),
+    AtomicLong(i64),
+    AtomicBoolean(bool),
+    /// A boxed `java.lang.Integer`
+    Integer(i32),
+    /// A `java.lang.Thread`
+    Thread {
+        /// The `Runnable` run by the thread, null if there is none
+        target: i32,
+        name: String,
+        /// Whether `start` was called, a thread may only be started once
+        started: bool,
+        /// Whether the thread was started and has not ended yet, only ever so in threaded mode
+        alive: bool,
+    },
+    /// A `java.util.zip.CRC32` with the checksum of the bytes so far
+    Crc32(u32),
+    /// A `java.util.zip.Adler32` with the checksum of the bytes so far
+    Adler32(u32),
+    /// A `java.util.Base64.Encoder`
+    Base64Encoder {
+        /// Whether `-` and `_` replace `+` and `/`
+        url: bool,
+        /// Whether lines are broken after 76 characters
+        mime: bool,
+        /// Whether the output is padded with `=` to a multiple of 4 characters
+        padding: bool,
+    },
+    /// A `java.util.Base64.Decoder`
+    Base64Decoder {
+        /// Whether `-` and `_` replace `+` and `/`
+        url: bool,
+        /// Whether characters outside the alphabet are skipped
+        mime: bool,
+    },
+    /// The `java.io.InputStream` of `System.in`, reading from `Vm::input`
+    SystemIn,
+    /// The `java.io.PrintStream` of `System.out`, writing to `Vm::output`,
+    /// or of `System.err` if `error`, writing to `Vm::error`
+    PrintStream {
+        error: bool,
+    },
+    /// A `java.util.concurrent.CountDownLatch` with its count
+    CountDownLatch(i64),
+    /// A `java.lang.ref.WeakReference` to an object, null once the object was collected.
+    /// The referent is not followed by the garbage collector.
+    WeakReference(i32),
+    BigInteger(BigInteger),
+    BigDecimal(BigDecimal),
+    RoundingMode(RoundingMode),
+    LocalDate {
+        year: i32,
+        month: u8,
+        day: u8,
+    },
+    /// A `java.util.Date`, as milliseconds since 1970-01-01T00:00:00Z
+    Date(i64),
+    /// A `java.util.Random`, with the 48 bits of the state of its linear congruential generator
+    Random {
+        seed: i64,
+        /// The second of the pair of values computed by `nextGaussian`, until it is returned
+        next_next_gaussian: Option<f64>,
+    },
+    Pattern(Regex),
+    /// The state of matching a `Pattern` against an input
+    Matcher {
+        regex: Regex,
+        input: Vec<char>,
+        /// The position the next `find` starts at
+        search_from: usize,
+        /// The groups of the last match, `None` if the last attempt failed
+        groups: Option<RegexGroups>,
+    },
+    /// A `java.util.TreeMap`
+    TreeMap {
+        /// The keys and values, sorted by key
+        entries: Vec<(i32, i32)>,
+        /// The `Comparator` ordering the keys, null for their natural ordering
+        comparator: i32,
+    },
+    /// A `java.util.LinkedHashMap`
+    LinkedHashMap {
+        /// The keys and values, in insertion order, or from the least to the most
+        /// recently accessed in access order
+        entries: Vec<(i32, i32)>,
+        /// Whether `get` and `put` move the entry to the end, e.g. for an LRU cache
+        access_order: bool,
+    },
+    /// A `java.util.Map.Entry` of a map
+    MapEntry {
+        key: i32,
+        value: i32,
+    },
+    /// A copy of the elements of a collection, e.g. returned by `Map.entrySet`
+    Collection(Vec<i32>),
+    /// An `java.util.Iterator` over a copy of the elements of a collection
+    Iterator {
+        elements: Vec<i32>,
+        next: usize,
+    },
+    /// The `java.lang.Class` object of a class, e.g. pushed by `ldc Foo.class`
+    Class(String),
+    /// An instance of a class of the program
+    Object {
+        class_name: String,
+        /// The values of the instance fields, including those of the superclasses, by name.
+        /// FIXME: a field hiding a field of a superclass shares its value
+        fields: HashMap<String, Vec<i32>>,
+    },
+    /// A thrown exception or error
+    Throwable {
+        class_name: String,
+        message: Option<String>,
+        cause: Option<Box<RuntimeError>>,
+    },
+    /// A lambda implementing a functional interface
+    Lambda {
+        /// The class, name and descriptor of the method implementing the lambda
+        class_name: String,
+        name: String,
+        descriptor: String,
+        /// The slots of the values captured when the lambda was created
+        captured: Vec<i32>,
+    },
+}
+
+impl HeapObject {
+    /// Get the values of the object that may be references to other objects.
+    /// Slots are not typed, so e.g. the captured values of a lambda are all included
+    /// and the collector treats them as references if they look like one.
+    fn references(&self) -> Vec<i32> {
+        match self {
+            HeapObject::Optional(Some(value)) => vec![*value],
+            HeapObject::TreeMap {
+                entries,
+                comparator,
+            } => entries
+                .iter()
+                .flat_map(|&(key, value)| [key, value])
+                .chain(std::iter::once(*comparator))
+                .collect(),
+            HeapObject::LinkedHashMap { entries, .. } => entries
+                .iter()
+                .flat_map(|&(key, value)| [key, value])
+                .collect(),
+            HeapObject::MapEntry { key, value } => vec![*key, *value],
+            HeapObject::Thread { target, .. } => vec![*target],
+            HeapObject::Collection(elements)
+            | HeapObject::Iterator { elements, .. }
+            | HeapObject::ReferenceArray(elements) => elements.clone(),
+            HeapObject::Lambda { captured, .. } => captured.clone(),
+            HeapObject::Object { fields, .. } => fields.values().flatten().copied().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Estimate the number of bytes the object takes, counting the contents it owns.
+    fn size(&self) -> usize {
+        let contents = match self {
+            HeapObject::String(s) => s.len(),
+            HeapObject::StringBuilder(chars) => chars.len() * std::mem::size_of::<char>(),
+            HeapObject::IntArray(values) | HeapObject::IntStream(values) => values.len() * 4,
+            HeapObject::ByteArray(values) => values.len(),
+            HeapObject::TreeMap { entries, .. } | HeapObject::LinkedHashMap { entries, .. } => {
+                entries.len() * 8
+            }
+            HeapObject::Collection(elements)
+            | HeapObject::Iterator { elements, .. }
+            | HeapObject::ReferenceArray(elements) => elements.len() * 4,
+            HeapObject::Lambda { captured, .. } => captured.len() * 4,
+            HeapObject::Object { fields, .. } => fields.values().map(|value| value.len() * 4).sum(),
+            _ => 0,
+        };
+        std::mem::size_of::<HeapObject>() + contents
+    }
+}
+
+/// Errors found while verifying the bytecode of a method.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The instruction at `pc` runs past the end of the code
+    TruncatedInstruction { method: String, pc: usize },
+    /// The branch at `pc` jumps to `target`, which is not the start of an instruction
+    InvalidBranchTarget {
+        method: String,
+        pc: usize,
+        target: isize,
+    },
+    /// The entry at `index` of the exception table does not cover a range of whole
+    /// instructions, or its handler is not the start of an instruction
+    InvalidExceptionHandler {
+        method: String,
+        index: usize,
+        start_pc: u16,
+        end_pc: u16,
+        handler_pc: u16,
+    },
+    /// The method branches or handles exceptions, but has no StackMapTable
+    /// although its class file version requires one
+    MissingStackMapTable { method: String },
+    /// A frame of the StackMapTable is at `offset`, which is not the start of an instruction
+    InvalidStackMapOffset { method: String, offset: usize },
+    /// The instruction at `pc` reads local `index`, which does not hold a value of the size
+    /// it reads, e.g. a long whose second slot was overwritten by an int
+    InvalidLocalRead {
+        method: String,
+        pc: usize,
+        index: usize,
+    },
+}
+
+/// The state of a running program, shared by all of its frames.
+pub struct Vm {
+    /// The wall-clock time after which execution is aborted
+    pub deadline: Option<Instant>,
+    /// The number of instructions executed so far
+    pub instruction_count: u64,
+    /// How many times each opcode was executed, only recorded if `Some`
+    pub opcode_counts: Option<HashMap<u8, u64>>,
+    /// The constant pool indexes the executed instructions referred to, keyed by
+    /// the internal name of their class, only recorded if `Some`
+    pub touched_constants: Option<HashMap<String, HashSet<u16>>>,
+    /// The live objects of the program, moved to the front by each garbage collection
+    pub heap: Vec<HeapObject>,
+    /// The reference of each object in `heap`
+    pub heap_references: Vec<i32>,
+    /// The index in `heap` of the object of each reference, `FREE_HANDLE` if unused.
+    /// A reference is an index of this table plus one, so 0 is null.
+    /// Slots are not typed, so the collector cannot tell which slots hold references
+    /// and rewrite them; it moves objects and updates this table instead.
+    pub handles: Vec<usize>,
+    /// The references whose objects were collected, reused by `alloc`
+    pub free_handles: Vec<i32>,
+    /// The slots of the locals and operand stack of each frame, by call depth,
+    /// saved before the frame calls a method or allocates; they are the roots of the collector
+    pub frame_roots: Vec<Vec<i32>>,
+    /// The number of objects on the heap at which the next garbage collection runs
+    pub next_gc: usize,
+    /// What the garbage collector did so far
+    pub gc_stats: GcStats,
+    /// Where `System.out` writes to, stdout by default.
+    /// It is flushed after every `print` and `println`.
+    pub output: Box<dyn Write + Send>,
+    /// Where `System.err` writes to, stderr by default.
+    /// It is flushed after every `print` and `println` too.
+    pub error: Box<dyn Write + Send>,
+    /// Where `System.in` reads from, stdin by default
+    pub input: Box<dyn Read + Send>,
+    /// What `println` writes after its argument, "\n" by default
+    pub line_separator: String,
+    /// The directory class files are loaded from
+    pub class_path: PathBuf,
+    /// The further directories class files are loaded from, e.g. those of the `Class-Path` of a jar
+    pub directories: Vec<PathBuf>,
+    /// The jars class files are loaded from when they are not in a directory
+    pub jars: Vec<Jar>,
+    /// The loaded classes, keyed by their internal name
+    pub classes: HashMap<String, Arc<ClassFile>>,
+    /// The classes whose superclasses are being loaded
+    pub loading: HashSet<String>,
+    /// The classes whose initialization has started
+    pub initialized: HashSet<String>,
+    /// The classes whose static initializer threw, they cannot be used any more
+    pub erroneous: HashSet<String>,
+    /// The slots of the static fields, keyed by class and field name
+    pub static_fields: HashMap<(String, String), Vec<i32>>,
+    /// Whether `assert` statements run, they are disabled by default like in Java
+    pub assertions_enabled: bool,
+    /// The number of objects the heap may hold, unlimited if `None`
+    pub max_heap: Option<usize>,
+    /// The references to the interned strings, such as string literals
+    pub interned_strings: HashMap<String, i32>,
+    /// The number of frames currently executing
+    pub call_depth: usize,
+    /// The number of frames after which `StackOverflowError` is thrown
+    pub max_call_depth: usize,
+    /// Whether every method entry and exit is printed to stderr, indented by call depth
+    pub trace_calls: bool,
+    /// The number of threads created without a name, which numbers their names
+    pub threads_created: usize,
+    /// The arguments passed to `main` by `invoke_main`
+    pub args: Vec<String>,
+    /// The thread holding the monitor of each object, and how many times it entered it
+    /// and did not exit it yet
+    pub monitors: HashMap<i32, (i32, usize)>,
+    /// Hands the VM between the threads of the program, `None` unless threaded mode is on,
+    /// see `Vm::set_threading`
+    pub scheduler: Option<Arc<Scheduler>>,
+    /// The running thread, `MAIN_THREAD` or the reference to a `Thread` object
+    pub current_thread: i32,
+    /// The call depth and the frame roots of each thread which is not running;
+    /// they are roots of the collector too
+    pub parked_frames: HashMap<i32, (usize, Vec<Vec<i32>>)>,
+    /// The OS thread of each thread started in threaded mode, until it is joined
+    pub thread_handles: HashMap<i32, std::thread::JoinHandle<()>>,
+    /// The `java.lang.Class` object of each class, keyed by internal name
+    pub class_objects: HashMap<String, i32>,
+    /// The object of each static field of the class library read so far, e.g. `BigInteger.ONE`,
+    /// keyed by class and field name, so that every read gets the same object
+    pub native_static_fields: HashMap<(String, String), i32>,
+    /// Whether the time spent in each method is recorded in `profile`
+    pub profiling: bool,
+    /// The time spent in each method so far, see `Vm::get_profile`
+    pub profile: ProfileData,
+}
+
+impl Default for Vm {
+    fn default() -> Vm {
+        Vm {
+            deadline: None,
+            instruction_count: 0,
+            opcode_counts: None,
+            touched_constants: None,
+            heap: Vec::new(),
+            heap_references: Vec::new(),
+            handles: Vec::new(),
+            free_handles: Vec::new(),
+            frame_roots: Vec::new(),
+            next_gc: GC_MIN_THRESHOLD,
+            gc_stats: GcStats::default(),
+            output: Box::new(std::io::stdout()),
+            error: Box::new(std::io::stderr()),
+            input: Box::new(std::io::stdin()),
+            line_separator: "\n".to_string(),
+            class_path: PathBuf::from("."),
+            directories: Vec::new(),
+            jars: Vec::new(),
+            classes: HashMap::new(),
+            loading: HashSet::new(),
+            initialized: HashSet::new(),
+            erroneous: HashSet::new(),
+            static_fields: HashMap::new(),
+            assertions_enabled: false,
+            max_heap: None,
+            interned_strings: HashMap::new(),
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            trace_calls: false,
+            threads_created: 0,
+            args: Vec::new(),
+            monitors: HashMap::new(),
+            scheduler: None,
+            current_thread: MAIN_THREAD,
+            parked_frames: HashMap::new(),
+            thread_handles: HashMap::new(),
+            class_objects: HashMap::new(),
+            native_static_fields: HashMap::new(),
+            profiling: false,
+            profile: ProfileData::default(),
+        }
+    }
+}
+
+/// Statistics of the garbage collector, see `Vm::gc_stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// The number of garbage collections run
+    pub collections: u64,
+    /// The estimated number of bytes taken by the collected objects
+    pub bytes_freed: u64,
+    /// The number of objects on the heap
+    pub heap_size: usize,
+}
+
+/// The time spent in each method of the program, see `Vm::get_profile`.
+#[derive(Debug, Default)]
+pub struct ProfileData {
+    /// The number of calls and the time spent in them, keyed by class, method name and descriptor.
+    /// The time of a call includes that of the methods it calls, so a recursive
+    /// method counts the time of its inner calls again.
+    pub methods: HashMap<(String, String, String), (u64, Duration)>,
+}
+
+/// The time spent in one method, see `ProfileData::top_n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+    /// The internal name of the class declaring the method, e.g. "Factorial"
+    pub class: String,
+    pub method: String,
+    /// The descriptor of the method, e.g. "(I)I"
+    pub descriptor: String,
+    /// The number of times the method was called
+    pub calls: u64,
+    /// The time spent in all the calls
+    pub time: Duration,
+}
+
+impl ProfileData {
+    /// Add a call to the profile of a method.
+    fn record(&mut self, class: &str, method: &Method, time: Duration) {
+        let key = (
+            class.to_string(),
+            method.name.clone(),
+            method.descriptor.clone(),
+        );
+        let (calls, total) = self.methods.entry(key).or_default();
+        *calls += 1;
+        *total += time;
+    }
+
+    /// Get the methods the most time was spent in.
+    ///
+    /// # Parameters
+    /// * `n` - the number of methods to return at most
+    /// # Return
+    /// the methods by decreasing time
+    pub fn top_n(&self, n: usize) -> Vec<ProfileEntry> {
+        let mut entries: Vec<ProfileEntry> = self
+            .methods
+            .iter()
+            .map(
+                |((class, method, descriptor), &(calls, time))| ProfileEntry {
+                    class: class.clone(),
+                    method: method.clone(),
+                    descriptor: descriptor.clone(),
+                    calls,
+                    time,
+                },
+            )
+            .collect();
+        // methods which took the same time are listed by name, so that the order is stable
+        entries.sort_unstable_by(|a, b| {
+            b.time.cmp(&a.time).then_with(|| {
+                (&a.class, &a.method, &a.descriptor).cmp(&(&b.class, &b.method, &b.descriptor))
+            })
+        });
+        entries.truncate(n);
+        entries
+    }
+}
+
+/// Configure a `Vm` before running a program, e.g.
+/// `VmBuilder::new().max_heap(10_000).build()`.
+#[derive(Default)]
+pub struct VmBuilder {
+    vm: Vm,
+}
+
+impl VmBuilder {
+    /// Start from the default settings of `Vm`.
+    pub fn new() -> VmBuilder {
+        VmBuilder::default()
+    }
+
+    /// Limit the number of objects the heap may hold, see `Vm::set_max_heap`.
+    pub fn max_heap(mut self, max_heap: usize) -> VmBuilder {
+        self.vm.set_max_heap(max_heap);
+        self
+    }
+
+    /// Set the number of frames after which `StackOverflowError` is thrown.
+    pub fn max_call_depth(mut self, max_call_depth: usize) -> VmBuilder {
+        self.vm.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Set the directory class files are loaded from.
+    pub fn class_path(mut self, class_path: PathBuf) -> VmBuilder {
+        self.vm.class_path = class_path;
+        self
+    }
+
+    /// Add a directory to load class files from, after the class path.
+    pub fn directory(mut self, directory: PathBuf) -> VmBuilder {
+        self.vm.directories.push(directory);
+        self
+    }
+
+    /// Add a jar to load class files from.
+    pub fn jar(mut self, jar: Jar) -> VmBuilder {
+        self.vm.jars.push(jar);
+        self
+    }
+
+    /// Set where `System.out` writes to.
+    pub fn output(mut self, output: Box<dyn Write + Send>) -> VmBuilder {
+        self.vm.output = output;
+        self
+    }
+
+    /// Set where `System.err` writes to.
+    pub fn error(mut self, error: Box<dyn Write + Send>) -> VmBuilder {
+        self.vm.error = error;
+        self
+    }
+
+    /// Set where `System.in` reads from.
+    pub fn input(mut self, input: Box<dyn Read + Send>) -> VmBuilder {
+        self.vm.input = input;
+        self
+    }
+
+    /// Enable or disable `assert` statements.
+    pub fn assertions_enabled(mut self, enabled: bool) -> VmBuilder {
+        self.vm.assertions_enabled = enabled;
+        self
+    }
+
+    /// Set the arguments passed to `main` by `Vm::invoke_main`.
+    pub fn args(mut self, args: Vec<String>) -> VmBuilder {
+        self.vm.args = args;
+        self
+    }
+
+    /// Enable or disable printing every method entry and exit to stderr.
+    pub fn trace_calls(mut self, enabled: bool) -> VmBuilder {
+        self.vm.trace_calls = enabled;
+        self
+    }
+
+    /// Record how many times each opcode is executed, see `Vm::record_opcodes`.
+    pub fn record_opcodes(mut self) -> VmBuilder {
+        self.vm.record_opcodes();
+        self
+    }
+
+    /// Record which constants the executed instructions refer to, see `Vm::record_constants`.
+    pub fn record_constants(mut self) -> VmBuilder {
+        self.vm.record_constants();
+        self
+    }
+
+    /// Enable or disable recording the time spent in each method, see `Vm::get_profile`.
+    /// It is disabled by default, as it reads the clock twice per call.
+    pub fn profile(mut self, enabled: bool) -> VmBuilder {
+        self.vm.profiling = enabled;
+        self
+    }
+
+    /// Enable or disable running the threads of the program on OS threads,
+    /// see `Vm::set_threading`.
+    pub fn threading(mut self, enabled: bool) -> VmBuilder {
+        self.vm.set_threading(enabled);
+        self
+    }
+
+    /// Create the configured `Vm`.
+    pub fn build(self) -> Vm {
+        self.vm
+    }
 }
 
+/// The number of instructions executed between two checks of the deadline.
+/// Reading the clock on every instruction would slow down the interpreter.
+pub const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// The number of objects on the heap before the first garbage collection runs.
+/// After each collection the next one runs once the heap has doubled, but not below this.
+pub const GC_MIN_THRESHOLD: usize = 1024;
+
+/// Marks an entry of `Vm::handles` whose object was collected.
+pub const FREE_HANDLE: usize = usize::MAX;
+
+/// The id of the thread running `main` in `Vm::current_thread`; the other threads are
+/// identified by the reference to their `Thread` object.
+pub const MAIN_THREAD: i32 = 0;
+
+/// The default number of nested calls a program may make.
+/// Every Java frame also takes a native frame of the interpreter, so this is kept well
+/// below what `INTERPRETER_STACK_SIZE` can hold.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 512;
+
+/// The size of the native stack the command line runs programs on,
+/// and that of the OS threads started in threaded mode.
+/// The frames of `execute` and `run_frame` take about 16 KiB per Java frame in debug
+/// builds, which leaves the 8 MiB stack of the main thread no room to spare.
+pub const INTERPRETER_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// The number of methods `--profile` prints, those the most time was spent in.
+pub const PROFILE_LENGTH: usize = 20;
+
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_SYNCHRONIZED: u16 = 0x0020;
+pub const ACC_NATIVE: u16 = 0x0100;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+
+pub const i_impdep2: u8 = 255;
+pub const i_impdep1: u8 = 254;
+pub const i_breakpoint: u8 = 202;
+pub const i_goto_w: u8 = 200;
+pub const i_ifnonnull: u8 = 199;
+pub const i_ifnull: u8 = 198;
+pub const i_monitorexit: u8 = 195;
+pub const i_monitorenter: u8 = 194;
+pub const i_checkcast: u8 = 192;
+pub const i_athrow: u8 = 191;
+pub const i_arraylength: u8 = 190;
+pub const i_anewarray: u8 = 189;
+pub const i_newarray: u8 = 188;
+pub const i_invokedynamic: u8 = 186;
+pub const i_invokeinterface: u8 = 185;
+pub const i_new: u8 = 187;
 pub const i_invokestatic: u8 = 184;
+pub const i_invokespecial: u8 = 183;
 pub const i_invokevirtual: u8 = 182;
+pub const i_putfield: u8 = 181;
+pub const i_getfield: u8 = 180;
+pub const i_putstatic: u8 = 179;
 pub const i_getstatic: u8 = 178;
 pub const i_return: u8 = 177;
+pub const i_areturn: u8 = 176;
 pub const i_ireturn: u8 = 172;
+pub const i_lookupswitch: u8 = 171;
+pub const i_tableswitch: u8 = 170;
+pub const i_jsr: u8 = 168;
 pub const i_goto: u8 = 167;
+pub const i_if_acmpne: u8 = 166;
+pub const i_if_acmpeq: u8 = 165;
 pub const i_if_icmple: u8 = 164;
 pub const i_if_icmpgt: u8 = 163;
 pub const i_if_icmpge: u8 = 162;
@@ -80,6 +1229,9 @@ pub const i_ifge: u8 = 156;
 pub const i_iflt: u8 = 155;
 pub const i_ifne: u8 = 154;
 pub const i_ifeq: u8 = 153;
+pub const i_i2s: u8 = 147;
+pub const i_i2c: u8 = 146;
+pub const i_i2b: u8 = 145;
 pub const i_iinc: u8 = 132;
 pub const i_ineg: u8 = 116;
 pub const i_irem: u8 = 112;
@@ -87,35 +1239,129 @@ pub const i_idiv: u8 = 108;
 pub const i_imul: u8 = 104;
 pub const i_isub: u8 = 100;
 pub const i_iadd: u8 = 96;
+pub const i_dup2: u8 = 92;
+pub const i_dup: u8 = 89;
+pub const i_pop2: u8 = 88;
+pub const i_pop: u8 = 87;
+pub const i_bastore: u8 = 84;
+pub const i_aastore: u8 = 83;
+pub const i_iastore: u8 = 79;
+pub const i_astore_3: u8 = 78;
+pub const i_astore_0: u8 = 75;
 pub const i_istore_3: u8 = 62;
 pub const i_istore_0: u8 = 59;
+pub const i_astore: u8 = 58;
 pub const i_istore: u8 = 54;
+pub const i_baload: u8 = 51;
+pub const i_aaload: u8 = 50;
+pub const i_iaload: u8 = 46;
+pub const i_aload_3: u8 = 45;
+pub const i_aload_0: u8 = 42;
 pub const i_iload_3: u8 = 29;
 pub const i_iload_0: u8 = 26;
+pub const i_aload: u8 = 25;
 pub const i_iload: u8 = 21;
+pub const i_ldc2_w: u8 = 20;
+pub const i_ldc_w: u8 = 19;
 pub const i_ldc: u8 = 18;
 pub const i_sipush: u8 = 17;
 pub const i_bipush: u8 = 16;
+pub const i_dconst_1: u8 = 15;
+pub const i_dconst_0: u8 = 14;
+pub const i_lconst_1: u8 = 10;
+pub const i_lconst_0: u8 = 9;
 pub const i_iconst_5: u8 = 8;
 pub const i_iconst_0: u8 = 3;
 pub const i_iconst_m1: u8 = 2;
+pub const i_aconst_null: u8 = 1;
+pub const i_nop: u8 = 0;
 
-fn read_1_byte<T: Read>(class_file: &mut T) -> u8 {
-    let mut bytes = [0u8; 1];
-    class_file.read_exact(&mut bytes).unwrap();
-    u8::from_be_bytes(bytes)
+/// A reader of the big-endian values of a class file, which keeps track of
+/// the offset in the file of the next byte to read.
+pub struct ClassReader<T: Read> {
+    source: T,
+    position: usize,
 }
 
-fn read_2_bytes<T: Read>(class_file: &mut T) -> u16 {
-    let mut bytes = [0u8; 2];
-    class_file.read_exact(&mut bytes).unwrap();
-    u16::from_be_bytes(bytes)
+impl<T: Read> ClassReader<T> {
+    /// Read a class file from its first byte.
+    pub fn new(source: T) -> ClassReader<T> {
+        ClassReader::at(source, 0)
+    }
+
+    /// Read a part of a class file, e.g. the contents of an attribute.
+    ///
+    /// # Parameters
+    /// * `source` - the bytes of the part
+    /// * `position` - the offset of the part in the class file
+    pub fn at(source: T, position: usize) -> ClassReader<T> {
+        ClassReader { source, position }
+    }
+
+    /// Get the offset in the class file of the next byte to read.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn u1(&mut self) -> Result<u8, ParseError> {
+        Ok(u8::from_be_bytes(self.array()?))
+    }
+
+    pub fn u2(&mut self) -> Result<u16, ParseError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    pub fn u4(&mut self) -> Result<u32, ParseError> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    /// Read the given number of bytes, e.g. the contents of an attribute.
+    /// The length comes from the class file, so only what is actually there is allocated.
+    pub fn bytes(&mut self, length: usize) -> Result<Vec<u8>, ParseError> {
+        let position = self.position;
+        let mut bytes = Vec::new();
+        self.source
+            .by_ref()
+            .take(length as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|e| ParseError::from(e).at(position))?;
+        if bytes.len() < length {
+            let error = ParseError::Io(std::io::ErrorKind::UnexpectedEof.into());
+            return Err(error.at(position));
+        }
+        self.position += length;
+        Ok(bytes)
+    }
+
+    /// Read the given number of bytes as a part to be read on its own, e.g. an attribute.
+    /// The offsets of the part are still those in the class file.
+    pub fn part(&mut self, length: usize) -> Result<ClassReader<Cursor<Vec<u8>>>, ParseError> {
+        let position = self.position;
+        Ok(ClassReader::at(Cursor::new(self.bytes(length)?), position))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ParseError> {
+        let position = self.position;
+        let mut bytes = [0u8; N];
+        self.source
+            .read_exact(&mut bytes)
+            .map_err(|e| ParseError::from(e).at(position))?;
+        self.position += N;
+        Ok(bytes)
+    }
 }
 
-fn read_4_bytes<T: Read>(class_file: &mut T) -> u32 {
-    let mut bytes = [0u8; 4];
-    class_file.read_exact(&mut bytes).unwrap();
-    u32::from_be_bytes(bytes)
+/// Get the string of a UTF8 constant while parsing, when the index is not trusted yet.
+///
+/// # Parameters
+/// * `cp` - the constant pool
+/// * `index` - the 1-indexed constant pool index
+/// * `offset` - the offset in the class file of the index, reported if it is invalid
+fn parse_utf8(cp: &[ConstantPool], index: u16, offset: usize) -> Result<String, ParseError> {
+    match cp.get((index as usize).wrapping_sub(1)) {
+        Some(ConstantPool::Utf8(s)) => Ok(s.clone()),
+        _ => Err(ParseError::BadConstant(index).at(offset)),
+    }
 }
 
 /// Get the constant at the given index in a constant pool.
@@ -126,7 +1372,7 @@ fn read_4_bytes<T: Read>(class_file: &mut T) -> u32 {
 /// * `index` - the 1-indexed constant pool index
 /// # Return
 /// the constant at the given index
-fn get_constant(constant_pool: &Vec<ConstantPool>, index: u16) -> &ConstantPool {
+fn get_constant(constant_pool: &[ConstantPool], index: u16) -> &ConstantPool {
     &constant_pool[index as usize - 1]
 }
 
@@ -141,7 +1387,7 @@ fn get_constant(constant_pool: &Vec<ConstantPool>, index: u16) -> &ConstantPool
 /// * `class` - the parsed class file
 /// # Return
 /// the method if it was found
-fn find_method<'a>(name: &str, desc: &str, methods: &'a Vec<Method>) -> &'a Method {
+fn find_method<'a>(name: &str, desc: &str, methods: &'a [Method]) -> &'a Method {
     for method in methods {
         if method.name == name && method.descriptor == desc {
             return method;
@@ -155,488 +1401,8161 @@ fn find_method<'a>(name: &str, desc: &str, methods: &'a Vec<Method>) -> &'a Meth
 ///
 /// # Parameters
 /// * `index` - the constant pool index of the Methodref to call
-/// * `class` - the parsed class file
+/// * `class` - the parsed class file holding the Methodref
+/// * `own_class` - the class file the method belongs to
 /// # Return
 /// the method if it was found
-fn find_method_from_index(index: u16, class: &ClassFile) -> &Method {
-    let name_and_type = get_method_name_and_type(&class.constant_pool, index);
-    let name = get_constant(&class.constant_pool, name_and_type.0);
-    if let ConstantPool::Utf8(n) = name {
-        let descriptor = get_constant(&class.constant_pool, name_and_type.1);
-        if let ConstantPool::Utf8(d) = descriptor {
-            return find_method(n, d, &class.method);
-        }
-    }
+fn find_method_from_index<'a>(
+    index: u16,
+    class: &ClassFile,
+    own_class: &'a ClassFile,
+) -> &'a Method {
+    let method_ref = resolve_ref(class, index);
+    find_method(method_ref.name, method_ref.descriptor, &own_class.method)
+}
 
-    panic!("Expected a UTF8");
+/// Resolve a reference of a class the interpreter runs.
+/// Panic if the constant is not a valid reference.
+fn resolve_ref(class: &ClassFile, index: u16) -> ResolvedRef<'_> {
+    class
+        .resolve_method_ref(index)
+        .expect("Expected correct MethodRef, Class and NameAndType index")
 }
 
-fn get_method_name_and_type(cp: &Vec<ConstantPool>, index: u16) -> (u16, u16) {
-    let method = get_constant(cp, index);
-    if let &ConstantPool::MethodOrFieldRef {
-        class_index: _,
-        name_and_type_index,
-    } = method
+/// Get the method a MethodHandle constant refers to.
+///
+/// # Parameters
+/// * `class` - the class file holding the MethodHandle
+/// * `index` - the constant pool index of the MethodHandle
+/// # Return
+/// the class name, method name and descriptor of the method
+fn get_method_handle_target(class: &ClassFile, index: u16) -> ResolvedRef<'_> {
+    if let ConstantPool::MethodHandle {
+        reference_index, ..
+    } = *get_constant(&class.constant_pool, index)
     {
-        let name_and_type_constant = get_constant(cp, name_and_type_index);
-        if let &ConstantPool::NameAndType {
-            name_index,
-            descriptor_index,
-        } = name_and_type_constant
-        {
-            return (name_index, descriptor_index);
-        }
+        return resolve_ref(class, reference_index);
     }
 
-    panic!("Expected correct MethodRef and NameAndType index");
+    panic!("Expected a MethodHandle");
 }
 
-fn get_class_header(class_file: &mut BufReader<File>) -> ClassHeader {
-    ClassHeader {
-        magic: read_4_bytes(class_file),
-        minor_version: read_2_bytes(class_file),
-        major_version: read_2_bytes(class_file),
+/// Get the string of a UTF8 constant.
+///
+/// # Parameters
+/// * `cp` - the class's constant pool
+/// * `index` - the constant pool index of the Utf8
+/// # Return
+/// the string stored in the constant
+fn get_utf8(cp: &[ConstantPool], index: u16) -> &str {
+    if let ConstantPool::Utf8(s) = get_constant(cp, index) {
+        return s;
     }
+
+    panic!("Expected a UTF8");
 }
 
-fn get_constant_pool(class_file: &mut BufReader<File>) -> Vec<ConstantPool> {
-    let count = read_2_bytes(class_file) as usize - 1;
-    let mut cp: Vec<ConstantPool> = Vec::with_capacity(count);
+/// Count the operand stack slots taken by the parameters of a method.
+/// long and double parameters take two slots, every other type takes one.
+///
+/// # Parameters
+/// * `descriptor` - the method descriptor string, e.g. "(J[Ljava/lang/String;)V"
+/// # Return
+/// the number of slots
+fn get_param_slots(descriptor: &str) -> usize {
+    get_param_types(descriptor)
+        .into_iter()
+        .map(get_type_slots)
+        .sum()
+}
 
-    for _ in 0..count {
-        let tag = read_1_byte(class_file);
-        match tag {
-            1 => {
-                let length = read_2_bytes(class_file) as usize;
-                let mut bytes = vec![0u8; length];
-                class_file.read_exact(&mut bytes).unwrap();
-                cp.push(ConstantPool::Utf8(String::from_utf8(bytes).unwrap()));
-            }
-            3 => {
-                let mut bytes = [0u8; 4];
-                class_file.read_exact(&mut bytes).unwrap();
-                cp.push(ConstantPool::Integer {
-                    bytes: i32::from_be_bytes(bytes),
-                });
-            }
-            7 => cp.push(ConstantPool::Class {
-                starting_index: read_2_bytes(class_file),
-            }),
-            9 | 10 => cp.push(ConstantPool::MethodOrFieldRef {
-                class_index: read_2_bytes(class_file),
-                name_and_type_index: read_2_bytes(class_file),
-            }),
-            12 => cp.push(ConstantPool::NameAndType {
-                name_index: read_2_bytes(class_file),
-                descriptor_index: read_2_bytes(class_file),
-            }),
-            x => panic!("Unsupport tag: {}", x),
-        }
+/// Split the parameter types out of a method descriptor.
+///
+/// # Parameters
+/// * `descriptor` - the method descriptor string, e.g. "(J[Ljava/lang/String;)V"
+/// # Return
+/// the field descriptor of every parameter, e.g. ["J", "[Ljava/lang/String;"]
+fn get_param_types(descriptor: &str) -> Vec<&str> {
+    let params = descriptor
+        .get(1..descriptor.find(')').unwrap_or(descriptor.len()))
+        .unwrap_or_default();
+    let mut types = vec![];
+    let mut start = 0;
+    // the parser rejects malformed descriptors, the types are listed up to the first error
+    while let Some(length) = get_field_descriptor_length(&params[start..]) {
+        types.push(&params[start..start + length]);
+        start += length;
     }
+    types
+}
 
-    cp
+/// Measure the field descriptor at the start of a string.
+///
+/// # Parameters
+/// * `descriptor` - a string starting with a field descriptor, e.g. "[ILjava/lang/String;)V"
+/// # Return
+/// the length of the field descriptor, e.g. 2 for "[I";
+/// `None` if the string does not start with a valid one
+fn get_field_descriptor_length(descriptor: &str) -> Option<usize> {
+    // an array has at most 255 dimensions
+    let element = descriptor.find(|c| c != '[')?;
+    if element > 255 {
+        return None;
+    }
+    match descriptor.as_bytes()[element] {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => Some(element + 1),
+        b'L' => match descriptor[element..].find(';')? {
+            // the class name may not be empty
+            1 => None,
+            end => Some(element + end + 1),
+        },
+        _ => None,
+    }
 }
 
-fn get_class_info(class_file: &mut BufReader<File>) -> ClassInfo {
-    let info = ClassInfo {
-        access_flags: read_2_bytes(class_file),
-        this_calss: read_2_bytes(class_file),
-        super_class: read_2_bytes(class_file),
+/// Check that a string is a valid method descriptor, e.g. "(I[J)V".
+fn is_method_descriptor(descriptor: &str) -> bool {
+    let mut rest = match descriptor.strip_prefix('(') {
+        Some(rest) => rest,
+        None => return false,
     };
-    let interfaces_count = read_2_bytes(class_file);
-    if interfaces_count != 0 {
-        panic!("This VM does not support interfaces.")
-    }
-    let fields_count = read_2_bytes(class_file);
-    if fields_count != 0 {
-        panic!("This VM does not support fields.")
+    while !rest.starts_with(')') {
+        match get_field_descriptor_length(rest) {
+            Some(length) => rest = &rest[length..],
+            None => return false,
+        }
     }
-    info
+    let return_type = &rest[1..];
+    return_type == "V" || get_field_descriptor_length(return_type) == Some(return_type.len())
 }
 
-fn read_method_attributes(
-    class_file: &mut BufReader<File>,
-    acount: u16,
-    cp: &Vec<ConstantPool>,
-) -> Code {
-    let mut codes = None;
-    let mut found_code = false;
-    for _ in 0..acount {
+/// Check that a string is a valid field descriptor, e.g. "Ljava/lang/String;".
+fn is_field_descriptor(descriptor: &str) -> bool {
+    get_field_descriptor_length(descriptor) == Some(descriptor.len())
+}
+
+/// Get a descriptor while parsing, checking that it is well-formed so that the interpreter
+/// can take it apart without checks.
+///
+/// # Parameters
+/// * `cp` - the constant pool
+/// * `index` - the 1-indexed constant pool index of the Utf8 descriptor
+/// * `offset` - the offset in the class file of the index, reported if it is invalid
+/// * `is_valid` - the check for the kind of descriptor expected
+fn parse_descriptor(
+    cp: &[ConstantPool],
+    index: u16,
+    offset: usize,
+    is_valid: fn(&str) -> bool,
+) -> Result<String, ParseError> {
+    let descriptor = parse_utf8(cp, index, offset)?;
+    if !is_valid(&descriptor) {
+        return Err(ParseError::InvalidDescriptor(descriptor).at(offset));
+    }
+    Ok(descriptor)
+}
+
+/// Count the slots a value of the given type takes in the operand stack.
+///
+/// # Parameters
+/// * `descriptor` - the field descriptor string, e.g. "J"
+/// # Return
+/// 2 for long and double, 1 for any other type
+fn get_type_slots(descriptor: &str) -> usize {
+    match descriptor {
+        "J" | "D" => 2,
+        _ => 1,
+    }
+}
+
+/// Split a long into the two slots it takes in the operand stack, high bits first.
+fn long_to_slots(value: i64) -> [i32; 2] {
+    [(value >> 32) as i32, value as i32]
+}
+
+/// Join the two slots of a long taken from the operand stack.
+fn slots_to_long(high: i32, low: i32) -> i64 {
+    ((high as i64) << 32) | (low as u32 as i64)
+}
+
+/// Split a double into the two slots it takes in the operand stack.
+fn double_to_slots(value: f64) -> [i32; 2] {
+    long_to_slots(value.to_bits() as i64)
+}
+
+/// Join the two slots of a double taken from the operand stack.
+fn slots_to_double(high: i32, low: i32) -> f64 {
+    f64::from_bits(slots_to_long(high, low) as u64)
+}
+
+/// Format a double the way `Double.toString` does, e.g. "3.0" or "1.0E10".
+fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let magnitude = value.abs();
+    let plain = magnitude == 0.0 || (1e-3..1e7).contains(&magnitude);
+    format_decimal(plain, value.to_string(), format!("{:e}", value))
+}
+
+/// Format a float the way `Float.toString` does, e.g. "3.0" or "1.0E10".
+/// Like Java, it prints the shortest digits telling the float apart, not those of the double.
+fn format_float(value: f32) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let magnitude = value.abs();
+    let plain = magnitude == 0.0 || (1e-3..1e7).contains(&magnitude);
+    format_decimal(plain, value.to_string(), format!("{:e}", value))
+}
+
+/// Turn the Rust formatting of a finite floating-point number into Java's.
+///
+/// # Parameters
+/// * `plain` - whether the number is printed without exponent;
+///   Java switches to scientific notation outside of [10^-3, 10^7)
+/// * `decimal` - the number formatted with `{}`, e.g. "3"
+/// * `scientific` - the number formatted with `{:e}`, e.g. "1e10"
+fn format_decimal(plain: bool, decimal: String, scientific: String) -> String {
+    if plain {
+        if decimal.contains('.') {
+            decimal
+        } else {
+            decimal + ".0"
+        }
+    } else {
+        let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+        if mantissa.contains('.') {
+            format!("{}E{}", mantissa, &exponent[1..])
+        } else {
+            format!("{}.0E{}", mantissa, &exponent[1..])
+        }
+    }
+}
+
+/// Execute a method of the Java class library natively.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `class_name` - the internal name of the class, e.g. "java/lang/Character"
+/// * `name` - the method name, e.g. "isDigit"
+/// * `descriptor` - the method descriptor string, e.g. "(C)Z"
+/// * `args` - the slots of the arguments, starting with the receiver for instance methods
+/// # Return
+/// the slots of the return value, empty if the method returns void;
+/// `RuntimeError::Exception` if the method threw
+fn invoke_native(
+    vm: &mut Vm,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    Ok(match class_name {
+        "java/lang/Character" => vec![native_character(name, args[0])],
+        "java/io/PrintStream" => {
+            native_print_stream(vm, name, descriptor, args);
+            vec![]
+        }
+        "java/lang/String" => native_string(vm, name, descriptor, args)?,
+        "java/lang/Integer" => native_integer(vm, name, args)?,
+        "java/lang/Long" => native_long(name, args),
+        "java/lang/Math" => native_math(name, args),
+        "java/lang/StringBuilder" => native_string_builder(vm, name, descriptor, args)?,
+        "java/util/stream/IntStream" => native_int_stream(vm, name, descriptor, args)?,
+        "java/util/Arrays" => native_arrays(vm, name, args)?,
+        "java/util/Optional" | "java/util/OptionalInt" | "java/util/OptionalDouble" => {
+            native_optional(vm, name, args)?
+        }
+        "java/util/concurrent/atomic/AtomicInteger" => native_atomic_integer(vm, name, args),
+        "java/util/concurrent/atomic/AtomicLong" => native_atomic_long(vm, name, args),
+        "java/util/concurrent/atomic/AtomicBoolean" => native_atomic_boolean(vm, name, args),
+        "java/lang/ref/WeakReference" | "java/lang/ref/Reference" => {
+            native_weak_reference(vm, name, args)
+        }
+        "java/lang/System" => native_system(vm, name, args),
+        "java/util/Base64" | "java/util/Base64$Encoder" | "java/util/Base64$Decoder" => {
+            native_base64(vm, name, descriptor, args)?
+        }
+        "java/util/zip/CRC32" | "java/util/zip/Adler32" | "java/util/zip/Checksum" => {
+            native_checksum(vm, name, descriptor, args)?
+        }
+        "java/io/InputStream" => native_input_stream(vm, name, descriptor, args)?,
+        "java/lang/Thread" => native_thread(vm, name, descriptor, args)?,
+        "java/util/concurrent/CountDownLatch" => native_count_down_latch(vm, name, args)?,
+        "java/lang/Class" => native_class(vm, name, args)?,
+        "java/lang/Object" => native_object(name),
+        "java/math/BigInteger" => native_big_integer(vm, name, descriptor, args)?,
+        "java/math/BigDecimal" => native_big_decimal(vm, name, descriptor, args)?,
+        "java/time/LocalDate" => native_local_date(vm, name, args)?,
+        "java/util/Date" => native_date(vm, name, descriptor, args)?,
+        "java/util/Random" => native_random(vm, name, descriptor, args)?,
+        // FIXME: TreeMap and LinkedHashMap are the only implementations of the map interfaces
+        "java/util/LinkedHashMap" => native_linked_hash_map(vm, name, descriptor, args)?,
+        "java/util/Map"
+            if args[0] != 0
+                && matches!(vm.get_object(args[0]), HeapObject::LinkedHashMap { .. }) =>
+        {
+            native_linked_hash_map(vm, name, descriptor, args)?
+        }
+        "java/util/TreeMap"
+        | "java/util/Map"
+        | "java/util/SortedMap"
+        | "java/util/NavigableMap" => native_tree_map(vm, name, descriptor, args)?,
+        "java/util/Map$Entry" => native_map_entry(vm, name, args)?,
+        "java/util/Set" | "java/util/Collection" => native_collection(vm, name, args)?,
+        "java/util/Iterator" => native_iterator(vm, name, args)?,
+        "java/util/regex/Pattern" => native_pattern(vm, name, args)?,
+        "java/util/regex/Matcher" => native_matcher(vm, name, args)?,
+        // the methods of an array class, e.g. "[I", are those it inherits from Object
+        _ if class_name.starts_with('[') => native_array(vm, name, args)?,
+        _ if is_subclass_of(class_name, "java/lang/Throwable") => {
+            native_throwable(vm, name, descriptor, args)?
+        }
+        _ => panic!("Unsupported native method: {}.{}", class_name, name),
+    })
+}
+
+/// Call a method of `java.lang.Object` which a class of the program does not override.
+///
+/// # Parameters
+/// * `name` - the name of the method, e.g. "<init>"
+/// # Return
+/// the slots of the return value, empty if the method returns void
+fn native_object(name: &str) -> Vec<i32> {
+    match name {
+        "<init>" => vec![],
+        _ => panic!("Unsupported native method: java/lang/Object.{}", name),
+    }
+}
+
+/// Get the value of a static field of a class of the class library.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `class_name` - the internal name of the class, e.g. "java/math/BigInteger"
+/// * `name` - the name of the field, e.g. "ONE"
+/// # Return
+/// the reference stored in the field, the same object every time it is read;
+/// `OutOfMemoryError` if the heap is full on the first read
+fn get_native_static_field(vm: &mut Vm, class_name: &str, name: &str) -> Result<i32, RuntimeError> {
+    let key = (class_name.to_string(), name.to_string());
+    if let Some(&reference) = vm.native_static_fields.get(&key) {
+        return Ok(reference);
+    }
+    let reference = match (class_name, name) {
+        ("java/math/BigInteger", "ZERO") => {
+            vm.try_alloc(HeapObject::BigInteger(BigInteger::from_i64(0)))?
+        }
+        ("java/math/BigInteger", "ONE") => {
+            vm.try_alloc(HeapObject::BigInteger(BigInteger::from_i64(1)))?
+        }
+        ("java/math/BigInteger", "TWO") => {
+            vm.try_alloc(HeapObject::BigInteger(BigInteger::from_i64(2)))?
+        }
+        ("java/math/BigInteger", "TEN") => {
+            vm.try_alloc(HeapObject::BigInteger(BigInteger::from_i64(10)))?
+        }
+        ("java/math/BigDecimal", "ZERO") => vm.try_alloc(HeapObject::BigDecimal(
+            BigDecimal::new(BigInteger::from_i64(0), 0),
+        ))?,
+        ("java/math/BigDecimal", "ONE") => vm.try_alloc(HeapObject::BigDecimal(
+            BigDecimal::new(BigInteger::from_i64(1), 0),
+        ))?,
+        ("java/math/BigDecimal", "TEN") => vm.try_alloc(HeapObject::BigDecimal(
+            BigDecimal::new(BigInteger::from_i64(10), 0),
+        ))?,
+        ("java/lang/System", "in") => vm.try_alloc(HeapObject::SystemIn)?,
+        ("java/lang/System", "out") => vm.try_alloc(HeapObject::PrintStream { error: false })?,
+        ("java/lang/System", "err") => vm.try_alloc(HeapObject::PrintStream { error: true })?,
+        ("java/math/RoundingMode", _) => {
+            let mode = RoundingMode::from_name(name).expect("Unknown RoundingMode");
+            vm.try_alloc(HeapObject::RoundingMode(mode))?
+        }
+        // FIXME: other static fields are unimplemented, push null
+        _ => return Ok(0),
+    };
+    vm.native_static_fields.insert(key, reference);
+    Ok(reference)
+}
+
+/// Create an object of a class of the class library, before its constructor runs.
+///
+/// # Parameters
+/// * `class_name` - the internal name of the class, e.g. "java/util/concurrent/atomic/AtomicInteger"
+/// # Return
+/// the object with its default state
+fn new_native_object(class_name: &str) -> HeapObject {
+    match class_name {
+        "java/util/concurrent/atomic/AtomicInteger" => HeapObject::AtomicInteger(0),
+        "java/util/concurrent/atomic/AtomicLong" => HeapObject::AtomicLong(0),
+        "java/util/concurrent/atomic/AtomicBoolean" => HeapObject::AtomicBoolean(false),
+        "java/lang/StringBuilder" => HeapObject::StringBuilder(vec![]),
+        "java/math/BigInteger" => HeapObject::BigInteger(BigInteger::from_i64(0)),
+        "java/math/BigDecimal" => {
+            HeapObject::BigDecimal(BigDecimal::new(BigInteger::from_i64(0), 0))
+        }
+        "java/util/Date" => HeapObject::Date(0),
+        "java/util/Random" => HeapObject::Random {
+            seed: 0,
+            next_next_gaussian: None,
+        },
+        "java/lang/ref/WeakReference" => HeapObject::WeakReference(0),
+        "java/util/zip/CRC32" => HeapObject::Crc32(0),
+        "java/util/zip/Adler32" => HeapObject::Adler32(1),
+        "java/util/concurrent/CountDownLatch" => HeapObject::CountDownLatch(0),
+        "java/lang/Thread" => HeapObject::Thread {
+            target: 0,
+            name: String::new(),
+            started: false,
+            alive: false,
+        },
+        "java/util/TreeMap" => HeapObject::TreeMap {
+            entries: vec![],
+            comparator: 0,
+        },
+        "java/util/LinkedHashMap" => HeapObject::LinkedHashMap {
+            entries: vec![],
+            access_order: false,
+        },
+        _ if is_subclass_of(class_name, "java/lang/Throwable") => HeapObject::Throwable {
+            class_name: class_name.to_string(),
+            message: None,
+            cause: None,
+        },
+        _ => panic!("Unsupported native class: {}", class_name),
+    }
+}
+
+/// Get the superclass of a throwable class of the class library.
+///
+/// # Parameters
+/// * `class_name` - the internal name of the class, e.g. "java/lang/StackOverflowError"
+/// # Return
+/// the internal name of the superclass, `None` for `java/lang/Throwable` and unknown classes
+fn get_throwable_superclass(class_name: &str) -> Option<&'static str> {
+    Some(match class_name {
+        "java/lang/Exception" | "java/lang/Error" => "java/lang/Throwable",
+        "java/lang/RuntimeException" | "java/lang/ReflectiveOperationException" => {
+            "java/lang/Exception"
+        }
+        "java/lang/ClassNotFoundException"
+        | "java/lang/InstantiationException"
+        | "java/lang/IllegalAccessException" => "java/lang/ReflectiveOperationException",
+        "java/io/IOException" => "java/lang/Exception",
+        "java/lang/NullPointerException"
+        | "java/util/NoSuchElementException"
+        | "java/lang/IllegalArgumentException"
+        | "java/lang/IllegalStateException"
+        | "java/lang/IndexOutOfBoundsException"
+        | "java/lang/NegativeArraySizeException"
+        | "java/lang/ArithmeticException" => "java/lang/RuntimeException",
+        "java/lang/NumberFormatException" | "java/lang/IllegalThreadStateException" => {
+            "java/lang/IllegalArgumentException"
+        }
+        "java/lang/StringIndexOutOfBoundsException"
+        | "java/lang/ArrayIndexOutOfBoundsException" => "java/lang/IndexOutOfBoundsException",
+        "java/time/DateTimeException" => "java/lang/RuntimeException",
+        "java/util/regex/PatternSyntaxException" => "java/lang/IllegalArgumentException",
+        "java/lang/VirtualMachineError" | "java/lang/LinkageError" | "java/lang/AssertionError" => {
+            "java/lang/Error"
+        }
+        "java/lang/NoClassDefFoundError"
+        | "java/lang/ClassFormatError"
+        | "java/lang/ExceptionInInitializerError"
+        | "java/lang/IncompatibleClassChangeError" => "java/lang/LinkageError",
+        "java/lang/AbstractMethodError"
+        | "java/lang/NoSuchFieldError"
+        | "java/lang/NoSuchMethodError" => "java/lang/IncompatibleClassChangeError",
+        "java/lang/StackOverflowError" | "java/lang/OutOfMemoryError" => {
+            "java/lang/VirtualMachineError"
+        }
+        _ => return None,
+    })
+}
+
+/// Check whether a class is a class of the class library or one of its subclasses.
+/// FIXME: only the hierarchy of throwables is known
+fn is_subclass_of(class_name: &str, superclass: &str) -> bool {
+    let mut current = Some(class_name);
+    while let Some(name) = current {
+        if name == superclass {
+            return true;
+        }
+        current = get_throwable_superclass(name);
+    }
+    false
+}
+
+/// Invoke the single abstract method of a functional interface, e.g. `Runnable.run()`.
+/// Objects implementing functional interfaces are lambdas created by invokedynamic,
+/// so whichever interface method is called, the lambda's implementation method runs.
+/// This covers `Runnable`, `Supplier`, `Consumer`, `Function`, `Predicate`, `BiFunction`
+/// and their primitive specializations alike.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `object` - the reference to the lambda
+/// * `args` - the slots of the arguments of the interface method
+/// # Return
+/// the slots of the return value, empty if the method returns void
+pub fn invoke_functional_interface(
+    vm: &mut Vm,
+    object: i32,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let (class_name, name, descriptor, mut locals) = match vm.get_object(object) {
+        HeapObject::Lambda {
+            class_name,
+            name,
+            descriptor,
+            captured,
+        } => (
+            class_name.clone(),
+            name.clone(),
+            descriptor.clone(),
+            captured.clone(),
+        ),
+        _ => panic!("Expected a lambda"),
+    };
+
+    // the captured values come before the arguments of the interface method
+    locals.extend_from_slice(args);
+    if class_name.starts_with("java/") {
+        return invoke_native(vm, &class_name, &name, &descriptor, &locals);
+    }
+
+    let class = vm.initialize_class(&class_name)?;
+    let method = find_method(&name, &descriptor, &class.method);
+    locals.resize(locals.len().max(method.max_locals()), 0);
+    Ok(execute(vm, method, locals, &class)?.into_iter().collect())
+}
+
+/// Execute a static method of `java.lang.Character`.
+/// Every supported method takes a single int (or char) and returns an int.
+fn native_character(name: &str, arg: i32) -> i32 {
+    let c = char::from_u32(arg as u32).unwrap_or_default();
+    match name {
+        "isDigit" => c.is_ascii_digit() as i32,
+        "isLetter" => c.is_alphabetic() as i32,
+        "isWhitespace" => c.is_whitespace() as i32,
+        // Characters that do not map to a single character are left unchanged
+        "toLowerCase" => {
+            let mut lower = c.to_lowercase();
+            if lower.len() == 1 {
+                lower.next().unwrap() as i32
+            } else {
+                arg
+            }
+        }
+        "toUpperCase" => {
+            let mut upper = c.to_uppercase();
+            if upper.len() == 1 {
+                upper.next().unwrap() as i32
+            } else {
+                arg
+            }
+        }
+        // Letters count as digits above 9, e.g. 'A' is 10
+        "getNumericValue" => c.to_digit(36).map_or(-1, |d| d as i32),
+        _ => panic!("Unsupported native method: java/lang/Character.{}", name),
+    }
+}
+
+/// Execute `print` or `println` of `java.io.PrintStream`, the type of `System.out`
+/// and `System.err`. The argument is formatted according to its type in the descriptor.
+fn native_print_stream(vm: &mut Vm, name: &str, descriptor: &str, args: &[i32]) {
+    let text = match descriptor {
+        "()V" => String::new(),
+        _ => format_argument(vm, descriptor, &args[1..]),
+    };
+
+    let (sink, stream) = match vm.get_object(args[0]) {
+        HeapObject::PrintStream { error: false } => (&mut vm.output, "System.out"),
+        HeapObject::PrintStream { error: true } => (&mut vm.error, "System.err"),
+        _ => panic!("Expected System.out or System.err"),
+    };
+    let written = match name {
+        "print" => write!(sink, "{}", text),
+        "println" => write!(sink, "{}{}", text, vm.line_separator),
+        _ => panic!("Unsupported native method: java/io/PrintStream.{}", name),
+    };
+    // flush every write, so that a sink streaming the output sees it while the program runs
+    written
+        .and_then(|()| sink.flush())
+        .unwrap_or_else(|e| panic!("Cannot write to {}: {}", stream, e));
+}
+
+/// Format the single argument of a method the way `String.valueOf` does.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `descriptor` - the method descriptor, e.g. "(J)V"
+/// * `args` - the slots of the argument
+fn format_argument(vm: &Vm, descriptor: &str, args: &[i32]) -> String {
+    match &descriptor[..descriptor.find(')').unwrap() + 1] {
+        "(Z)" => (args[0] != 0).to_string(),
+        "(C)" => char::from_u32(args[0] as u32)
+            .unwrap_or_default()
+            .to_string(),
+        "(F)" => format_float(f32::from_bits(args[0] as u32)),
+        "(J)" => slots_to_long(args[0], args[1]).to_string(),
+        "(D)" => format_double(slots_to_double(args[0], args[1])),
+        params if params.starts_with("(L") => format_object(vm, args[0]),
+        _ => args[0].to_string(),
+    }
+}
+
+/// Format an object the way its `toString` does, "null" for a null reference.
+fn format_object(vm: &Vm, reference: i32) -> String {
+    let join =
+        |references: &mut dyn Iterator<Item = String>| references.collect::<Vec<_>>().join(", ");
+    if reference == 0 {
+        return "null".to_string();
+    }
+    match vm.get_object(reference) {
+        HeapObject::String(s) => s.clone(),
+        HeapObject::StringBuilder(chars) => chars.iter().collect(),
+        HeapObject::Integer(value) => value.to_string(),
+        HeapObject::BigInteger(value) => value.to_string(),
+        HeapObject::BigDecimal(value) => value.to_string(),
+        &HeapObject::LocalDate { year, month, day } => format_local_date(year, month, day),
+        &HeapObject::Date(millis) => format_date(millis),
+        HeapObject::TreeMap { entries, .. } | HeapObject::LinkedHashMap { entries, .. } => {
+            let mut entries = entries.iter().map(|&(key, value)| {
+                format!("{}={}", format_object(vm, key), format_object(vm, value))
+            });
+            format!("{{{}}}", join(&mut entries))
+        }
+        &HeapObject::MapEntry { key, value } => {
+            format!("{}={}", format_object(vm, key), format_object(vm, value))
+        }
+        HeapObject::Collection(elements) => {
+            let mut elements = elements.iter().map(|&element| format_object(vm, element));
+            format!("[{}]", join(&mut elements))
+        }
+        _ => panic!("Cannot print object"),
+    }
+}
+
+/// Execute an instance method of `java.lang.String`.
+fn native_string(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let s = match vm.get_object(args[0]) {
+        HeapObject::String(s) => s,
+        _ => panic!("Expected a String"),
+    };
+    let replaced = match (name, descriptor) {
+        ("replace", "(CC)Ljava/lang/String;") => {
+            let from = char::from_u32(args[1] as u32).unwrap_or_default();
+            let to = char::from_u32(args[2] as u32).unwrap_or_default();
+            Some(s.chars().map(|c| if c == from { to } else { c }).collect())
+        }
+        // the target is taken literally, unlike by replaceAll
+        ("replace", _) => Some(s.replace(&format_object(vm, args[1]), &format_object(vm, args[2]))),
+        ("replaceAll", _) | ("replaceFirst", _) => {
+            let regex = Regex::new(get_string(vm, args[1])).map_err(|message| {
+                RuntimeError::exception("java/util/regex/PatternSyntaxException", &message)
+            })?;
+            let input: Vec<char> = s.chars().collect();
+            let replacement = get_string(vm, args[2]);
+            Some(replace_matches(
+                &regex,
+                &input,
+                replacement,
+                name == "replaceAll",
+            )?)
+        }
+        _ => None,
+    };
+    if let Some(replaced) = replaced {
+        return Ok(vec![vm.try_alloc(HeapObject::String(replaced))?]);
+    }
+
+    // Java strings are made of UTF-16 code units, which their indexes count
+    let units: Vec<u16> = s.encode_utf16().collect();
+    let length = units.len() as i32;
+    Ok(match name {
+        "chars" => {
+            let values = units.iter().map(|&c| c as i32).collect();
+            vec![vm.try_alloc(HeapObject::IntStream(values))?]
+        }
+        "length" => vec![length],
+        "charAt" => {
+            if args[1] < 0 || args[1] >= length {
+                return Err(RuntimeError::exception(
+                    "java/lang/StringIndexOutOfBoundsException",
+                    &format!("String index out of range: {}", args[1]),
+                ));
+            }
+            vec![units[args[1] as usize] as i32]
+        }
+        "substring" => {
+            let (begin, end) = match descriptor {
+                "(I)Ljava/lang/String;" => (args[1], length),
+                _ => (args[1], args[2]),
+            };
+            if begin < 0 || begin > end || end > length {
+                return Err(RuntimeError::exception(
+                    "java/lang/StringIndexOutOfBoundsException",
+                    &format!("begin {}, end {}, length {}", begin, end, length),
+                ));
+            }
+            let text = String::from_utf16_lossy(&units[begin as usize..end as usize]);
+            vec![vm.try_alloc(HeapObject::String(text))?]
+        }
+        // the search starts at an index clamped to the string, so it never throws
+        "indexOf" => {
+            let target: Vec<u16> = if descriptor.starts_with("(I") {
+                // a surrogate is not a char of Rust, but it is a code unit to search for
+                match char::from_u32(args[1] as u32) {
+                    Some(c) => c.encode_utf16(&mut [0; 2]).to_vec(),
+                    None if (0xD800..0xE000).contains(&args[1]) => vec![args[1] as u16],
+                    None => return Ok(vec![-1]),
+                }
+            } else {
+                get_string(vm, args[1]).encode_utf16().collect()
+            };
+            let from = match descriptor {
+                "(II)I" | "(Ljava/lang/String;I)I" => args[2].clamp(0, length) as usize,
+                _ => 0,
+            };
+            let index = (from..=units.len().saturating_sub(target.len()))
+                .find(|&start| units[start..].starts_with(&target))
+                .map_or(-1, |index| index as i32);
+            vec![index]
+        }
+        // equal strings share one object from the pool of string literals,
+        // a string missing from the pool becomes the shared object itself
+        "intern" => {
+            if let Some(&reference) = vm.interned_strings.get(s) {
+                return Ok(vec![reference]);
+            }
+            vm.interned_strings.insert(s.clone(), args[0]);
+            vec![args[0]]
+        }
+        _ => panic!("Unsupported native method: java/lang/String.{}", name),
+    })
+}
+
+/// Execute a method of `java.util.stream.IntStream`.
+/// Streams are evaluated eagerly, every operation works on the materialized values.
+fn native_int_stream(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    // static factories
+    match (name, descriptor) {
+        ("of", "(I)Ljava/util/stream/IntStream;") => {
+            return Ok(vec![vm.try_alloc(HeapObject::IntStream(vec![args[0]]))?]);
+        }
+        ("of", "([I)Ljava/util/stream/IntStream;") => {
+            let values = match vm.get_object(args[0]) {
+                HeapObject::IntArray(values) => values.clone(),
+                _ => panic!("Expected an int[]"),
+            };
+            return Ok(vec![vm.try_alloc(HeapObject::IntStream(values))?]);
+        }
+        _ => {}
+    }
+
+    // operations taking a lambda run it on every value
+    match name {
+        "filter" | "map" | "forEach" => {
+            let values = match vm.get_object(args[0]) {
+                HeapObject::IntStream(values) => values.clone(),
+                _ => panic!("Expected an IntStream"),
+            };
+            let mut results = Vec::new();
+            for value in values {
+                let result = invoke_functional_interface(vm, args[1], &[value])?;
+                match name {
+                    "filter" if result[0] != 0 => results.push(value),
+                    "map" => results.push(result[0]),
+                    _ => {}
+                }
+            }
+            if name == "forEach" {
+                return Ok(vec![]);
+            }
+            return Ok(vec![vm.try_alloc(HeapObject::IntStream(results))?]);
+        }
+        _ => {}
+    }
+
+    let values = match vm.get_object(args[0]) {
+        HeapObject::IntStream(values) => values,
+        _ => panic!("Expected an IntStream"),
+    };
+    Ok(match name {
+        "count" => long_to_slots(values.len() as i64).to_vec(),
+        "sum" => vec![values.iter().fold(0, |sum, &v| sum.wrapping_add(v))],
+        "min" => {
+            let min = values.iter().min().copied();
+            vec![vm.try_alloc(HeapObject::OptionalInt(min))?]
+        }
+        "max" => {
+            let max = values.iter().max().copied();
+            vec![vm.try_alloc(HeapObject::OptionalInt(max))?]
+        }
+        "average" => {
+            let average = if values.is_empty() {
+                None
+            } else {
+                let sum: i64 = values.iter().map(|&v| v as i64).sum();
+                Some(sum as f64 / values.len() as f64)
+            };
+            vec![vm.try_alloc(HeapObject::OptionalDouble(average))?]
+        }
+        "toArray" => {
+            let values = values.clone();
+            vec![vm.try_alloc(HeapObject::IntArray(values))?]
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/stream/IntStream.{}",
+            name
+        ),
+    })
+}
+
+/// Execute a static method of `java.util.Arrays`.
+fn native_arrays(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    Ok(match name {
+        "stream" => {
+            let values = match vm.get_object(args[0]) {
+                HeapObject::IntArray(values) => values.clone(),
+                _ => panic!("Expected an int[]"),
+            };
+            vec![vm.try_alloc(HeapObject::IntStream(values))?]
+        }
+        // the overloads for every array type compare the same way
+        "equals" => vec![arrays_equal(vm, args[0], args[1], false)? as i32],
+        "deepEquals" => vec![arrays_equal(vm, args[0], args[1], true)? as i32],
+        _ => panic!("Unsupported native method: java/util/Arrays.{}", name),
+    })
+}
+
+/// Compare two arrays element by element, like `Arrays.equals` and `Arrays.deepEquals`.
+///
+/// # Parameters
+/// * `a`, `b` - the references to the arrays, either may be null
+/// * `deep` - whether arrays nested in arrays of references are compared by their elements
+///   rather than by their `equals` methods
+/// # Return
+/// whether both are null, or both have equal elements;
+/// `RuntimeError::Exception` if an `equals` method threw
+fn arrays_equal(vm: &mut Vm, a: i32, b: i32, deep: bool) -> Result<bool, RuntimeError> {
+    if a == b {
+        return Ok(true);
+    }
+    if a == 0 || b == 0 {
+        return Ok(false);
+    }
+    let pairs: Vec<(i32, i32)> = match (vm.get_object(a), vm.get_object(b)) {
+        (HeapObject::IntArray(a), HeapObject::IntArray(b)) => return Ok(a == b),
+        (HeapObject::ByteArray(a), HeapObject::ByteArray(b)) => return Ok(a == b),
+        (HeapObject::ReferenceArray(a), HeapObject::ReferenceArray(b)) if a.len() == b.len() => {
+            a.iter().copied().zip(b.iter().copied()).collect()
+        }
+        _ => return Ok(false),
+    };
+    for (a, b) in pairs {
+        let is_array = |vm: &Vm, reference: i32| {
+            reference != 0
+                && matches!(
+                    vm.get_object(reference),
+                    HeapObject::IntArray(_)
+                        | HeapObject::ByteArray(_)
+                        | HeapObject::ReferenceArray(_)
+                )
+        };
+        let equal = if deep && (is_array(vm, a) || is_array(vm, b)) {
+            arrays_equal(vm, a, b, deep)?
+        } else {
+            objects_equal(vm, a, b)?
+        };
+        if !equal {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Compare two objects with the `equals` method of the first one,
+/// where null is only equal to null.
+///
+/// # Return
+/// whether the objects are equal;
+/// `RuntimeError::Exception` if an `equals` method of the program threw
+fn objects_equal(vm: &mut Vm, a: i32, b: i32) -> Result<bool, RuntimeError> {
+    if a == b {
+        return Ok(true);
+    }
+    if a == 0 || b == 0 {
+        return Ok(false);
+    }
+    // an object of the program uses its own equals, or the identity it inherits from Object
+    if let HeapObject::Object { class_name, .. } = vm.get_object(a) {
+        let class_name = class_name.clone();
+        let descriptor = "(Ljava/lang/Object;)Z";
+        return Ok(
+            match vm.find_virtual_method(&class_name, "equals", descriptor)? {
+                Some(own_class) => {
+                    invoke_method(vm, &own_class, "equals", descriptor, vec![a, b])?[0] != 0
+                }
+                None => false,
+            },
+        );
+    }
+    Ok(match (vm.get_object(a), vm.get_object(b)) {
+        (HeapObject::String(a), HeapObject::String(b)) => a == b,
+        (HeapObject::Integer(a), HeapObject::Integer(b)) => a == b,
+        (HeapObject::BigInteger(a), HeapObject::BigInteger(b)) => a == b,
+        (HeapObject::BigDecimal(a), HeapObject::BigDecimal(b)) => a == b,
+        (HeapObject::LocalDate { .. }, HeapObject::LocalDate { .. }) => {
+            compare_natural(vm, a, b)? == std::cmp::Ordering::Equal
+        }
+        (HeapObject::Date(a), HeapObject::Date(b)) => a == b,
+        // the other classes of the class library compare by identity
+        _ => false,
+    })
+}
+
+/// Execute a method of an array, which implements `Cloneable`.
+fn native_array(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    if args[0] == 0 {
+        return Err(RuntimeError::Exception {
+            class: "java/lang/NullPointerException".to_string(),
+            message: None,
+            cause: None,
+        });
+    }
+    match name {
+        // the copy is shallow, the elements of an array of references are shared
+        "clone" => {
+            let copy = match vm.get_object(args[0]) {
+                HeapObject::IntArray(values) => HeapObject::IntArray(values.clone()),
+                HeapObject::ByteArray(values) => HeapObject::ByteArray(values.clone()),
+                HeapObject::ReferenceArray(values) => HeapObject::ReferenceArray(values.clone()),
+                _ => panic!("Expected an array"),
+            };
+            Ok(vec![vm.try_alloc(copy)?])
+        }
+        _ => panic!("Unsupported native method: array.{}", name),
+    }
+}
+
+/// Execute an instance method of `java.lang.Throwable`, inherited by every exception.
+fn native_throwable(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    if name == "<init>" {
+        // the constructor without argument leaves the message null
+        let text = match descriptor {
+            "()V" => None,
+            // the constructor taking a message keeps null as is
+            "(Ljava/lang/String;)V" if args[1] == 0 => None,
+            _ => Some(format_argument(vm, descriptor, &args[1..])),
+        };
+        match vm.get_object_mut(args[0]) {
+            HeapObject::Throwable { message, .. } => *message = text,
+            _ => panic!("Expected a Throwable"),
+        }
+        return Ok(vec![]);
+    }
+
+    let (message, cause) = match vm.get_object(args[0]) {
+        HeapObject::Throwable { message, cause, .. } => (message.clone(), cause.clone()),
+        _ => panic!("Expected a Throwable"),
+    };
+    Ok(match name {
+        "getMessage" => match message {
+            Some(m) => vec![vm.try_alloc(HeapObject::String(m))?],
+            None => vec![0],
+        },
+        // the cause is kept as the error it was thrown as, so each call gets a new object
+        "getCause" => match cause.map(|cause| *cause) {
+            Some(RuntimeError::Exception {
+                class,
+                message,
+                cause,
+            }) => vec![vm.try_alloc(HeapObject::Throwable {
+                class_name: class,
+                message,
+                cause,
+            })?],
+            _ => vec![0],
+        },
+        _ => panic!("Unsupported native method: java/lang/Throwable.{}", name),
+    })
+}
+
+/// Execute a method of `java.lang.Class`.
+fn native_class(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    Ok(match name {
+        // whether the assert statements of the class run
+        "desiredAssertionStatus" => vec![vm.assertions_enabled as i32],
+        // load and initialize a class by its binary name, e.g. "java.lang.Integer"
+        "forName" => {
+            if args[0] == 0 {
+                return Err(RuntimeError::Exception {
+                    class: "java/lang/NullPointerException".to_string(),
+                    message: None,
+                    cause: None,
+                });
+            }
+            let class_name = get_string(vm, args[0]).replace('.', "/");
+            // FIXME: every class of the class library is assumed to exist
+            if !class_name.starts_with("java/") {
+                vm.load_class(&class_name)?;
+                vm.initialize_class(&class_name)?;
+            }
+            vec![vm.class_object(&class_name)?]
+        }
+        "getName" => {
+            let class_name = match vm.get_object(args[0]) {
+                HeapObject::Class(class_name) => class_name.replace('/', "."),
+                _ => panic!("Expected a Class"),
+            };
+            vec![vm.try_alloc(HeapObject::String(class_name))?]
+        }
+        // create an object with the constructor taking no arguments
+        "newInstance" => {
+            let class_name = match vm.get_object(args[0]) {
+                HeapObject::Class(class_name) => class_name.clone(),
+                _ => panic!("Expected a Class"),
+            };
+            if class_name.starts_with("java/") {
+                let object = vm.try_alloc(new_native_object(&class_name))?;
+                invoke_native(vm, &class_name, "<init>", "()V", &[object])?;
+                return Ok(vec![object]);
+            }
+
+            let class = vm.initialize_class(&class_name)?;
+            let binary_name = class_name.replace('/', ".");
+            if class.is_interface() {
+                return Err(RuntimeError::exception(
+                    "java/lang/InstantiationException",
+                    &binary_name,
+                ));
+            }
+            if class.access_flags & ACC_ABSTRACT != 0 {
+                return Err(RuntimeError::Exception {
+                    class: "java/lang/InstantiationException".to_string(),
+                    message: None,
+                    cause: None,
+                });
+            }
+            let constructor = class
+                .method
+                .iter()
+                .find(|m| m.name == "<init>" && m.descriptor == "()V")
+                .ok_or_else(|| {
+                    RuntimeError::exception("java/lang/InstantiationException", &binary_name)
+                })?;
+            // FIXME: the caller is assumed to be in the same package, so only private is checked
+            if constructor.access_flags & ACC_PRIVATE != 0 {
+                return Err(RuntimeError::exception(
+                    "java/lang/IllegalAccessException",
+                    &format!(
+                        "cannot access a member of class {} with modifiers \"private\"",
+                        binary_name
+                    ),
+                ));
+            }
+
+            let object = vm.new_object(&class_name)?;
+            let object = vm.try_alloc(object)?;
+            invoke_method(vm, &class, "<init>", "()V", vec![object])?;
+            vec![object]
+        }
+        _ => panic!("Unsupported native method: java/lang/Class.{}", name),
+    })
+}
+
+/// Execute a static method of `java.lang.System`.
+fn native_system(vm: &mut Vm, name: &str, args: &[i32]) -> Vec<i32> {
+    match name {
+        "gc" => {
+            vm.collect_garbage();
+            vec![]
+        }
+        // the reference identifies an object for its whole life, even when the collector moves it,
+        // unlike its index in the heap; null is 0
+        "identityHashCode" => vec![args[0]],
+        _ => panic!("Unsupported native method: java/lang/System.{}", name),
+    }
+}
+
+/// Execute a method of `java.io.InputStream`, the type of `System.in`.
+fn native_input_stream(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    if !matches!(vm.get_object(args[0]), HeapObject::SystemIn) {
+        panic!("Expected System.in");
+    }
+    let io_exception =
+        |e: std::io::Error| RuntimeError::exception("java/io/IOException", &e.to_string());
+    match (name, descriptor) {
+        // a single byte as 0 to 255, -1 at the end of the stream
+        ("read", "()I") => {
+            let mut byte = [0u8];
+            let read = vm.input.read(&mut byte).map_err(io_exception)?;
+            Ok(vec![if read == 0 { -1 } else { byte[0] as i32 }])
+        }
+        ("read", _) => {
+            let length = match vm.get_object(args[1]) {
+                HeapObject::ByteArray(values) => values.len() as i32,
+                _ => panic!("Expected a byte[]"),
+            };
+            let (offset, count) = match descriptor {
+                "([BII)I" => (args[2], args[3]),
+                _ => (0, length),
+            };
+            if offset < 0 || count < 0 || count > length - offset {
+                return Err(RuntimeError::Exception {
+                    class: "java/lang/IndexOutOfBoundsException".to_string(),
+                    message: None,
+                    cause: None,
+                });
+            }
+            if count == 0 {
+                return Ok(vec![0]);
+            }
+            let mut buffer = vec![0u8; count as usize];
+            let read = vm.input.read(&mut buffer).map_err(io_exception)?;
+            if read == 0 {
+                return Ok(vec![-1]);
+            }
+            if let HeapObject::ByteArray(values) = vm.get_object_mut(args[1]) {
+                for (i, &byte) in buffer[..read].iter().enumerate() {
+                    values[offset as usize + i] = byte as i8;
+                }
+            }
+            Ok(vec![read as i32])
+        }
+        _ => panic!("Unsupported native method: java/io/InputStream.{}", name),
+    }
+}
+
+/// Add bytes to a CRC-32 checksum, using the polynomial of zip and PNG.
+///
+/// # Parameters
+/// * `crc` - the checksum of the bytes so far, 0 for none
+/// * `bytes` - the bytes to add
+/// # Return
+/// the checksum of all the bytes
+pub fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            // the polynomial is applied when the bit shifted out is set
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Add bytes to an Adler-32 checksum, as used by zlib.
+///
+/// # Parameters
+/// * `adler` - the checksum of the bytes so far, 1 for none
+/// * `bytes` - the bytes to add
+/// # Return
+/// the checksum of all the bytes
+pub fn adler32_update(adler: u32, bytes: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (mut a, mut b) = (adler & 0xffff, adler >> 16);
+    for &byte in bytes {
+        a = (a + byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+/// Execute a method of `java.util.zip.CRC32` or `java.util.zip.Adler32`.
+fn native_checksum(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let bytes = match descriptor {
+        "(I)V" => vec![args[1] as u8],
+        "([B)V" | "([BII)V" => {
+            let array: Vec<u8> = match vm.get_object(args[1]) {
+                HeapObject::ByteArray(values) => values.iter().map(|&value| value as u8).collect(),
+                _ => panic!("Expected a byte[]"),
+            };
+            let (offset, length) = match descriptor {
+                "([BII)V" => (args[2], args[3]),
+                _ => (0, array.len() as i32),
+            };
+            if offset < 0 || length < 0 || offset > array.len() as i32 - length {
+                return Err(RuntimeError::Exception {
+                    class: "java/lang/ArrayIndexOutOfBoundsException".to_string(),
+                    message: None,
+                    cause: None,
+                });
+            }
+            array[offset as usize..(offset + length) as usize].to_vec()
+        }
+        _ => vec![],
+    };
+
+    let checksum = vm.get_object_mut(args[0]);
+    Ok(match (name, checksum) {
+        ("<init>", _) => vec![],
+        ("update", HeapObject::Crc32(crc)) => {
+            *crc = crc32_update(*crc, &bytes);
+            vec![]
+        }
+        ("update", HeapObject::Adler32(adler)) => {
+            *adler = adler32_update(*adler, &bytes);
+            vec![]
+        }
+        ("reset", HeapObject::Crc32(crc)) => {
+            *crc = 0;
+            vec![]
+        }
+        ("reset", HeapObject::Adler32(adler)) => {
+            *adler = 1;
+            vec![]
+        }
+        ("getValue", HeapObject::Crc32(value) | HeapObject::Adler32(value)) => {
+            long_to_slots(*value as i64).to_vec()
+        }
+        _ => panic!("Unsupported native method: java/util/zip/Checksum.{}", name),
+    })
+}
+
+/// The alphabet of Base64, the URL and filename safe one ends with "-_" instead.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes to Base64 the way `java.util.Base64.Encoder` does.
+///
+/// # Parameters
+/// * `bytes` - the bytes to encode
+/// * `url` - whether to use "-_" instead of "+/"
+/// * `mime` - whether to break lines with "\r\n" after 76 characters
+/// * `padding` - whether to pad the output with '=' to a multiple of 4 characters
+pub fn base64_encode(bytes: &[u8], url: bool, mime: bool, padding: bool) -> String {
+    let alphabet = |index: u32| match (index, url) {
+        (62, true) => '-',
+        (63, true) => '_',
+        _ => BASE64_ALPHABET[index as usize] as char,
+    };
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        // n bytes take n + 1 characters
+        for i in 0..=chunk.len() {
+            encoded.push(alphabet((bits >> (18 - 6 * i)) & 0x3f));
+        }
+        if padding {
+            encoded.extend(std::iter::repeat_n('=', 3 - chunk.len()));
+        }
+    }
+    if !mime {
+        return encoded;
+    }
+    let lines: Vec<_> = encoded.as_bytes().chunks(76).collect();
+    lines
+        .iter()
+        .map(|line| String::from_utf8_lossy(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// Decode Base64 the way `java.util.Base64.Decoder` does, including its error messages.
+///
+/// # Parameters
+/// * `src` - the characters to decode, as bytes
+/// * `url` - whether "-_" are used instead of "+/"
+/// * `mime` - whether characters outside the alphabet are skipped
+/// # Return
+/// the decoded bytes, or the message of the `IllegalArgumentException` for invalid input
+pub fn base64_decode(src: &[u8], url: bool, mime: bool) -> Result<Vec<u8>, String> {
+    let value = |byte: u8| match byte {
+        b'-' | b'_' if url => Some(if byte == b'-' { 62 } else { 63 }),
+        b'+' | b'/' if url => None,
+        _ => BASE64_ALPHABET
+            .iter()
+            .position(|&c| c == byte)
+            .map(|index| index as u32),
+    };
+    if src.len() == 1 && !mime {
+        return Err("Input byte[] should at least have 2 bytes for base64 bytes".to_string());
+    }
+
+    let mut decoded = Vec::new();
+    let mut bits = 0u32;
+    let mut shift = 18i32;
+    let mut sp = 0;
+    while sp < src.len() {
+        let byte = src[sp];
+        sp += 1;
+        let value = match value(byte) {
+            Some(value) => value,
+            None if byte == b'=' => {
+                // padding may only complete a unit of two or three characters
+                if shift == 6 && (sp == src.len() || src[sp] != b'=') || shift == 18 {
+                    return Err("Input byte array has wrong 4-byte ending unit".to_string());
+                }
+                if shift == 6 {
+                    sp += 1;
+                }
+                break;
+            }
+            None if mime => continue,
+            // Java prints the signed byte in hex
+            None => {
+                let byte = byte as i8;
+                let sign = if byte < 0 { "-" } else { "" };
+                return Err(format!(
+                    "Illegal base64 character {}{:x}",
+                    sign,
+                    byte.unsigned_abs()
+                ));
+            }
+        };
+        bits |= value << shift;
+        shift -= 6;
+        if shift < 0 {
+            decoded.extend_from_slice(&[(bits >> 16) as u8, (bits >> 8) as u8, bits as u8]);
+            shift = 18;
+            bits = 0;
+        }
+    }
+    match shift {
+        6 => decoded.push((bits >> 16) as u8),
+        0 => decoded.extend_from_slice(&[(bits >> 16) as u8, (bits >> 8) as u8]),
+        12 => return Err("Last unit does not have enough valid bits".to_string()),
+        _ => {}
+    }
+    // only characters outside the alphabet may follow the padding, and only for MIME;
+    // like Java, the position reported for MIME is one past the offending byte
+    while sp < src.len() {
+        if mime {
+            sp += 1;
+            if value(src[sp - 1]).is_none() {
+                continue;
+            }
+        }
+        return Err(format!(
+            "Input byte array has incorrect ending byte at {}",
+            sp
+        ));
+    }
+    Ok(decoded)
+}
+
+/// Execute a method of `java.util.Base64` or of its `Encoder` and `Decoder`.
+fn native_base64(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let encoder = |url, mime| HeapObject::Base64Encoder {
+        url,
+        mime,
+        padding: true,
+    };
+    let decoder = |url, mime| HeapObject::Base64Decoder { url, mime };
+    let created = match name {
+        "getEncoder" => Some(encoder(false, false)),
+        "getUrlEncoder" => Some(encoder(true, false)),
+        "getMimeEncoder" => Some(encoder(false, true)),
+        "getDecoder" => Some(decoder(false, false)),
+        "getUrlDecoder" => Some(decoder(true, false)),
+        "getMimeDecoder" => Some(decoder(false, true)),
+        _ => None,
+    };
+    if let Some(object) = created {
+        return Ok(vec![vm.try_alloc(object)?]);
+    }
+
+    // the argument is a byte[] or a String, whose chars are taken as ISO-8859-1 bytes
+    let input: Vec<u8> = match args.get(1).map(|&arg| vm.get_object(arg)) {
+        Some(HeapObject::ByteArray(values)) => values.iter().map(|&value| value as u8).collect(),
+        Some(HeapObject::String(s)) => s
+            .chars()
+            .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let to_byte_array =
+        |bytes: Vec<u8>| HeapObject::ByteArray(bytes.into_iter().map(|byte| byte as i8).collect());
+    Ok(match *vm.get_object(args[0]) {
+        HeapObject::Base64Encoder { url, mime, padding } => match name {
+            "withoutPadding" => vec![vm.try_alloc(HeapObject::Base64Encoder {
+                url,
+                mime,
+                padding: false,
+            })?],
+            "encodeToString" => {
+                let encoded = base64_encode(&input, url, mime, padding);
+                vec![vm.try_alloc(HeapObject::String(encoded))?]
+            }
+            "encode" => {
+                let encoded = base64_encode(&input, url, mime, padding);
+                vec![vm.try_alloc(to_byte_array(encoded.into_bytes()))?]
+            }
+            _ => panic!(
+                "Unsupported native method: java/util/Base64$Encoder.{}",
+                name
+            ),
+        },
+        HeapObject::Base64Decoder { url, mime } if name == "decode" => {
+            let decoded = base64_decode(&input, url, mime).map_err(|message| {
+                RuntimeError::exception("java/lang/IllegalArgumentException", &message)
+            })?;
+            vec![vm.try_alloc(to_byte_array(decoded))?]
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/Base64.{}{}",
+            name, descriptor
+        ),
+    })
+}
+
+/// Execute a method of `java.lang.ref.WeakReference`.
+/// There are no reference queues, so a reference is never enqueued.
+fn native_weak_reference(vm: &mut Vm, name: &str, args: &[i32]) -> Vec<i32> {
+    let referent = match vm.get_object_mut(args[0]) {
+        HeapObject::WeakReference(referent) => referent,
+        _ => panic!("Expected a WeakReference"),
+    };
+    match name {
+        "<init>" => {
+            *referent = args[1];
+            vec![]
+        }
+        "get" => vec![*referent],
+        "refersTo" => vec![(*referent == args[1]) as i32],
+        "clear" => {
+            *referent = 0;
+            vec![]
+        }
+        "enqueue" | "isEnqueued" => vec![0],
+        _ => panic!(
+            "Unsupported native method: java/lang/ref/WeakReference.{}",
+            name
+        ),
+    }
+}
+
+/// Execute a method of `java.util.concurrent.atomic.AtomicInteger`.
+/// Only one thread runs the VM at a time, so the value is updated without any synchronization.
+fn native_atomic_integer(vm: &mut Vm, name: &str, args: &[i32]) -> Vec<i32> {
+    let value = match vm.get_object_mut(args[0]) {
+        HeapObject::AtomicInteger(value) => value,
+        _ => panic!("Expected an AtomicInteger"),
+    };
+    let old = *value;
+    match name {
+        "<init>" => {
+            // the constructor without argument keeps the value 0
+            *value = args.get(1).copied().unwrap_or(0);
+            vec![]
+        }
+        "get" | "intValue" => vec![old],
+        "set" => {
+            *value = args[1];
+            vec![]
+        }
+        "getAndIncrement" => {
+            *value = old.wrapping_add(1);
+            vec![old]
+        }
+        "incrementAndGet" => {
+            *value = old.wrapping_add(1);
+            vec![*value]
+        }
+        "getAndDecrement" => {
+            *value = old.wrapping_sub(1);
+            vec![old]
+        }
+        "decrementAndGet" => {
+            *value = old.wrapping_sub(1);
+            vec![*value]
+        }
+        "getAndAdd" => {
+            *value = old.wrapping_add(args[1]);
+            vec![old]
+        }
+        "addAndGet" => {
+            *value = old.wrapping_add(args[1]);
+            vec![*value]
+        }
+        "getAndSet" => {
+            *value = args[1];
+            vec![old]
+        }
+        "compareAndSet" => {
+            let swapped = old == args[1];
+            if swapped {
+                *value = args[2];
+            }
+            vec![swapped as i32]
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/concurrent/atomic/AtomicInteger.{}",
+            name
+        ),
+    }
+}
+
+/// Execute a method of `java.util.concurrent.atomic.AtomicLong`.
+/// Like `AtomicInteger`, the value is updated without any synchronization.
+fn native_atomic_long(vm: &mut Vm, name: &str, args: &[i32]) -> Vec<i32> {
+    let value = match vm.get_object_mut(args[0]) {
+        HeapObject::AtomicLong(value) => value,
+        _ => panic!("Expected an AtomicLong"),
+    };
+    let old = *value;
+    // the long arguments take two slots each
+    let arg = |n: usize| slots_to_long(args[2 * n - 1], args[2 * n]);
+    let (new, result) = match name {
+        // the constructor without argument keeps the value 0
+        "<init>" if args.len() == 1 => return vec![],
+        "<init>" | "set" => (arg(1), None),
+        "get" | "longValue" => (old, Some(old)),
+        "getAndIncrement" => (old.wrapping_add(1), Some(old)),
+        "incrementAndGet" => (old.wrapping_add(1), Some(old.wrapping_add(1))),
+        "getAndDecrement" => (old.wrapping_sub(1), Some(old)),
+        "decrementAndGet" => (old.wrapping_sub(1), Some(old.wrapping_sub(1))),
+        "getAndAdd" => (old.wrapping_add(arg(1)), Some(old)),
+        "addAndGet" => (old.wrapping_add(arg(1)), Some(old.wrapping_add(arg(1)))),
+        "getAndSet" => (arg(1), Some(old)),
+        "compareAndSet" => {
+            let swapped = old == arg(1);
+            *value = if swapped { arg(2) } else { old };
+            return vec![swapped as i32];
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/concurrent/atomic/AtomicLong.{}",
+            name
+        ),
+    };
+    *value = new;
+    result.map_or(vec![], |result| long_to_slots(result).to_vec())
+}
+
+/// Execute a method of `java.util.concurrent.atomic.AtomicBoolean`.
+fn native_atomic_boolean(vm: &mut Vm, name: &str, args: &[i32]) -> Vec<i32> {
+    let value = match vm.get_object_mut(args[0]) {
+        HeapObject::AtomicBoolean(value) => value,
+        _ => panic!("Expected an AtomicBoolean"),
+    };
+    let old = *value;
+    match name {
+        "<init>" => {
+            // the constructor without argument keeps the value false
+            *value = args.get(1).is_some_and(|&arg| arg != 0);
+            vec![]
+        }
+        "get" => vec![old as i32],
+        "set" => {
+            *value = args[1] != 0;
+            vec![]
+        }
+        "getAndSet" => {
+            *value = args[1] != 0;
+            vec![old as i32]
+        }
+        "compareAndSet" => {
+            let swapped = old == (args[1] != 0);
+            if swapped {
+                *value = args[2] != 0;
+            }
+            vec![swapped as i32]
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/concurrent/atomic/AtomicBoolean.{}",
+            name
+        ),
+    }
+}
+
+/// Execute a method of `java.math.BigInteger`.
+/// Every operation creates a new object, like in Java where `BigInteger` is immutable.
+fn native_big_integer(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let get = |vm: &Vm, reference: i32| match vm.get_object(reference) {
+        HeapObject::BigInteger(value) => value.clone(),
+        _ => panic!("Expected a BigInteger"),
+    };
+    let arithmetic =
+        |message: &str| RuntimeError::exception("java/lang/ArithmeticException", message);
+
+    // static factory and constructors
+    match name {
+        "valueOf" => {
+            let value = BigInteger::from_i64(slots_to_long(args[0], args[1]));
+            return Ok(vec![vm.try_alloc(HeapObject::BigInteger(value))?]);
+        }
+        "<init>" => {
+            // a negative radix wraps around to one out of range
+            let radix = if descriptor == "(Ljava/lang/String;I)V" {
+                args[2] as u32
+            } else {
+                10
+            };
+            let value = BigInteger::parse(get_string(vm, args[1]), radix).map_err(|message| {
+                RuntimeError::exception("java/lang/NumberFormatException", &message)
+            })?;
+            *vm.get_object_mut(args[0]) = HeapObject::BigInteger(value);
+            return Ok(vec![]);
+        }
+        _ => {}
+    }
+
+    let value = get(vm, args[0]);
+    let result = match name {
+        "add" => value.add(&get(vm, args[1])),
+        "subtract" => value.subtract(&get(vm, args[1])),
+        "multiply" => value.multiply(&get(vm, args[1])),
+        "divide" | "remainder" => {
+            let (quotient, remainder) = value
+                .divide_and_remainder(&get(vm, args[1]))
+                .ok_or_else(|| arithmetic("BigInteger divide by zero"))?;
+            if name == "divide" {
+                quotient
+            } else {
+                remainder
+            }
+        }
+        // unlike the remainder, the modulus is never negative
+        "mod" => {
+            let modulus = get(vm, args[1]);
+            if modulus.signum() <= 0 {
+                return Err(arithmetic("BigInteger: modulus not positive"));
+            }
+            let remainder = value.divide_and_remainder(&modulus).unwrap().1;
+            if remainder.signum() < 0 {
+                remainder.add(&modulus)
+            } else {
+                remainder
+            }
+        }
+        "pow" if args[1] < 0 => return Err(arithmetic("Negative exponent")),
+        "pow" => value.pow(args[1] as u32),
+        "gcd" => value.gcd(&get(vm, args[1])),
+        "max" => value.max(get(vm, args[1])),
+        "min" => value.min(get(vm, args[1])),
+        "negate" => value.negate(),
+        "abs" => value.abs(),
+        "compareTo" => return Ok(vec![value.cmp(&get(vm, args[1])) as i32]),
+        "equals" => {
+            let equal = args[1] != 0
+                && matches!(vm.get_object(args[1]), HeapObject::BigInteger(other) if *other == value);
+            return Ok(vec![equal as i32]);
+        }
+        "signum" => return Ok(vec![value.signum()]),
+        "intValue" => return Ok(vec![value.to_i64() as i32]),
+        "longValue" => return Ok(long_to_slots(value.to_i64()).to_vec()),
+        "isProbablePrime" => return Ok(vec![value.is_probable_prime(args[1]) as i32]),
+        "toString" => {
+            let radix = if descriptor == "(I)Ljava/lang/String;" {
+                args[1] as u32
+            } else {
+                10
+            };
+            let text = value.to_string_radix(radix);
+            return Ok(vec![vm.try_alloc(HeapObject::String(text))?]);
+        }
+        _ => panic!("Unsupported native method: java/math/BigInteger.{}", name),
+    };
+    Ok(vec![vm.try_alloc(HeapObject::BigInteger(result))?])
+}
+
+/// Execute a method of `java.math.BigDecimal`.
+/// Every operation creates a new object, like in Java where `BigDecimal` is immutable.
+fn native_big_decimal(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let get = |vm: &Vm, reference: i32| match vm.get_object(reference) {
+        HeapObject::BigDecimal(value) => value.clone(),
+        _ => panic!("Expected a BigDecimal"),
+    };
+    // the rounding mode is either a RoundingMode or a legacy int constant
+    let get_mode = |vm: &Vm, slot: i32| {
+        if descriptor.contains("Ljava/math/RoundingMode;") {
+            match vm.get_object(slot) {
+                HeapObject::RoundingMode(mode) => Ok(*mode),
+                _ => panic!("Expected a RoundingMode"),
+            }
+        } else {
+            RoundingMode::from_ordinal(slot).ok_or_else(|| {
+                RuntimeError::exception(
+                    "java/lang/IllegalArgumentException",
+                    "Invalid rounding mode",
+                )
+            })
+        }
+    };
+    let arithmetic =
+        |message: &str| RuntimeError::exception("java/lang/ArithmeticException", message);
+    let rounding_necessary = || arithmetic("Rounding necessary");
+
+    // static factories and constructors
+    let created = match (name, descriptor) {
+        ("<init>", "(Ljava/lang/String;)V") => Some(
+            BigDecimal::parse(get_string(vm, args[1])).map_err(|message| {
+                RuntimeError::exception("java/lang/NumberFormatException", &message)
+            })?,
+        ),
+        ("<init>", "(I)V") => Some(BigDecimal::new(BigInteger::from_i64(args[1] as i64), 0)),
+        ("<init>", "(J)V") => Some(BigDecimal::new(
+            BigInteger::from_i64(slots_to_long(args[1], args[2])),
+            0,
+        )),
+        ("valueOf", "(J)Ljava/math/BigDecimal;") => Some(BigDecimal::new(
+            BigInteger::from_i64(slots_to_long(args[0], args[1])),
+            0,
+        )),
+        ("valueOf", "(JI)Ljava/math/BigDecimal;") => Some(BigDecimal::new(
+            BigInteger::from_i64(slots_to_long(args[0], args[1])),
+            args[2],
+        )),
+        // like Java, a double is converted through its string representation
+        ("valueOf", "(D)Ljava/math/BigDecimal;") => {
+            let value = slots_to_double(args[0], args[1]);
+            if !value.is_finite() {
+                return Err(RuntimeError::exception(
+                    "java/lang/NumberFormatException",
+                    "Infinite or NaN",
+                ));
+            }
+            Some(BigDecimal::parse(&format_double(value)).unwrap())
+        }
+        _ => None,
+    };
+    if let Some(value) = created {
+        if name == "<init>" {
+            *vm.get_object_mut(args[0]) = HeapObject::BigDecimal(value);
+            return Ok(vec![]);
+        }
+        return Ok(vec![vm.try_alloc(HeapObject::BigDecimal(value))?]);
+    }
+
+    let value = get(vm, args[0]);
+    let result = match name {
+        "add" => value.add(&get(vm, args[1])),
+        "subtract" => value.subtract(&get(vm, args[1])),
+        "multiply" => value.multiply(&get(vm, args[1])),
+        "negate" => value.negate(),
+        "abs" => value.abs(),
+        "divide" => {
+            let divisor = get(vm, args[1]);
+            if divisor.signum() == 0 {
+                let message = if value.signum() == 0 {
+                    "Division undefined"
+                } else {
+                    "Division by zero"
+                };
+                return Err(arithmetic(message));
+            }
+            match descriptor {
+                "(Ljava/math/BigDecimal;)Ljava/math/BigDecimal;" => {
+                    value.divide_exact(&divisor).ok_or_else(|| {
+                        arithmetic("Non-terminating decimal expansion; no exact representable decimal result.")
+                    })?
+                }
+                // the quotient keeps the scale of the dividend
+                "(Ljava/math/BigDecimal;Ljava/math/RoundingMode;)Ljava/math/BigDecimal;"
+                | "(Ljava/math/BigDecimal;I)Ljava/math/BigDecimal;" => {
+                    let mode = get_mode(vm, args[2])?;
+                    value
+                        .divide(&divisor, value.scale, mode)
+                        .ok_or_else(rounding_necessary)?
+                }
+                _ => {
+                    let mode = get_mode(vm, args[3])?;
+                    value
+                        .divide(&divisor, args[2], mode)
+                        .ok_or_else(rounding_necessary)?
+                }
+            }
+        }
+        "setScale" => {
+            let mode = if descriptor == "(I)Ljava/math/BigDecimal;" {
+                RoundingMode::Unnecessary
+            } else {
+                get_mode(vm, args[2])?
+            };
+            value
+                .set_scale(args[1], mode)
+                .ok_or_else(rounding_necessary)?
+        }
+        "compareTo" => return Ok(vec![value.compare(&get(vm, args[1])) as i32]),
+        "equals" => {
+            let equal = args[1] != 0
+                && matches!(vm.get_object(args[1]), HeapObject::BigDecimal(other) if *other == value);
+            return Ok(vec![equal as i32]);
+        }
+        "signum" => return Ok(vec![value.signum()]),
+        "scale" => return Ok(vec![value.scale]),
+        "doubleValue" => {
+            let double = value.to_string().parse().unwrap();
+            return Ok(double_to_slots(double).to_vec());
+        }
+        "toString" | "toPlainString" => {
+            let text = if name == "toString" {
+                value.to_string()
+            } else {
+                value.to_plain_string()
+            };
+            return Ok(vec![vm.try_alloc(HeapObject::String(text))?]);
+        }
+        _ => panic!("Unsupported native method: java/math/BigDecimal.{}", name),
+    };
+    Ok(vec![vm.try_alloc(HeapObject::BigDecimal(result))?])
+}
+
+/// Get the number of days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    // count from March, so that the leap day is the last day of the year
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Get the date of the proleptic Gregorian calendar a number of days after 1970-01-01.
+///
+/// # Return
+/// the year, month and day of month
+fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year as i32, month as u8, day as u8)
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Format a date like `LocalDate.toString()`, e.g. "2021-03-04".
+fn format_local_date(year: i32, month: u8, day: u8) -> String {
+    let year = match year {
+        0..=9999 => format!("{:04}", year),
+        y if y < 0 => format!("-{:04}", -y),
+        y => format!("+{}", y),
+    };
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+/// Format a date like `Date.toString()`, e.g. "Thu Jan 01 00:00:00 UTC 1970".
+/// FIXME: the time zone is always UTC
+fn format_date(millis: i64) -> String {
+    let days = millis.div_euclid(86_400_000);
+    let seconds = millis.rem_euclid(86_400_000) / 1000;
+    let (year, month, day) = civil_from_days(days);
+    // 1970-01-01 was a Thursday
+    let weekday = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"][days.rem_euclid(7) as usize];
+    format!(
+        "{} {} {:02} {:02}:{:02}:{:02} UTC {}",
+        weekday,
+        &MONTH_NAMES[month as usize - 1][..3],
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        year
+    )
+}
+
+/// Get the current time in milliseconds since 1970-01-01T00:00:00Z.
+fn current_time_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+/// Execute a method of `java.time.LocalDate`.
+fn native_local_date(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    let date_time = |message: &str| RuntimeError::exception("java/time/DateTimeException", message);
+    let from_days = |vm: &mut Vm, days: i64| {
+        let (year, month, day) = civil_from_days(days);
+        Ok(vec![vm.try_alloc(HeapObject::LocalDate {
+            year,
+            month,
+            day,
+        })?])
+    };
+    let get = |vm: &Vm, reference: i32| match *vm.get_object(reference) {
+        HeapObject::LocalDate { year, month, day } => (year, month, day),
+        _ => panic!("Expected a LocalDate"),
+    };
+
+    // static factories
+    match name {
+        "now" => return from_days(vm, current_time_millis().div_euclid(86_400_000)),
+        "ofEpochDay" => return from_days(vm, slots_to_long(args[0], args[1])),
+        "of" => {
+            let (year, month, day) = (args[0], args[1], args[2]);
+            if !(1..=12).contains(&month) {
+                let message = format!(
+                    "Invalid value for MonthOfYear (valid values 1 - 12): {}",
+                    month
+                );
+                return Err(date_time(&message));
+            }
+            if !(1..=31).contains(&day) {
+                let message = format!(
+                    "Invalid value for DayOfMonth (valid values 1 - 28/31): {}",
+                    day
+                );
+                return Err(date_time(&message));
+            }
+            let (month, day) = (month as u8, day as u8);
+            if day > days_in_month(year, month) {
+                let month_name = MONTH_NAMES[month as usize - 1];
+                let message = if month == 2 && day == 29 {
+                    format!(
+                        "Invalid date '{} 29' as '{}' is not a leap year",
+                        month_name, year
+                    )
+                } else {
+                    format!("Invalid date '{} {}'", month_name.to_uppercase(), day)
+                };
+                return Err(date_time(&message));
+            }
+            return Ok(vec![vm.try_alloc(HeapObject::LocalDate {
+                year,
+                month,
+                day,
+            })?]);
+        }
+        _ => {}
+    }
+
+    let (year, month, day) = get(vm, args[0]);
+    let days = days_from_civil(year, month, day);
+    Ok(match name {
+        "plusDays" => from_days(vm, days + slots_to_long(args[1], args[2]))?,
+        "minusDays" => from_days(vm, days - slots_to_long(args[1], args[2]))?,
+        "getYear" => vec![year],
+        "getMonthValue" => vec![month as i32],
+        "getDayOfMonth" => vec![day as i32],
+        "getDayOfYear" => vec![(days - days_from_civil(year, 1, 1) + 1) as i32],
+        "isLeapYear" => vec![is_leap_year(year) as i32],
+        "lengthOfMonth" => vec![days_in_month(year, month) as i32],
+        "toEpochDay" => long_to_slots(days).to_vec(),
+        "isBefore" | "isAfter" | "isEqual" | "compareTo" | "equals" => {
+            let other = match args[1] {
+                0 if name == "equals" => return Ok(vec![0]),
+                other => get(vm, other),
+            };
+            let ordering = (year, month, day).cmp(&other);
+            vec![match name {
+                "isBefore" => ordering.is_lt() as i32,
+                "isAfter" => ordering.is_gt() as i32,
+                "compareTo" => ordering as i32,
+                _ => ordering.is_eq() as i32,
+            }]
+        }
+        "toString" => {
+            let text = format_local_date(year, month, day);
+            vec![vm.try_alloc(HeapObject::String(text))?]
+        }
+        _ => panic!("Unsupported native method: java/time/LocalDate.{}", name),
+    })
+}
+
+/// Execute a method of `java.util.Date`.
+/// The deprecated calendar getters work on the date in UTC, like `LocalDate`.
+fn native_date(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    if name == "<init>" {
+        let millis = if descriptor == "(J)V" {
+            slots_to_long(args[1], args[2])
+        } else {
+            current_time_millis()
+        };
+        *vm.get_object_mut(args[0]) = HeapObject::Date(millis);
+        return Ok(vec![]);
+    }
+
+    let get = |vm: &Vm, reference: i32| match *vm.get_object(reference) {
+        HeapObject::Date(millis) => millis,
+        _ => panic!("Expected a Date"),
+    };
+    let millis = get(vm, args[0]);
+    let days = millis.div_euclid(86_400_000);
+    let (year, month, day) = civil_from_days(days);
+    Ok(match name {
+        "getTime" => long_to_slots(millis).to_vec(),
+        "getYear" => vec![year - 1900],
+        "getMonth" => vec![month as i32 - 1],
+        "getDate" => vec![day as i32],
+        // 0 is Sunday, 1970-01-01 was a Thursday
+        "getDay" => vec![(days + 4).rem_euclid(7) as i32],
+        "before" => vec![(millis < get(vm, args[1])) as i32],
+        "after" => vec![(millis > get(vm, args[1])) as i32],
+        "compareTo" => vec![millis.cmp(&get(vm, args[1])) as i32],
+        "equals" => {
+            let equal = args[1] != 0
+                && matches!(*vm.get_object(args[1]), HeapObject::Date(other) if other == millis);
+            vec![equal as i32]
+        }
+        "toString" => vec![vm.try_alloc(HeapObject::String(format_date(millis)))?],
+        _ => panic!("Unsupported native method: java/util/Date.{}", name),
+    })
+}
+
+/// The multiplier of the linear congruential generator of `java.util.Random`.
+const RANDOM_MULTIPLIER: i64 = 0x5_DEEC_E66D;
+/// The mask keeping the 48 bits of the state of `java.util.Random`.
+const RANDOM_MASK: i64 = (1 << 48) - 1;
+
+/// Advance the generator of a `java.util.Random` like its `next` method.
+///
+/// # Parameters
+/// * `seed` - the state of the generator
+/// * `bits` - the number of random bits, at most 32
+/// # Return
+/// the highest `bits` bits of the new state
+fn random_next(seed: &mut i64, bits: u32) -> i32 {
+    *seed = (seed.wrapping_mul(RANDOM_MULTIPLIER).wrapping_add(0xB)) & RANDOM_MASK;
+    (*seed >> (48 - bits)) as i32
+}
+
+/// Compute the natural logarithm like `StrictMath.log`, with the algorithm of fdlibm,
+/// whose result `f64::ln` can differ from in the last bit.
+fn strict_log(x: f64) -> f64 {
+    let ln2_hi = f64::from_bits(0x3FE6_2E42_FEE0_0000);
+    let ln2_lo = f64::from_bits(0x3DEA_39EF_3579_3C76);
+    let lg = [
+        f64::from_bits(0x3FE5_5555_5555_5593),
+        f64::from_bits(0x3FD9_9999_9997_FA04),
+        f64::from_bits(0x3FD2_4924_9422_9359),
+        f64::from_bits(0x3FCC_71C5_1D8E_78AF),
+        f64::from_bits(0x3FC7_4664_96CB_03DE),
+        f64::from_bits(0x3FC3_9A09_D078_C69F),
+        f64::from_bits(0x3FC2_F112_DF3E_5244),
+    ];
+    let mut x = x;
+    let mut high = (x.to_bits() >> 32) as i32;
+    let mut k = 0;
+    if high < 0x0010_0000 {
+        if x == 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        if x < 0.0 {
+            return f64::NAN;
+        }
+        // scale up a subnormal number
+        k -= 54;
+        x *= f64::from_bits(0x4350_0000_0000_0000);
+        high = (x.to_bits() >> 32) as i32;
+    }
+    if high >= 0x7FF0_0000 {
+        return x + x;
+    }
+    k += (high >> 20) - 1023;
+    high &= 0x000F_FFFF;
+    // normalize x into [sqrt(2)/2, sqrt(2)) and compute log(1 + f) for f = x - 1
+    let i = (high + 0x95F64) & 0x10_0000;
+    let bits = ((high | (i ^ 0x3FF0_0000)) as u64) << 32 | (x.to_bits() & 0xFFFF_FFFF);
+    x = f64::from_bits(bits);
+    k += i >> 20;
+    let f = x - 1.0;
+    let dk = k as f64;
+    if (0x000F_FFFF & (2 + high)) < 3 {
+        // |f| < 2^-20
+        if f == 0.0 {
+            return dk * ln2_hi + dk * ln2_lo;
+        }
+        let r = f * f * (0.5 - 0.333_333_333_333_333_3 * f);
+        return if k == 0 {
+            f - r
+        } else {
+            dk * ln2_hi - ((r - dk * ln2_lo) - f)
+        };
+    }
+    let s = f / (2.0 + f);
+    let z = s * s;
+    let w = z * z;
+    let t1 = w * (lg[1] + w * (lg[3] + w * lg[5]));
+    let t2 = z * (lg[0] + w * (lg[2] + w * (lg[4] + w * lg[6])));
+    let r = t2 + t1;
+    if ((high - 0x6147A) | (0x6B851 - high)) > 0 {
+        let hfsq = 0.5 * f * f;
+        if k == 0 {
+            f - (hfsq - s * (hfsq + r))
+        } else {
+            dk * ln2_hi - ((hfsq - (s * (hfsq + r) + dk * ln2_lo)) - f)
+        }
+    } else if k == 0 {
+        f - s * (f - r)
+    } else {
+        dk * ln2_hi - ((s * (f - r) - dk * ln2_lo) - f)
+    }
+}
+
+/// Get a double in [0, 1) from the generator of a `java.util.Random` like `nextDouble`.
+fn random_next_double(seed: &mut i64) -> f64 {
+    let high = (random_next(seed, 26) as i64) << 27;
+    (high + random_next(seed, 27) as i64) as f64 * (1.0 / (1i64 << 53) as f64)
+}
+
+/// Execute a method of `java.util.Random`.
+/// A seeded generator produces the same values as Java's, so that programs are reproducible.
+fn native_random(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    if name == "<init>" {
+        let seed = if descriptor == "(J)V" {
+            slots_to_long(args[1], args[2])
+        } else {
+            // FIXME: Java also mixes in a counter, so that generators created at the same time differ
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as i64)
+        };
+        *vm.get_object_mut(args[0]) = HeapObject::Random {
+            seed: (seed ^ RANDOM_MULTIPLIER) & RANDOM_MASK,
+            next_next_gaussian: None,
+        };
+        return Ok(vec![]);
+    }
+
+    // the bytes are filled in once the generator is no longer borrowed
+    let mut bytes = match name {
+        "nextBytes" if args[1] == 0 => return Err(null_array()),
+        "nextBytes" => match vm.get_object(args[1]) {
+            HeapObject::ByteArray(values) => values.len(),
+            _ => panic!("Expected a byte[]"),
+        },
+        _ => 0,
+    };
+    let (seed, next_next_gaussian) = match vm.get_object_mut(args[0]) {
+        HeapObject::Random {
+            seed,
+            next_next_gaussian,
+        } => (seed, next_next_gaussian),
+        _ => panic!("Expected a Random"),
+    };
+    Ok(match (name, descriptor) {
+        ("nextInt", "()I") => vec![random_next(seed, 32)],
+        ("nextInt", "(I)I") => {
+            let bound = args[1];
+            if bound <= 0 {
+                return Err(RuntimeError::exception(
+                    "java/lang/IllegalArgumentException",
+                    "bound must be positive",
+                ));
+            }
+            let mut value = random_next(seed, 31);
+            let mask = bound - 1;
+            if bound & mask == 0 {
+                // a power of 2 takes the highest bits
+                value = ((bound as i64 * value as i64) >> 31) as i32;
+            } else {
+                // reject the values of the last, incomplete range of `bound` numbers
+                let mut candidate = value;
+                loop {
+                    value = candidate % bound;
+                    if candidate.wrapping_sub(value).wrapping_add(mask) >= 0 {
+                        break;
+                    }
+                    candidate = random_next(seed, 31);
+                }
+            }
+            vec![value]
+        }
+        ("nextLong", _) => {
+            let high = (random_next(seed, 32) as i64) << 32;
+            long_to_slots(high.wrapping_add(random_next(seed, 32) as i64)).to_vec()
+        }
+        ("nextBoolean", _) => vec![(random_next(seed, 1) != 0) as i32],
+        ("nextDouble", _) => double_to_slots(random_next_double(seed)).to_vec(),
+        ("nextGaussian", _) => {
+            let value = match next_next_gaussian.take() {
+                Some(value) => value,
+                None => {
+                    // the polar method of Box, Muller and Marsaglia gives a pair of values
+                    let (v1, v2, s) = loop {
+                        let v1 = 2.0 * random_next_double(seed) - 1.0;
+                        let v2 = 2.0 * random_next_double(seed) - 1.0;
+                        let s = v1 * v1 + v2 * v2;
+                        if s < 1.0 && s != 0.0 {
+                            break (v1, v2, s);
+                        }
+                    };
+                    let multiplier = (-2.0 * strict_log(s) / s).sqrt();
+                    *next_next_gaussian = Some(v2 * multiplier);
+                    v1 * multiplier
+                }
+            };
+            double_to_slots(value).to_vec()
+        }
+        ("nextBytes", _) => {
+            // each int gives 4 bytes, lowest first
+            let mut values = Vec::with_capacity(bytes);
+            while bytes > 0 {
+                let mut value = random_next(seed, 32);
+                for _ in 0..bytes.min(4) {
+                    values.push(value as i8);
+                    value >>= 8;
+                }
+                bytes -= bytes.min(4);
+            }
+            *vm.get_object_mut(args[1]) = HeapObject::ByteArray(values);
+            vec![]
+        }
+        _ => panic!("Unsupported native method: java/util/Random.{}", name),
+    })
+}
+
+/// Execute a method of `java.lang.StringBuilder`.
+/// Indexes count characters rather than UTF-16 code units.
+fn native_string_builder(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    // the text inserted by the constructor, `append` or `insert`
+    let text: Vec<char> = match (name, descriptor) {
+        ("<init>", "()V") | ("<init>", "(I)V") => vec![],
+        ("<init>", _) | ("append", _) => format_argument(vm, descriptor, &args[1..])
+            .chars()
+            .collect(),
+        // skip the offset in the descriptor as well
+        ("insert", _) => {
+            let descriptor = format!("({}", &descriptor[2..]);
+            format_argument(vm, &descriptor, &args[2..])
+                .chars()
+                .collect()
+        }
+        _ => vec![],
+    };
+    let chars = match vm.get_object_mut(args[0]) {
+        HeapObject::StringBuilder(chars) => chars,
+        _ => panic!("Expected a StringBuilder"),
+    };
+    let length = chars.len();
+    let out_of_bounds = |kind: &str, index: i32| {
+        let message = format!("{} {}, length {}", kind, index, length);
+        RuntimeError::exception("java/lang/StringIndexOutOfBoundsException", &message)
+    };
+    // check an index of an existing character
+    let check = |index: i32| {
+        if index < 0 || index as usize >= length {
+            return Err(out_of_bounds("index", index));
+        }
+        Ok(index as usize)
+    };
+
+    Ok(match name {
+        "<init>" => {
+            *chars = text;
+            vec![]
+        }
+        // these return the builder itself
+        "append" => {
+            chars.extend(text);
+            vec![args[0]]
+        }
+        "insert" => {
+            if args[1] < 0 || args[1] as usize > length {
+                return Err(out_of_bounds("offset", args[1]));
+            }
+            let offset = args[1] as usize;
+            chars.splice(offset..offset, text);
+            vec![args[0]]
+        }
+        "reverse" => {
+            chars.reverse();
+            vec![args[0]]
+        }
+        "deleteCharAt" => {
+            chars.remove(check(args[1])?);
+            vec![args[0]]
+        }
+        "length" => vec![length as i32],
+        "charAt" => vec![chars[check(args[1])?] as i32],
+        "setCharAt" => {
+            chars[check(args[1])?] = char::from_u32(args[2] as u32).unwrap_or_default();
+            vec![]
+        }
+        "indexOf" => {
+            let target: Vec<char> = get_string(vm, args[1]).chars().collect();
+            let chars = match vm.get_object(args[0]) {
+                HeapObject::StringBuilder(chars) => chars,
+                _ => unreachable!(),
+            };
+            let index = (0..=chars.len().saturating_sub(target.len()))
+                .find(|&start| chars[start..].starts_with(&target))
+                .map_or(-1, |index| index as i32);
+            vec![index]
+        }
+        "toString" => {
+            let text = chars.iter().collect();
+            vec![vm.try_alloc(HeapObject::String(text))?]
+        }
+        _ => panic!(
+            "Unsupported native method: java/lang/StringBuilder.{}",
+            name
+        ),
+    })
+}
+
+/// Print an exception which ended a thread the way java does, without the stack trace,
+/// followed by a "Caused by:" line for each of its causes.
+///
+/// # Parameters
+/// * `thread` - the name of the thread, e.g. "main"
+/// * `exception` - the exception, a `RuntimeError::Exception`
+fn report_uncaught_exception(thread: &str, exception: &RuntimeError) {
+    eprintln!("Exception in thread \"{}\" {}", thread, exception);
+    let mut current = exception;
+    while let RuntimeError::Exception {
+        cause: Some(cause), ..
+    } = current
+    {
+        eprintln!("Caused by: {}", cause);
+        current = cause;
+    }
+}
+
+/// Hands a `Vm` between the OS threads running the threads of a program in threaded mode,
+/// see `Vm::set_threading`. Only the thread whose turn it is holds the `Vm`, so the
+/// interpreter needs no locking of its own.
+#[derive(Default)]
+pub struct Scheduler {
+    state: Mutex<SchedulerState>,
+    /// Signaled whenever the `Vm` is handed over
+    turn: Condvar,
+}
+
+/// The threads waiting for their turn, guarded by `Scheduler::state`.
+#[derive(Default)]
+struct SchedulerState {
+    /// The `Vm` while no thread runs it
+    vm: Option<Vm>,
+    /// The thread the `Vm` is handed to
+    next: Option<i32>,
+    /// The threads which can run, in the order they get their turn
+    ready: VecDeque<i32>,
+    /// The threads waiting for something another thread does, e.g. in `join`.
+    /// They get ready again once another thread has run.
+    waiting: Vec<i32>,
+    /// The number of threads in `Thread.sleep`
+    sleeping: usize,
+    /// The error which aborted a thread other than main, e.g. `RuntimeError::Timeout`;
+    /// it is passed to main, which ends the program with it
+    aborted: Option<RuntimeError>,
+}
+
+impl SchedulerState {
+    /// Pick the thread the `Vm` goes to if it was released and nobody was picked yet.
+    fn hand_over(&mut self) {
+        if self.vm.is_some() && self.next.is_none() {
+            self.next = if self.aborted.is_some() {
+                Some(MAIN_THREAD)
+            } else {
+                self.ready.pop_front()
+            };
+        }
+    }
+}
+
+/// Why a thread releases the `Vm`, see `park`.
+enum Park {
+    /// It ran for a while, the other ready threads get their turn first
+    Yield,
+    /// It waits for another thread, `progressed` if it ran since it last waited
+    Wait { progressed: bool },
+    /// It sleeps, and gets ready again afterwards
+    Sleep(Duration),
+    /// It has ended
+    Finish,
+}
+
+/// Let the other threads run in threaded mode, then wait for the turn of the running one.
+/// Without a scheduler, this returns at once.
+///
+/// # Parameters
+/// * `vm` - the state of the program, moved to the thread whose turn it is meanwhile
+/// * `park` - why the thread releases the `Vm`
+/// # Return
+/// `RuntimeError::DeadlockDetected` if the thread waits and no other thread can run;
+/// in main, the error which aborted another thread
+fn park(vm: &mut Vm, park: Park) -> Result<(), RuntimeError> {
+    let scheduler = match &vm.scheduler {
+        Some(scheduler) => scheduler.clone(),
+        None => return Ok(()),
+    };
+    let me = vm.current_thread;
+    let mut state = scheduler.state.lock().unwrap();
+    // a thread which ran may have done what the waiting threads wait for
+    if !matches!(park, Park::Wait { progressed: false }) {
+        let waiting = std::mem::take(&mut state.waiting);
+        state.ready.extend(waiting);
+    }
+    match park {
+        Park::Yield if state.ready.is_empty() => return Ok(()),
+        Park::Wait { .. } if state.ready.is_empty() && state.sleeping == 0 => {
+            return Err(RuntimeError::DeadlockDetected)
+        }
+        Park::Yield => state.ready.push_back(me),
+        Park::Wait { .. } => state.waiting.push(me),
+        Park::Sleep(_) => state.sleeping += 1,
+        Park::Finish => {}
+    }
+
+    if !matches!(park, Park::Finish) {
+        let frames = (vm.call_depth, std::mem::take(&mut vm.frame_roots));
+        vm.parked_frames.insert(me, frames);
+    }
+    state.vm = Some(std::mem::take(vm));
+    state.hand_over();
+    scheduler.turn.notify_all();
+    match park {
+        Park::Finish => return Ok(()),
+        Park::Sleep(duration) => {
+            drop(state);
+            std::thread::sleep(duration);
+            state = scheduler.state.lock().unwrap();
+            state.sleeping -= 1;
+            state.ready.push_back(me);
+            state.hand_over();
+            scheduler.turn.notify_all();
+        }
+        _ => {}
+    }
+    // the placeholder left by `take` knows neither the scheduler nor the thread
+    vm.scheduler = Some(scheduler.clone());
+    vm.current_thread = me;
+    take_turn(vm, &scheduler, state)
+}
+
+/// Wait until the `Vm` is handed to the running thread, and take it.
+///
+/// # Parameters
+/// * `vm` - a placeholder, replaced by the `Vm`; its `current_thread` is the running thread
+/// * `scheduler` - the scheduler of the `Vm`
+/// * `state` - the locked state of `scheduler`
+/// # Return
+/// in main, the error which aborted another thread
+fn take_turn(
+    vm: &mut Vm,
+    scheduler: &Scheduler,
+    mut state: std::sync::MutexGuard<SchedulerState>,
+) -> Result<(), RuntimeError> {
+    let me = vm.current_thread;
+    while state.next != Some(me) || state.vm.is_none() {
+        state = scheduler.turn.wait(state).unwrap();
+    }
+    state.next = None;
+    *vm = state.vm.take().unwrap();
+    let (call_depth, frame_roots) = vm.parked_frames.remove(&me).unwrap_or_default();
+    vm.call_depth = call_depth;
+    vm.frame_roots = frame_roots;
+    vm.current_thread = me;
+    match state.aborted.take() {
+        Some(error) if me == MAIN_THREAD => Err(error),
+        aborted => {
+            state.aborted = aborted;
+            Ok(())
+        }
+    }
+}
+
+/// Wait until another thread has done something, e.g. ended or counted down a latch.
+///
+/// # Parameters
+/// * `vm` - the state of the program
+/// * `ready` - whether the wait is over
+/// # Return
+/// `RuntimeError::DeadlockDetected` if no other thread can do it, always so outside of
+/// threaded mode, since started threads have already ended there
+fn wait_until(vm: &mut Vm, ready: impl Fn(&Vm) -> bool) -> Result<(), RuntimeError> {
+    let mut progressed = true;
+    while !ready(vm) {
+        if vm.scheduler.is_none() {
+            return Err(RuntimeError::DeadlockDetected);
+        }
+        park(vm, Park::Wait { progressed })?;
+        progressed = false;
+    }
+    Ok(())
+}
+
+/// Whether a thread was started in threaded mode and has not ended yet.
+fn is_thread_alive(vm: &Vm, thread: i32) -> bool {
+    matches!(
+        vm.get_object(thread),
+        HeapObject::Thread { alive: true, .. }
+    )
+}
+
+/// Run a thread started in threaded mode, on the OS thread spawned for it by `Thread.start`.
+///
+/// # Parameters
+/// * `scheduler` - the scheduler of the `Vm`
+/// * `thread` - the reference to the `Thread` object
+fn run_thread(scheduler: Arc<Scheduler>, thread: i32) {
+    let mut vm = Vm {
+        scheduler: Some(scheduler.clone()),
+        current_thread: thread,
+        ..Vm::default()
+    };
+    let state = scheduler.state.lock().unwrap();
+    if take_turn(&mut vm, &scheduler, state).is_err() {
+        unreachable!("only main is passed the errors of other threads");
+    }
+
+    let (target, thread_name) = match vm.get_object(thread) {
+        HeapObject::Thread { target, name, .. } => (*target, name.clone()),
+        _ => panic!("Expected a Thread"),
+    };
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        if target == 0 {
+            return Ok(vec![]);
+        }
+        invoke_functional_interface(&mut vm, target, &[])
+    }));
+    match result {
+        // the panic hook has reported it, and the other threads cannot go on without the `Vm`
+        Err(_) => std::process::exit(101),
+        // an exception ends the thread but not the program
+        Ok(Err(exception @ RuntimeError::Exception { .. })) => {
+            report_uncaught_exception(&thread_name, &exception)
+        }
+        Ok(Err(error)) => {
+            let mut state = scheduler.state.lock().unwrap();
+            state.aborted = Some(error);
+            state.ready.clear();
+            state.waiting.clear();
+        }
+        Ok(Ok(_)) => {}
+    }
+    if let HeapObject::Thread { alive, .. } = vm.get_object_mut(thread) {
+        *alive = false;
+    }
+    // nothing is waited for when finishing
+    let _ = park(&mut vm, Park::Finish);
+}
+
+/// Execute a method of `java.lang.Thread`.
+/// Unless threaded mode is on, see `Vm::set_threading`, `start` runs the thread to
+/// completion before returning and `join` has nothing to wait for. A thread waiting for
+/// something the starting thread only does later, e.g. `await` on a `CountDownLatch`
+/// counted down after `start`, then fails with `RuntimeError::DeadlockDetected`.
+fn native_thread(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    if name == "sleep" {
+        let millis = slots_to_long(args[0], args[1]);
+        let duration = Duration::from_millis(millis.max(0) as u64);
+        if vm.scheduler.is_some() {
+            park(vm, Park::Sleep(duration))?;
+        } else {
+            std::thread::sleep(duration);
+        }
+        return Ok(vec![]);
+    }
+    if name == "<init>" {
+        // unnamed threads are numbered in the order they are created
+        let thread_name = match descriptor {
+            "(Ljava/lang/String;)V" => format_object(vm, args[1]),
+            "(Ljava/lang/Runnable;Ljava/lang/String;)V" => format_object(vm, args[2]),
+            _ => {
+                vm.threads_created += 1;
+                format!("Thread-{}", vm.threads_created - 1)
+            }
+        };
+        let target = if descriptor.starts_with("(Ljava/lang/Runnable;") {
+            args[1]
+        } else {
+            0
+        };
+        *vm.get_object_mut(args[0]) = HeapObject::Thread {
+            target,
+            name: thread_name,
+            started: false,
+            alive: false,
+        };
+        return Ok(vec![]);
+    }
+
+    let (target, started) = match vm.get_object_mut(args[0]) {
+        HeapObject::Thread {
+            target, started, ..
+        } => (*target, started),
+        _ => panic!("Expected a Thread"),
+    };
+    Ok(match name {
+        "start" => {
+            if *started {
+                return Err(RuntimeError::Exception {
+                    class: "java/lang/IllegalThreadStateException".to_string(),
+                    message: None,
+                    cause: None,
+                });
+            }
+            *started = true;
+            if let Some(scheduler) = vm.scheduler.clone() {
+                start_thread(vm, scheduler, args[0])?;
+            } else if target != 0 {
+                // an exception ends the thread but not the program
+                match invoke_functional_interface(vm, target, &[]) {
+                    Err(exception @ RuntimeError::Exception { .. }) => {
+                        let thread_name = match vm.get_object(args[0]) {
+                            HeapObject::Thread { name, .. } => name.clone(),
+                            _ => unreachable!(),
+                        };
+                        report_uncaught_exception(&thread_name, &exception);
+                    }
+                    result => {
+                        result?;
+                    }
+                }
+            }
+            vec![]
+        }
+        "run" => {
+            if target != 0 {
+                invoke_functional_interface(vm, target, &[])?;
+            }
+            vec![]
+        }
+        "join" => {
+            let thread = args[0];
+            wait_until(vm, |vm| !is_thread_alive(vm, thread))?;
+            if let Some(handle) = vm.thread_handles.remove(&thread) {
+                // a thread which panicked has already ended the process
+                let _ = handle.join();
+            }
+            vec![]
+        }
+        "isAlive" => vec![is_thread_alive(vm, args[0]) as i32],
+        "getName" => match vm.get_object(args[0]) {
+            HeapObject::Thread { name, .. } => {
+                let name = name.clone();
+                vec![vm.try_alloc(HeapObject::String(name))?]
+            }
+            _ => unreachable!(),
+        },
+        "setName" => {
+            let new_name = format_object(vm, args[1]);
+            if let HeapObject::Thread { name, .. } = vm.get_object_mut(args[0]) {
+                *name = new_name;
+            }
+            vec![]
+        }
+        _ => panic!("Unsupported native method: java/lang/Thread.{}", name),
+    })
+}
+
+/// Spawn the OS thread running a started thread in threaded mode.
+/// It gets its turn once the running thread releases the `Vm`.
+///
+/// # Parameters
+/// * `vm` - the state of the program
+/// * `scheduler` - the scheduler of `vm`
+/// * `thread` - the reference to the `Thread` object
+/// # Return
+/// `OutOfMemoryError` if the OS thread cannot be created
+fn start_thread(vm: &mut Vm, scheduler: Arc<Scheduler>, thread: i32) -> Result<(), RuntimeError> {
+    let thread_name = match vm.get_object_mut(thread) {
+        HeapObject::Thread { name, alive, .. } => {
+            *alive = true;
+            name.clone()
+        }
+        _ => unreachable!(),
+    };
+    // the thread starts with only its own object as a root of the collector
+    vm.parked_frames.insert(thread, (0, vec![vec![thread]]));
+    let spawned = std::thread::Builder::new()
+        .name(thread_name)
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn({
+            let scheduler = scheduler.clone();
+            move || run_thread(scheduler, thread)
+        });
+    match spawned {
+        Ok(handle) => {
+            vm.thread_handles.insert(thread, handle);
+            scheduler.state.lock().unwrap().ready.push_back(thread);
+            Ok(())
+        }
+        Err(_) => {
+            vm.parked_frames.remove(&thread);
+            if let HeapObject::Thread { alive, .. } = vm.get_object_mut(thread) {
+                *alive = false;
+            }
+            Err(RuntimeError::exception(
+                "java/lang/OutOfMemoryError",
+                "unable to create native thread",
+            ))
+        }
+    }
+}
+
+/// Execute a method of `java.util.concurrent.CountDownLatch`.
+/// Unless threaded mode is on, started threads have already finished, so `await`
+/// either returns at once or never.
+fn native_count_down_latch(
+    vm: &mut Vm,
+    name: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    if name == "await" {
+        let latch = args[0];
+        wait_until(vm, |vm| {
+            matches!(vm.get_object(latch), HeapObject::CountDownLatch(0))
+        })?;
+        return Ok(vec![]);
+    }
+    let count = match vm.get_object_mut(args[0]) {
+        HeapObject::CountDownLatch(count) => count,
+        _ => panic!("Expected a CountDownLatch"),
+    };
+    Ok(match name {
+        "<init>" => {
+            if args[1] < 0 {
+                return Err(RuntimeError::exception(
+                    "java/lang/IllegalArgumentException",
+                    "count < 0",
+                ));
+            }
+            *count = args[1] as i64;
+            vec![]
+        }
+        "countDown" => {
+            *count = (*count - 1).max(0);
+            vec![]
+        }
+        "getCount" => long_to_slots(*count).to_vec(),
+        _ => panic!(
+            "Unsupported native method: java/util/concurrent/CountDownLatch.{}",
+            name
+        ),
+    })
+}
+
+/// Execute a method of `java.lang.Integer`, the box of an int.
+fn native_integer(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    if name == "valueOf" {
+        return Ok(vec![vm.try_alloc(HeapObject::Integer(args[0]))?]);
+    }
+    // the static bit manipulations of an int
+    let bits = args[0];
+    let result = match name {
+        "bitCount" => Some(bits.count_ones() as i32),
+        "highestOneBit" => Some(
+            0x8000_0000u32
+                .checked_shr(bits.leading_zeros())
+                .unwrap_or(0) as i32,
+        ),
+        "lowestOneBit" => Some(bits & bits.wrapping_neg()),
+        "numberOfLeadingZeros" => Some(bits.leading_zeros() as i32),
+        "numberOfTrailingZeros" => Some(bits.trailing_zeros() as i32),
+        "reverse" => Some(bits.reverse_bits()),
+        "reverseBytes" => Some(bits.swap_bytes()),
+        "signum" => Some(bits.signum()),
+        _ => None,
+    };
+    if let Some(result) = result {
+        return Ok(vec![result]);
+    }
+
+    let get = |vm: &Vm, reference: i32| match *vm.get_object(reference) {
+        HeapObject::Integer(value) => value,
+        _ => panic!("Expected an Integer"),
+    };
+    let value = get(vm, args[0]);
+    Ok(match name {
+        "intValue" | "hashCode" => vec![value],
+        "compareTo" => vec![value.cmp(&get(vm, args[1])) as i32],
+        "equals" => {
+            let equal = args[1] != 0
+                && matches!(*vm.get_object(args[1]), HeapObject::Integer(other) if other == value);
+            vec![equal as i32]
+        }
+        "toString" => vec![vm.try_alloc(HeapObject::String(value.to_string()))?],
+        _ => panic!("Unsupported native method: java/lang/Integer.{}", name),
+    })
+}
+
+/// Execute a static method of `java.lang.Long`.
+fn native_long(name: &str, args: &[i32]) -> Vec<i32> {
+    let bits = slots_to_long(args[0], args[1]);
+    // the counts fit into an int
+    match name {
+        "bitCount" => vec![bits.count_ones() as i32],
+        "numberOfLeadingZeros" => vec![bits.leading_zeros() as i32],
+        "numberOfTrailingZeros" => vec![bits.trailing_zeros() as i32],
+        _ => panic!("Unsupported native method: java/lang/Long.{}", name),
+    }
+}
+
+/// Execute a static method of `java.lang.Math`.
+/// `Math.PI` and `Math.E` need no support, since javac inlines them as `ldc2_w`.
+fn native_math(name: &str, args: &[i32]) -> Vec<i32> {
+    let arg = |index: usize| slots_to_double(args[2 * index], args[2 * index + 1]);
+    // like Java, the logarithm of 0 is -Infinity and that of a negative number NaN
+    let result = match name {
+        "log" => arg(0).ln(),
+        "log10" => arg(0).log10(),
+        "exp" => arg(0).exp(),
+        "sin" => arg(0).sin(),
+        "cos" => arg(0).cos(),
+        "tan" => arg(0).tan(),
+        "atan2" => arg(0).atan2(arg(1)),
+        _ => panic!("Unsupported native method: java/lang/Math.{}", name),
+    };
+    double_to_slots(result).to_vec()
+}
+
+/// Compare two objects by their natural ordering, i.e. their `compareTo` methods.
+fn compare_natural(vm: &Vm, a: i32, b: i32) -> Result<std::cmp::Ordering, RuntimeError> {
+    if a == 0 || b == 0 {
+        return Err(RuntimeError::Exception {
+            class: "java/lang/NullPointerException".to_string(),
+            message: None,
+            cause: None,
+        });
+    }
+    Ok(match (vm.get_object(a), vm.get_object(b)) {
+        // Java strings compare their UTF-16 code units
+        (HeapObject::String(a), HeapObject::String(b)) => a.encode_utf16().cmp(b.encode_utf16()),
+        (HeapObject::Integer(a), HeapObject::Integer(b)) => a.cmp(b),
+        (HeapObject::BigInteger(a), HeapObject::BigInteger(b)) => a.cmp(b),
+        (HeapObject::BigDecimal(a), HeapObject::BigDecimal(b)) => a.compare(b),
+        (
+            &HeapObject::LocalDate { year, month, day },
+            &HeapObject::LocalDate {
+                year: other_year,
+                month: other_month,
+                day: other_day,
+            },
+        ) => (year, month, day).cmp(&(other_year, other_month, other_day)),
+        (HeapObject::Date(a), HeapObject::Date(b)) => a.cmp(b),
+        _ => panic!("Cannot compare objects"),
+    })
+}
+
+/// Find a key in a `TreeMap` by binary search.
+///
+/// # Return
+/// the index of the entry with the key, or the index to insert it at;
+/// `RuntimeError::Exception` if comparing the keys threw
+fn search_tree_map(vm: &mut Vm, map: i32, key: i32) -> Result<Result<usize, usize>, RuntimeError> {
+    let (length, comparator) = match vm.get_object(map) {
+        HeapObject::TreeMap {
+            entries,
+            comparator,
+        } => (entries.len(), *comparator),
+        _ => panic!("Expected a TreeMap"),
+    };
+    // the natural ordering rejects null even when there is nothing to compare with
+    if comparator == 0 && key == 0 {
+        compare_natural(vm, key, key)?;
+    }
+    let (mut low, mut high) = (0, length);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let middle_key = match vm.get_object(map) {
+            HeapObject::TreeMap { entries, .. } => entries[middle].0,
+            _ => unreachable!(),
+        };
+        let ordering = if comparator == 0 {
+            compare_natural(vm, middle_key, key)?
+        } else {
+            invoke_functional_interface(vm, comparator, &[middle_key, key])?[0].cmp(&0)
+        };
+        match ordering {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => return Ok(Ok(middle)),
+        }
+    }
+    Ok(Err(low))
+}
+
+/// Get the entries and the comparator of a `TreeMap`.
+fn tree_map_entries(vm: &mut Vm, map: i32) -> (&mut Vec<(i32, i32)>, i32) {
+    match vm.get_object_mut(map) {
+        HeapObject::TreeMap {
+            entries,
+            comparator,
+        } => (entries, *comparator),
+        _ => panic!("Expected a TreeMap"),
+    }
+}
+
+/// Execute a method of `java.util.TreeMap`, or of a map interface it implements.
+/// The views returned by `headMap`, `tailMap` and `subMap` are copies of the entries.
+fn native_tree_map(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    let no_element = || RuntimeError::Exception {
+        class: "java/util/NoSuchElementException".to_string(),
+        message: None,
+        cause: None,
+    };
+    // copy the entries from `from` to `to` into a new map with the same ordering
+    let copy_range = |vm: &mut Vm, from: usize, to: usize| {
+        let (entries, comparator) = tree_map_entries(vm, args[0]);
+        let entries = entries[from..to.max(from)].to_vec();
+        Ok(vec![vm.try_alloc(HeapObject::TreeMap {
+            entries,
+            comparator,
+        })?])
+    };
+
+    Ok(match name {
+        "<init>" => {
+            if descriptor == "(Ljava/util/Comparator;)V" {
+                match vm.get_object_mut(args[0]) {
+                    HeapObject::TreeMap { comparator, .. } => *comparator = args[1],
+                    _ => panic!("Expected a TreeMap"),
+                }
+            }
+            vec![]
+        }
+        "put" => {
+            let index = search_tree_map(vm, args[0], args[1])?;
+            let entries = tree_map_entries(vm, args[0]).0;
+            match index {
+                Ok(index) => vec![std::mem::replace(&mut entries[index].1, args[2])],
+                Err(index) => {
+                    entries.insert(index, (args[1], args[2]));
+                    vec![0]
+                }
+            }
+        }
+        "get" => match search_tree_map(vm, args[0], args[1])? {
+            Ok(index) => vec![tree_map_entries(vm, args[0]).0[index].1],
+            Err(_) => vec![0],
+        },
+        "containsKey" => vec![search_tree_map(vm, args[0], args[1])?.is_ok() as i32],
+        "remove" => match search_tree_map(vm, args[0], args[1])? {
+            Ok(index) => vec![tree_map_entries(vm, args[0]).0.remove(index).1],
+            Err(_) => vec![0],
+        },
+        "firstKey" => vec![
+            tree_map_entries(vm, args[0])
+                .0
+                .first()
+                .ok_or_else(no_element)?
+                .0,
+        ],
+        "lastKey" => vec![
+            tree_map_entries(vm, args[0])
+                .0
+                .last()
+                .ok_or_else(no_element)?
+                .0,
+        ],
+        "size" => vec![tree_map_entries(vm, args[0]).0.len() as i32],
+        "isEmpty" => vec![tree_map_entries(vm, args[0]).0.is_empty() as i32],
+        "clear" => {
+            tree_map_entries(vm, args[0]).0.clear();
+            vec![]
+        }
+        // keys before the given key
+        "headMap" => {
+            let to = search_tree_map(vm, args[0], args[1])?.unwrap_or_else(|index| index);
+            copy_range(vm, 0, to)?
+        }
+        // keys from the given key on
+        "tailMap" => {
+            let from = search_tree_map(vm, args[0], args[1])?.unwrap_or_else(|index| index);
+            let length = tree_map_entries(vm, args[0]).0.len();
+            copy_range(vm, from, length)?
+        }
+        // keys from the first key, and before the second key
+        "subMap" => {
+            let from = search_tree_map(vm, args[0], args[1])?.unwrap_or_else(|index| index);
+            let to = search_tree_map(vm, args[0], args[2])?.unwrap_or_else(|index| index);
+            if from > to {
+                return Err(RuntimeError::exception(
+                    "java/lang/IllegalArgumentException",
+                    "fromKey > toKey",
+                ));
+            }
+            copy_range(vm, from, to)?
+        }
+        "entrySet" | "keySet" | "values" => {
+            let entries = tree_map_entries(vm, args[0]).0.clone();
+            let elements = entries
+                .into_iter()
+                .map(|(key, value)| match name {
+                    "keySet" => Ok(key),
+                    "values" => Ok(value),
+                    _ => vm.try_alloc(HeapObject::MapEntry { key, value }),
+                })
+                .collect::<Result<_, _>>()?;
+            vec![vm.try_alloc(HeapObject::Collection(elements))?]
+        }
+        "toString" => vec![vm.try_alloc(HeapObject::String(format_object(vm, args[0])))?],
+        _ => panic!("Unsupported native method: java/util/TreeMap.{}", name),
+    })
+}
+
+/// Get the entries of a `LinkedHashMap` and whether they are in access order.
+fn linked_hash_map_entries(vm: &mut Vm, map: i32) -> (&mut Vec<(i32, i32)>, bool) {
+    match vm.get_object_mut(map) {
+        HeapObject::LinkedHashMap {
+            entries,
+            access_order,
+        } => (entries, *access_order),
+        _ => panic!("Expected a LinkedHashMap"),
+    }
+}
+
+/// Find a key in a `LinkedHashMap`, comparing the keys with `equals`.
+/// FIXME: keys are not hashed, so a lookup takes linear time
+///
+/// # Return
+/// the index of the entry with the key, if any;
+/// `RuntimeError::Exception` if an `equals` method threw
+fn search_linked_hash_map(vm: &mut Vm, map: i32, key: i32) -> Result<Option<usize>, RuntimeError> {
+    let keys: Vec<_> = linked_hash_map_entries(vm, map)
+        .0
+        .iter()
+        .map(|&(key, _)| key)
+        .collect();
+    for (index, existing) in keys.into_iter().enumerate() {
+        if objects_equal(vm, key, existing)? {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Execute a method of `java.util.LinkedHashMap`, or of a map interface it implements.
+/// The entries keep the order they were inserted in, or in access order the order they
+/// were last accessed in by `get`, `getOrDefault` or `put`.
+fn native_linked_hash_map(
+    vm: &mut Vm,
+    name: &str,
+    descriptor: &str,
+    args: &[i32],
+) -> Result<Vec<i32>, RuntimeError> {
+    // move an accessed entry to the end in access order
+    let access = |vm: &mut Vm, index: usize| {
+        let (entries, access_order) = linked_hash_map_entries(vm, args[0]);
+        if access_order {
+            let entry = entries.remove(index);
+            entries.push(entry);
+            entries.len() - 1
+        } else {
+            index
+        }
+    };
+
+    Ok(match name {
+        "<init>" => {
+            if descriptor.starts_with("(I") && args[1] < 0 {
+                return Err(RuntimeError::exception(
+                    "java/lang/IllegalArgumentException",
+                    &format!("Illegal initial capacity: {}", args[1]),
+                ));
+            }
+            if descriptor.starts_with("(IF") {
+                let load_factor = f32::from_bits(args[2] as u32);
+                if load_factor.is_nan() || load_factor <= 0.0 {
+                    return Err(RuntimeError::exception(
+                        "java/lang/IllegalArgumentException",
+                        &format!("Illegal load factor: {}", format_float(load_factor)),
+                    ));
+                }
+            }
+            // the copy constructor takes the entries in the order of the map
+            let copied = if descriptor == "(Ljava/util/Map;)V" {
+                if args[1] == 0 {
+                    return Err(RuntimeError::Exception {
+                        class: "java/lang/NullPointerException".to_string(),
+                        message: None,
+                        cause: None,
+                    });
+                }
+                match vm.get_object(args[1]) {
+                    HeapObject::TreeMap { entries, .. }
+                    | HeapObject::LinkedHashMap { entries, .. } => entries.clone(),
+                    _ => panic!("Expected a Map"),
+                }
+            } else {
+                vec![]
+            };
+            *vm.get_object_mut(args[0]) = HeapObject::LinkedHashMap {
+                entries: copied,
+                access_order: descriptor == "(IFZ)V" && args[3] != 0,
+            };
+            vec![]
+        }
+        "put" => match search_linked_hash_map(vm, args[0], args[1])? {
+            Some(index) => {
+                let index = access(vm, index);
+                let entries = linked_hash_map_entries(vm, args[0]).0;
+                vec![std::mem::replace(&mut entries[index].1, args[2])]
+            }
+            None => {
+                linked_hash_map_entries(vm, args[0])
+                    .0
+                    .push((args[1], args[2]));
+                vec![0]
+            }
+        },
+        "get" | "getOrDefault" => match search_linked_hash_map(vm, args[0], args[1])? {
+            Some(index) => {
+                let index = access(vm, index);
+                vec![linked_hash_map_entries(vm, args[0]).0[index].1]
+            }
+            None if name == "getOrDefault" => vec![args[2]],
+            None => vec![0],
+        },
+        "containsKey" => vec![search_linked_hash_map(vm, args[0], args[1])?.is_some() as i32],
+        "remove" => match search_linked_hash_map(vm, args[0], args[1])? {
+            Some(index) => vec![linked_hash_map_entries(vm, args[0]).0.remove(index).1],
+            None => vec![0],
+        },
+        "size" => vec![linked_hash_map_entries(vm, args[0]).0.len() as i32],
+        "isEmpty" => vec![linked_hash_map_entries(vm, args[0]).0.is_empty() as i32],
+        "clear" => {
+            linked_hash_map_entries(vm, args[0]).0.clear();
+            vec![]
+        }
+        "entrySet" | "keySet" | "values" => {
+            let entries = linked_hash_map_entries(vm, args[0]).0.clone();
+            let elements = entries
+                .into_iter()
+                .map(|(key, value)| match name {
+                    "keySet" => Ok(key),
+                    "values" => Ok(value),
+                    _ => vm.try_alloc(HeapObject::MapEntry { key, value }),
+                })
+                .collect::<Result<_, _>>()?;
+            vec![vm.try_alloc(HeapObject::Collection(elements))?]
+        }
+        "toString" => vec![vm.try_alloc(HeapObject::String(format_object(vm, args[0])))?],
+        _ => panic!(
+            "Unsupported native method: java/util/LinkedHashMap.{}",
+            name
+        ),
+    })
+}
+
+/// Execute a method of `java.util.Map.Entry`.
+fn native_map_entry(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    let (key, value) = match *vm.get_object(args[0]) {
+        HeapObject::MapEntry { key, value } => (key, value),
+        _ => panic!("Expected a Map.Entry"),
+    };
+    Ok(match name {
+        "getKey" => vec![key],
+        "getValue" => vec![value],
+        "toString" => vec![vm.try_alloc(HeapObject::String(format_object(vm, args[0])))?],
+        _ => panic!("Unsupported native method: java/util/Map$Entry.{}", name),
+    })
+}
+
+/// Execute a method of `java.util.Collection`, on a copy of the elements of a collection.
+fn native_collection(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    let elements = match vm.get_object(args[0]) {
+        HeapObject::Collection(elements) => elements,
+        _ => panic!("Expected a Collection"),
+    };
+    Ok(match name {
+        "size" => vec![elements.len() as i32],
+        "isEmpty" => vec![elements.is_empty() as i32],
+        "iterator" => {
+            let elements = elements.clone();
+            vec![vm.try_alloc(HeapObject::Iterator { elements, next: 0 })?]
+        }
+        "toString" => vec![vm.try_alloc(HeapObject::String(format_object(vm, args[0])))?],
+        _ => panic!("Unsupported native method: java/util/Collection.{}", name),
+    })
+}
+
+/// Execute a method of `java.util.Iterator`.
+fn native_iterator(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    let (elements, next) = match vm.get_object_mut(args[0]) {
+        HeapObject::Iterator { elements, next } => (elements, next),
+        _ => panic!("Expected an Iterator"),
+    };
+    Ok(match name {
+        "hasNext" => vec![(*next < elements.len()) as i32],
+        "next" => {
+            let element = *elements.get(*next).ok_or_else(|| RuntimeError::Exception {
+                class: "java/util/NoSuchElementException".to_string(),
+                message: None,
+                cause: None,
+            })?;
+            *next += 1;
+            vec![element]
+        }
+        _ => panic!("Unsupported native method: java/util/Iterator.{}", name),
+    })
+}
+
+/// Get the string behind a reference, e.g. an argument of a native method.
+fn get_string(vm: &Vm, reference: i32) -> &str {
+    match vm.get_object(reference) {
+        HeapObject::String(s) => s,
+        _ => panic!("Expected a String"),
+    }
+}
+
+/// Execute a method of `java.util.regex.Pattern`.
+fn native_pattern(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    let compile = |vm: &Vm, reference: i32| {
+        Regex::new(get_string(vm, reference)).map_err(|message| {
+            RuntimeError::exception("java/util/regex/PatternSyntaxException", &message)
+        })
+    };
+    Ok(match name {
+        "compile" => {
+            let regex = compile(vm, args[0])?;
+            vec![vm.try_alloc(HeapObject::Pattern(regex))?]
+        }
+        "matches" => {
+            let regex = compile(vm, args[0])?;
+            let input: Vec<char> = get_string(vm, args[1]).chars().collect();
+            vec![regex.full_match(&input).is_some() as i32]
+        }
+        "matcher" => {
+            let regex = match vm.get_object(args[0]) {
+                HeapObject::Pattern(regex) => regex.clone(),
+                _ => panic!("Expected a Pattern"),
+            };
+            let input = get_string(vm, args[1]).chars().collect();
+            vec![vm.try_alloc(HeapObject::Matcher {
+                regex,
+                input,
+                search_from: 0,
+                groups: None,
+            })?]
+        }
+        "pattern" => {
+            let pattern = match vm.get_object(args[0]) {
+                HeapObject::Pattern(regex) => regex.pattern.clone(),
+                _ => panic!("Expected a Pattern"),
+            };
+            vec![vm.try_alloc(HeapObject::String(pattern))?]
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/regex/Pattern.{}",
+            name
+        ),
+    })
+}
+
+/// Replace the matches of a regular expression, like `String.replaceAll`.
+/// In the replacement, `$n` stands for the text of group n and `\` escapes the next character.
+///
+/// # Parameters
+/// * `regex` - the regular expression to find
+/// * `input` - the characters to search
+/// * `replacement` - the text replacing every match
+/// * `all` - whether to replace every match, or only the first one
+/// # Return
+/// the input with the matches replaced;
+/// `IllegalArgumentException` or `IndexOutOfBoundsException` for a bad replacement
+fn replace_matches(
+    regex: &Regex,
+    input: &[char],
+    replacement: &str,
+    all: bool,
+) -> Result<String, RuntimeError> {
+    let illegal =
+        |message: &str| RuntimeError::exception("java/lang/IllegalArgumentException", message);
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut search_from = 0;
+    while let Some(groups) = regex.find_at(input, search_from) {
+        let (start, end) = groups[0].unwrap();
+        result.extend(&input[copied..start]);
+
+        let mut chars = replacement.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => result.push(
+                    chars
+                        .next()
+                        .ok_or_else(|| illegal("character to be escaped is missing"))?,
+                ),
+                '$' => {
+                    let mut group = match chars.next() {
+                        Some(digit @ '0'..='9') => digit as usize - '0' as usize,
+                        // FIXME: named groups are unsupported
+                        Some('{') => {
+                            let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                            return Err(illegal(&format!("No group with name {{{}}}", name)));
+                        }
+                        Some(_) => return Err(illegal("Illegal group reference")),
+                        None => {
+                            return Err(illegal("Illegal group reference: group index is missing"))
+                        }
+                    };
+                    if group > regex.group_count {
+                        let message = format!("No group {}", group);
+                        return Err(RuntimeError::exception(
+                            "java/lang/IndexOutOfBoundsException",
+                            &message,
+                        ));
+                    }
+                    // take more digits as long as they still name a group
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        let longer = group * 10 + digit as usize;
+                        if longer > regex.group_count {
+                            break;
+                        }
+                        group = longer;
+                        chars.next();
+                    }
+                    if let Some((start, end)) = groups[group] {
+                        result.extend(&input[start..end]);
+                    }
+                }
+                c => result.push(c),
+            }
+        }
+
+        copied = end;
+        // after an empty match, the next search starts one character later
+        search_from = if start == end { end + 1 } else { end };
+        if !all {
+            break;
+        }
+    }
+    result.extend(&input[copied..]);
+    Ok(result)
+}
+
+/// Execute a method of `java.util.regex.Matcher`.
+fn native_matcher(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    let (regex, input, search_from, groups) = match vm.get_object_mut(args[0]) {
+        HeapObject::Matcher {
+            regex,
+            input,
+            search_from,
+            groups,
+        } => (regex, input, search_from, groups),
+        _ => panic!("Expected a Matcher"),
+    };
+
+    match name {
+        "find" | "matches" => {
+            let found = if name == "find" {
+                regex.find_at(input, *search_from)
+            } else {
+                regex.full_match(input)
+            };
+            if let Some(found) = &found {
+                // after an empty match, the next search starts one character later
+                let (start, end) = found[0].unwrap();
+                *search_from = if start == end { end + 1 } else { end };
+            }
+            *groups = found;
+            return Ok(vec![groups.is_some() as i32]);
+        }
+        "groupCount" => return Ok(vec![regex.group_count as i32]),
+        _ => {}
+    }
+
+    let groups = groups.as_ref().ok_or_else(|| {
+        RuntimeError::exception("java/lang/IllegalStateException", "No match found")
+    })?;
+    let group = args.get(1).copied().unwrap_or(0);
+    let (start, end) = match groups.get(group as usize) {
+        Some(&position) => position,
+        None => {
+            let message = format!("No group {}", group);
+            return Err(RuntimeError::exception(
+                "java/lang/IndexOutOfBoundsException",
+                &message,
+            ));
+        }
+    }
+    // a group that did not take part in the match is null, or -1 for its positions
+    .map_or((-1, -1), |(start, end)| (start as i32, end as i32));
+    Ok(match name {
+        "start" => vec![start],
+        "end" => vec![end],
+        "group" if start < 0 => vec![0],
+        "group" => {
+            let text = input[start as usize..end as usize].iter().collect();
+            vec![vm.try_alloc(HeapObject::String(text))?]
+        }
+        _ => panic!(
+            "Unsupported native method: java/util/regex/Matcher.{}",
+            name
+        ),
+    })
+}
+
+/// Execute a method of `java.util.Optional`, `java.util.OptionalInt` or `java.util.OptionalDouble`.
+fn native_optional(vm: &mut Vm, name: &str, args: &[i32]) -> Result<Vec<i32>, RuntimeError> {
+    // static factories of Optional
+    match name {
+        "empty" => return Ok(vec![vm.try_alloc(HeapObject::Optional(None))?]),
+        "of" if args[0] == 0 => {
+            return Err(RuntimeError::Exception {
+                class: "java/lang/NullPointerException".to_string(),
+                message: None,
+                cause: None,
+            });
+        }
+        "of" | "ofNullable" => {
+            let value = if args[0] == 0 { None } else { Some(args[0]) };
+            return Ok(vec![vm.try_alloc(HeapObject::Optional(value))?]);
+        }
+        _ => {}
+    }
+
+    // operations taking a lambda
+    if let HeapObject::Optional(value) = *vm.get_object(args[0]) {
+        match (value, name) {
+            (Some(value), "orElseGet") => return Ok(vec![value]),
+            (None, "orElseGet") => return invoke_functional_interface(vm, args[1], &[]),
+            (Some(value), "ifPresent") => {
+                invoke_functional_interface(vm, args[1], &[value])?;
+                return Ok(vec![]);
+            }
+            (None, "ifPresent") => return Ok(vec![]),
+            _ => {}
+        }
+    }
+
+    let no_value =
+        || RuntimeError::exception("java/util/NoSuchElementException", "No value present");
+    Ok(match (vm.get_object(args[0]), name) {
+        (HeapObject::Optional(value), "isPresent") => vec![value.is_some() as i32],
+        (HeapObject::Optional(value), "isEmpty") => vec![value.is_none() as i32],
+        (HeapObject::Optional(value), "get") => vec![value.ok_or_else(no_value)?],
+        (HeapObject::Optional(value), "orElse") => vec![value.unwrap_or(args[1])],
+        (HeapObject::OptionalInt(value), "isPresent") => vec![value.is_some() as i32],
+        (HeapObject::OptionalInt(value), "getAsInt") => vec![value.ok_or_else(no_value)?],
+        (HeapObject::OptionalInt(value), "orElse") => vec![value.unwrap_or(args[1])],
+        (HeapObject::OptionalDouble(value), "isPresent") => vec![value.is_some() as i32],
+        (HeapObject::OptionalDouble(value), "getAsDouble") => {
+            double_to_slots(value.ok_or_else(no_value)?).to_vec()
+        }
+        (HeapObject::OptionalDouble(value), "orElse") => {
+            double_to_slots(value.unwrap_or_else(|| slots_to_double(args[1], args[2]))).to_vec()
+        }
+        _ => panic!("Unsupported native method: Optional.{}", name),
+    })
+}
+
+/// The base of the digits of a `BigInteger`, a power of ten to make printing cheap.
+const BIG_BASE: u64 = 1_000_000_000;
+
+/// An arbitrary-precision integer, the value of a `java.math.BigInteger`.
+/// Its magnitude is stored as digits in base 10^9, least significant first,
+/// without leading zeros, so zero has no digits and is never negative.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BigInteger {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+/// Remove the leading zeros of a magnitude.
+fn trim_magnitude(mut magnitude: Vec<u32>) -> Vec<u32> {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    magnitude
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> std::cmp::Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let digit = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push((digit % BIG_BASE) as u32);
+        carry = digit / BIG_BASE;
+    }
+    sum.push(carry as u32);
+    trim_magnitude(sum)
+}
+
+/// Subtract the magnitude `b` from the larger magnitude `a`.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &digit) in a.iter().enumerate() {
+        let mut digit = digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = (digit < 0) as i64;
+        if digit < 0 {
+            digit += BIG_BASE as i64;
+        }
+        difference.push(digit as u32);
+    }
+    trim_magnitude(difference)
+}
+
+fn mul_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u64; a.len() + b.len() + 1];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let digit = product[i + j] + x as u64 * y as u64 + carry;
+            product[i + j] = digit % BIG_BASE;
+            carry = digit / BIG_BASE;
+        }
+        product[i + b.len()] += carry;
+    }
+    trim_magnitude(product.into_iter().map(|d| d as u32).collect())
+}
+
+/// Divide a magnitude by a small divisor.
+///
+/// # Return
+/// the quotient and the remainder
+fn divrem_magnitude_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder = 0u64;
+    for i in (0..a.len()).rev() {
+        let current = remainder * BIG_BASE + a[i] as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    (trim_magnitude(quotient), remainder as u32)
+}
+
+/// Divide a magnitude by a non-zero magnitude with schoolbook long division,
+/// finding each digit of the quotient by binary search.
+///
+/// # Return
+/// the quotient and the remainder
+fn divrem_magnitudes(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for i in (0..a.len()).rev() {
+        remainder.insert(0, a[i]);
+        remainder = trim_magnitude(remainder);
+        let (mut low, mut high) = (0, BIG_BASE as u32 - 1);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            let product = mul_magnitudes(b, &[middle]);
+            if compare_magnitudes(&product, &remainder) == std::cmp::Ordering::Greater {
+                high = middle - 1;
+            } else {
+                low = middle;
+            }
+        }
+        quotient[i] = low;
+        remainder = sub_magnitudes(&remainder, &mul_magnitudes(b, &[low]));
+    }
+    (trim_magnitude(quotient), remainder)
+}
+
+impl BigInteger {
+    fn new(negative: bool, magnitude: Vec<u32>) -> BigInteger {
+        let magnitude = trim_magnitude(magnitude);
+        BigInteger {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn from_i64(value: i64) -> BigInteger {
+        let mut rest = value.unsigned_abs();
+        let mut magnitude = Vec::new();
+        while rest > 0 {
+            magnitude.push((rest % BIG_BASE) as u32);
+            rest /= BIG_BASE;
+        }
+        BigInteger::new(value < 0, magnitude)
+    }
+
+    /// Parse a number written in the given radix, with an optional sign.
+    ///
+    /// # Return
+    /// the number, or the message of the `NumberFormatException` to throw,
+    /// also if the radix is not between 2 and 36
+    pub fn parse(s: &str, radix: u32) -> Result<BigInteger, String> {
+        if !(2..=36).contains(&radix) {
+            return Err("Radix out of range".to_string());
+        }
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if s.is_empty() || digits.is_empty() {
+            return Err("Zero length BigInteger".to_string());
+        }
+        if digits.contains(['-', '+']) {
+            return Err("Illegal embedded sign character".to_string());
+        }
+        let mut magnitude = Vec::new();
+        for c in digits.chars() {
+            let digit = c
+                .to_digit(radix)
+                .ok_or_else(|| format!("For input string: \"{}\"", s))?;
+            magnitude = add_magnitudes(&mul_magnitudes(&magnitude, &[radix]), &[digit]);
+        }
+        Ok(BigInteger::new(negative, magnitude))
+    }
+
+    pub fn signum(&self) -> i32 {
+        if self.magnitude.is_empty() {
+            0
+        } else if self.negative {
+            -1
+        } else {
+            1
+        }
+    }
+
+    pub fn negate(&self) -> BigInteger {
+        BigInteger::new(!self.negative, self.magnitude.clone())
+    }
+
+    pub fn abs(&self) -> BigInteger {
+        BigInteger::new(false, self.magnitude.clone())
+    }
+
+    pub fn add(&self, other: &BigInteger) -> BigInteger {
+        if self.negative == other.negative {
+            return BigInteger::new(
+                self.negative,
+                add_magnitudes(&self.magnitude, &other.magnitude),
+            );
+        }
+        // the sign is the one of the larger magnitude
+        match compare_magnitudes(&self.magnitude, &other.magnitude) {
+            std::cmp::Ordering::Less => BigInteger::new(
+                other.negative,
+                sub_magnitudes(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInteger::new(
+                self.negative,
+                sub_magnitudes(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+
+    pub fn subtract(&self, other: &BigInteger) -> BigInteger {
+        self.add(&other.negate())
+    }
+
+    pub fn multiply(&self, other: &BigInteger) -> BigInteger {
+        BigInteger::new(
+            self.negative != other.negative,
+            mul_magnitudes(&self.magnitude, &other.magnitude),
+        )
+    }
+
+    /// Divide, truncating towards zero like Java.
+    ///
+    /// # Return
+    /// the quotient and the remainder, which has the sign of `self`;
+    /// `None` when dividing by zero
+    pub fn divide_and_remainder(&self, other: &BigInteger) -> Option<(BigInteger, BigInteger)> {
+        if other.magnitude.is_empty() {
+            return None;
+        }
+        let (quotient, remainder) = divrem_magnitudes(&self.magnitude, &other.magnitude);
+        Some((
+            BigInteger::new(self.negative != other.negative, quotient),
+            BigInteger::new(self.negative, remainder),
+        ))
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> BigInteger {
+        let mut result = BigInteger::from_i64(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            base = base.multiply(&base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    pub fn gcd(&self, other: &BigInteger) -> BigInteger {
+        let (mut a, mut b) = (self.magnitude.clone(), other.magnitude.clone());
+        while !b.is_empty() {
+            let remainder = divrem_magnitudes(&a, &b).1;
+            a = std::mem::replace(&mut b, remainder);
+        }
+        BigInteger::new(false, a)
+    }
+
+    /// Compute `self^exponent mod modulus` for non-negative numbers.
+    fn mod_pow(&self, exponent: &BigInteger, modulus: &BigInteger) -> BigInteger {
+        let mut result = BigInteger::from_i64(1);
+        let mut base = self.clone();
+        let mut exponent = exponent.magnitude.clone();
+        while !exponent.is_empty() {
+            let (half, bit) = divrem_magnitude_small(&exponent, 2);
+            if bit == 1 {
+                result = result
+                    .multiply(&base)
+                    .divide_and_remainder(modulus)
+                    .unwrap()
+                    .1;
+            }
+            base = base
+                .multiply(&base)
+                .divide_and_remainder(modulus)
+                .unwrap()
+                .1;
+            exponent = half;
+        }
+        result
+    }
+
+    /// Test whether the absolute value is prime with the Miller-Rabin test.
+    /// The bases used make the answer exact below 3.3 * 10^24.
+    pub fn is_probable_prime(&self, certainty: i32) -> bool {
+        if certainty <= 0 {
+            return true;
+        }
+        let n = self.abs();
+        let small_primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+        for &p in &small_primes {
+            let p = BigInteger::from_i64(p);
+            if n == p {
+                return true;
+            }
+            if divrem_magnitudes(&n.magnitude, &p.magnitude).1.is_empty() {
+                return false;
+            }
+        }
+        if compare_magnitudes(&n.magnitude, &[2]) == std::cmp::Ordering::Less {
+            return false;
+        }
+
+        // n - 1 = d * 2^s with d odd
+        let one = BigInteger::from_i64(1);
+        let n_minus_one = n.subtract(&one);
+        let mut d = n_minus_one.clone();
+        let mut s = 0;
+        while d.magnitude[0].is_multiple_of(2) {
+            d = BigInteger::new(false, divrem_magnitude_small(&d.magnitude, 2).0);
+            s += 1;
+        }
+        'witness: for &a in &small_primes {
+            let mut x = BigInteger::from_i64(a).mod_pow(&d, &n);
+            if x == one || x == n_minus_one {
+                continue;
+            }
+            for _ in 1..s {
+                x = x.multiply(&x).divide_and_remainder(&n).unwrap().1;
+                if x == n_minus_one {
+                    continue 'witness;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /// Get the low 64 bits of the two's complement representation, like `longValue()`.
+    pub fn to_i64(&self) -> i64 {
+        let low = self.magnitude.iter().rev().fold(0u64, |low, &digit| {
+            low.wrapping_mul(BIG_BASE).wrapping_add(digit as u64)
+        });
+        if self.negative {
+            low.wrapping_neg() as i64
+        } else {
+            low as i64
+        }
+    }
+
+    /// Format the number in the given radix, like `toString(int)`.
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        let radix = if (2..=36).contains(&radix) { radix } else { 10 };
+        if self.magnitude.is_empty() {
+            return "0".to_string();
+        }
+        let mut digits = Vec::new();
+        let mut rest = self.magnitude.clone();
+        while !rest.is_empty() {
+            let (quotient, digit) = divrem_magnitude_small(&rest, radix);
+            digits.push(std::char::from_digit(digit, radix).unwrap());
+            rest = quotient;
+        }
+        if self.negative {
+            digits.push('-');
+        }
+        digits.iter().rev().collect()
+    }
+}
+
+impl std::cmp::Ord for BigInteger {
+    fn cmp(&self, other: &BigInteger) -> std::cmp::Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => std::cmp::Ordering::Greater,
+            (true, false) => std::cmp::Ordering::Less,
+            (false, false) => compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl std::cmp::PartialOrd for BigInteger {
+    fn partial_cmp(&self, other: &BigInteger) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for BigInteger {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_string_radix(10))
+    }
+}
+
+/// A rounding mode of `java.math.RoundingMode`, numbered like its ordinal
+/// and like the legacy `BigDecimal.ROUND_*` constants.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Up = 0,
+    Down = 1,
+    Ceiling = 2,
+    Floor = 3,
+    HalfUp = 4,
+    HalfDown = 5,
+    HalfEven = 6,
+    Unnecessary = 7,
+}
+
+impl RoundingMode {
+    const ALL: [RoundingMode; 8] = [
+        RoundingMode::Up,
+        RoundingMode::Down,
+        RoundingMode::Ceiling,
+        RoundingMode::Floor,
+        RoundingMode::HalfUp,
+        RoundingMode::HalfDown,
+        RoundingMode::HalfEven,
+        RoundingMode::Unnecessary,
+    ];
+
+    /// Get the rounding mode of a `RoundingMode` constant, e.g. "HALF_UP".
+    pub fn from_name(name: &str) -> Option<RoundingMode> {
+        let names = [
+            "UP",
+            "DOWN",
+            "CEILING",
+            "FLOOR",
+            "HALF_UP",
+            "HALF_DOWN",
+            "HALF_EVEN",
+            "UNNECESSARY",
+        ];
+        let index = names.iter().position(|&n| n == name)?;
+        Some(RoundingMode::ALL[index])
+    }
+
+    /// Get the rounding mode of a legacy `BigDecimal.ROUND_*` constant.
+    pub fn from_ordinal(ordinal: i32) -> Option<RoundingMode> {
+        RoundingMode::ALL.get(ordinal as usize).copied()
+    }
+}
+
+/// An arbitrary-precision decimal, the value of a `java.math.BigDecimal`.
+/// Its value is `unscaled * 10^-scale`, so 1.50 is 150 with scale 2.
+#[derive(Clone)]
+pub struct BigDecimal {
+    unscaled: BigInteger,
+    scale: i32,
+}
+
+/// Get 10 to the power of `exponent`.
+fn ten_pow(exponent: u32) -> BigInteger {
+    BigInteger::from_i64(10).pow(exponent)
+}
+
+/// Round the quotient of a truncating division.
+///
+/// # Parameters
+/// * `quotient` - the quotient, truncated towards zero
+/// * `remainder` - the remainder, with the sign of the dividend
+/// * `divisor` - the divisor
+/// * `mode` - how to round
+/// # Return
+/// the rounded quotient, `None` if rounding is necessary with `RoundingMode::Unnecessary`
+fn round_quotient(
+    quotient: BigInteger,
+    remainder: &BigInteger,
+    divisor: &BigInteger,
+    mode: RoundingMode,
+) -> Option<BigInteger> {
+    if remainder.signum() == 0 {
+        return Some(quotient);
+    }
+    // the sign of the exact quotient, which is not 0
+    let sign = remainder.signum() * divisor.signum();
+    let half = remainder
+        .abs()
+        .multiply(&BigInteger::from_i64(2))
+        .cmp(&divisor.abs());
+    let away_from_zero = match mode {
+        RoundingMode::Up => true,
+        RoundingMode::Down => false,
+        RoundingMode::Ceiling => sign > 0,
+        RoundingMode::Floor => sign < 0,
+        RoundingMode::HalfUp => half != std::cmp::Ordering::Less,
+        RoundingMode::HalfDown => half == std::cmp::Ordering::Greater,
+        RoundingMode::HalfEven => {
+            let odd = quotient.to_i64() & 1 == 1;
+            half == std::cmp::Ordering::Greater || half == std::cmp::Ordering::Equal && odd
+        }
+        RoundingMode::Unnecessary => return None,
+    };
+    Some(if away_from_zero {
+        quotient.add(&BigInteger::from_i64(sign as i64))
+    } else {
+        quotient
+    })
+}
+
+impl BigDecimal {
+    pub fn new(unscaled: BigInteger, scale: i32) -> BigDecimal {
+        BigDecimal { unscaled, scale }
+    }
+
+    /// Parse a decimal such as "-12.50" or "1.5E-3".
+    ///
+    /// # Return
+    /// the decimal, or the message of the `NumberFormatException` to throw
+    pub fn parse(s: &str) -> Result<BigDecimal, String> {
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(at) => {
+                let exponent = s[at + 1..]
+                    .parse::<i32>()
+                    .map_err(|_| "No digits found.".to_string())?;
+                (&s[..at], exponent)
+            }
+            None => (s, 0),
+        };
+        let (sign, mantissa) = match mantissa.strip_prefix(['-', '+']) {
+            Some(rest) => (&mantissa[..1], rest),
+            None => ("", mantissa),
+        };
+        if let Some(c) = mantissa.chars().find(|&c| !c.is_ascii_digit() && c != '.') {
+            return Err(format!(
+                "Character {} is neither a decimal digit number, decimal point, nor \"e\" notation exponential mark.",
+                c
+            ));
+        }
+        let fraction = mantissa.find('.').map_or(0, |at| mantissa.len() - at - 1);
+        let digits = mantissa.replacen('.', "", 1);
+        if digits.is_empty() || digits.contains('.') {
+            return Err("No digits found.".to_string());
+        }
+        let unscaled = BigInteger::parse(&format!("{}{}", sign, digits), 10)?;
+        Ok(BigDecimal::new(unscaled, fraction as i32 - exponent))
+    }
+
+    /// Get the same value with a scale at least as large as the given one.
+    fn upscale(&self, scale: i32) -> BigInteger {
+        if scale <= self.scale {
+            return self.unscaled.clone();
+        }
+        self.unscaled
+            .multiply(&ten_pow((scale - self.scale) as u32))
+    }
+
+    pub fn signum(&self) -> i32 {
+        self.unscaled.signum()
+    }
+
+    pub fn add(&self, other: &BigDecimal) -> BigDecimal {
+        let scale = self.scale.max(other.scale);
+        BigDecimal::new(self.upscale(scale).add(&other.upscale(scale)), scale)
+    }
+
+    pub fn subtract(&self, other: &BigDecimal) -> BigDecimal {
+        self.add(&other.negate())
+    }
+
+    pub fn multiply(&self, other: &BigDecimal) -> BigDecimal {
+        BigDecimal::new(
+            self.unscaled.multiply(&other.unscaled),
+            self.scale + other.scale,
+        )
+    }
+
+    pub fn negate(&self) -> BigDecimal {
+        BigDecimal::new(self.unscaled.negate(), self.scale)
+    }
+
+    pub fn abs(&self) -> BigDecimal {
+        BigDecimal::new(self.unscaled.abs(), self.scale)
+    }
+
+    /// Change the scale, rounding if digits are dropped.
+    ///
+    /// # Return
+    /// the rescaled decimal, `None` if rounding is necessary with `RoundingMode::Unnecessary`
+    pub fn set_scale(&self, scale: i32, mode: RoundingMode) -> Option<BigDecimal> {
+        if scale >= self.scale {
+            return Some(BigDecimal::new(self.upscale(scale), scale));
+        }
+        let divisor = ten_pow((self.scale - scale) as u32);
+        let (quotient, remainder) = self.unscaled.divide_and_remainder(&divisor)?;
+        let unscaled = round_quotient(quotient, &remainder, &divisor, mode)?;
+        Some(BigDecimal::new(unscaled, scale))
+    }
+
+    /// Divide, rounding the quotient to the given scale.
+    /// The divisor must not be zero.
+    ///
+    /// # Return
+    /// the quotient, `None` if rounding is necessary with `RoundingMode::Unnecessary`
+    pub fn divide(&self, other: &BigDecimal, scale: i32, mode: RoundingMode) -> Option<BigDecimal> {
+        // self / other = (a * 10^-s1) / (b * 10^-s2), scaled by 10^scale
+        let shift = scale - self.scale + other.scale;
+        let (dividend, divisor) = if shift >= 0 {
+            (
+                self.unscaled.multiply(&ten_pow(shift as u32)),
+                other.unscaled.clone(),
+            )
+        } else {
+            (
+                self.unscaled.clone(),
+                other.unscaled.multiply(&ten_pow(-shift as u32)),
+            )
+        };
+        let (quotient, remainder) = dividend.divide_and_remainder(&divisor)?;
+        let unscaled = round_quotient(quotient, &remainder, &divisor, mode)?;
+        Some(BigDecimal::new(unscaled, scale))
+    }
+
+    /// Divide exactly, like `divide(BigDecimal)`.
+    /// The quotient has the scale `self.scale - other.scale`, or the smallest larger one
+    /// which represents it exactly.
+    ///
+    /// # Return
+    /// the quotient, `None` if it has a non-terminating decimal expansion.
+    /// The divisor must not be zero.
+    pub fn divide_exact(&self, other: &BigDecimal) -> Option<BigDecimal> {
+        // the expansion terminates if the reduced divisor only has the factors 2 and 5
+        let mut divisor = other
+            .unscaled
+            .divide_and_remainder(&self.unscaled.gcd(&other.unscaled))?
+            .0
+            .abs();
+        let mut digits = 0;
+        for factor in [2, 5] {
+            let factor = BigInteger::from_i64(factor);
+            let mut count = 0;
+            loop {
+                let (quotient, remainder) = divisor.divide_and_remainder(&factor)?;
+                if remainder.signum() != 0 {
+                    break;
+                }
+                divisor = quotient;
+                count += 1;
+            }
+            digits = digits.max(count);
+        }
+        if divisor != BigInteger::from_i64(1) {
+            return None;
+        }
+
+        let preferred = self.scale - other.scale;
+        (preferred..=preferred + digits)
+            .find_map(|scale| self.divide(other, scale, RoundingMode::Unnecessary))
+    }
+
+    /// Compare the values, ignoring the scale like `compareTo`.
+    pub fn compare(&self, other: &BigDecimal) -> std::cmp::Ordering {
+        let scale = self.scale.max(other.scale);
+        self.upscale(scale).cmp(&other.upscale(scale))
+    }
+
+    /// Get the digits of the unscaled value, without sign.
+    fn coefficient(&self) -> String {
+        self.unscaled.abs().to_string()
+    }
+
+    /// Format the decimal without exponent, like `toPlainString()`.
+    pub fn to_plain_string(&self) -> String {
+        let sign = if self.signum() < 0 { "-" } else { "" };
+        let coefficient = self.coefficient();
+        if self.signum() == 0 && self.scale < 0 {
+            return "0".to_string();
+        }
+        if self.scale <= 0 {
+            return format!(
+                "{}{}{}",
+                sign,
+                coefficient,
+                "0".repeat(-self.scale as usize)
+            );
+        }
+        let scale = self.scale as usize;
+        if coefficient.len() > scale {
+            let (integer, fraction) = coefficient.split_at(coefficient.len() - scale);
+            format!("{}{}.{}", sign, integer, fraction)
+        } else {
+            format!(
+                "{}0.{}{}",
+                sign,
+                "0".repeat(scale - coefficient.len()),
+                coefficient
+            )
+        }
+    }
+}
+
+impl std::cmp::PartialEq for BigDecimal {
+    /// Like `equals`, 2.0 and 2.00 are different.
+    fn eq(&self, other: &BigDecimal) -> bool {
+        self.scale == other.scale && self.unscaled == other.unscaled
+    }
+}
+
+impl std::fmt::Display for BigDecimal {
+    /// Format the decimal like `toString()`, with an exponent for very large or small values.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let coefficient = self.coefficient();
+        let adjusted = -(self.scale as i64) + (coefficient.len() as i64 - 1);
+        if self.scale >= 0 && adjusted >= -6 {
+            return write!(f, "{}", self.to_plain_string());
+        }
+        let sign = if self.signum() < 0 { "-" } else { "" };
+        let (first, rest) = coefficient.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        write!(f, "{}{}{}{}E{:+}", sign, first, point, rest, adjusted)
+    }
+}
+
+/// A node of a parsed regular expression.
+#[derive(Clone)]
+enum RegexNode {
+    Char(char),
+    /// `.`, any character but a line terminator
+    Any,
+    /// `[...]` or a predefined class such as `\d`
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    /// `^`
+    Start,
+    /// `$`
+    End,
+    /// `\b`, or `\B` if negated
+    WordBoundary(bool),
+    /// A group, capturing if it has an index
+    Group(Box<RegexNode>, Option<usize>),
+    Alternation(Vec<RegexNode>),
+    Concat(Vec<RegexNode>),
+    Repeat {
+        node: Box<RegexNode>,
+        min: usize,
+        max: usize,
+        greedy: bool,
+    },
+}
+
+/// An item of a character class.
+#[derive(Clone)]
+enum ClassItem {
+    Range(char, char),
+    /// A predefined class, e.g. 'd' for `\d`
+    Predefined(char),
+}
+
+/// The positions of the match of every group, group 0 being the whole match.
+type RegexGroups = Vec<Option<(usize, usize)>>;
+
+/// A compiled regular expression, following the syntax of `java.util.regex.Pattern`.
+/// It is matched by backtracking on the characters of the input.
+/// FIXME: only the common constructs are supported, e.g. no flags, lookarounds or backreferences
+#[derive(Clone)]
+pub struct Regex {
+    pattern: String,
+    root: RegexNode,
+    /// The number of capturing groups, without group 0
+    group_count: usize,
+}
+
+/// Parse a regular expression, one construct at a time.
+struct RegexParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    group_count: usize,
+}
+
+impl RegexParser<'_> {
+    fn parse_alternation(&mut self) -> Result<RegexNode, String> {
+        let mut options = vec![self.parse_concat()?];
+        while self.chars.next_if_eq(&'|').is_some() {
+            options.push(self.parse_concat()?);
+        }
+        Ok(if options.len() == 1 {
+            options.pop().unwrap()
+        } else {
+            RegexNode::Alternation(options)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<RegexNode, String> {
+        let mut nodes = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(RegexNode::Concat(nodes))
+    }
+
+    fn parse_atom(&mut self) -> Result<RegexNode, String> {
+        Ok(match self.chars.next().unwrap() {
+            '(' => {
+                let index = if self.chars.next_if_eq(&'?').is_some() {
+                    if self.chars.next() != Some(':') {
+                        return Err("Unsupported group".to_string());
+                    }
+                    None
+                } else {
+                    self.group_count += 1;
+                    Some(self.group_count)
+                };
+                let node = self.parse_alternation()?;
+                if self.chars.next() != Some(')') {
+                    return Err("Unclosed group".to_string());
+                }
+                RegexNode::Group(Box::new(node), index)
+            }
+            '[' => self.parse_class()?,
+            '.' => RegexNode::Any,
+            '^' => RegexNode::Start,
+            '$' => RegexNode::End,
+            '\\' => match self.parse_escape()? {
+                ClassItem::Predefined('b') => RegexNode::WordBoundary(false),
+                ClassItem::Predefined('B') => RegexNode::WordBoundary(true),
+                ClassItem::Predefined(c) => RegexNode::Class {
+                    items: vec![ClassItem::Predefined(c)],
+                    negated: false,
+                },
+                ClassItem::Range(c, _) => RegexNode::Char(c),
+            },
+            c @ ('*' | '+' | '?' | '{') => {
+                return Err(format!("Dangling meta character '{}'", c));
+            }
+            c => RegexNode::Char(c),
+        })
+    }
+
+    /// Parse what follows a backslash, either a predefined class or an escaped character.
+    fn parse_escape(&mut self) -> Result<ClassItem, String> {
+        Ok(match self.chars.next() {
+            Some(c @ ('d' | 'D' | 'w' | 'W' | 's' | 'S' | 'b' | 'B')) => ClassItem::Predefined(c),
+            Some('t') => ClassItem::Range('\t', '\t'),
+            Some('n') => ClassItem::Range('\n', '\n'),
+            Some('r') => ClassItem::Range('\r', '\r'),
+            Some(c) if !c.is_ascii_alphanumeric() => ClassItem::Range(c, c),
+            Some(c) => return Err(format!("Illegal/unsupported escape sequence \\{}", c)),
+            None => return Err("Unexpected internal error".to_string()),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<RegexNode, String> {
+        let negated = self.chars.next_if_eq(&'^').is_some();
+        let mut items = Vec::new();
+        loop {
+            let item = match self.chars.next() {
+                None => return Err("Unclosed character class".to_string()),
+                // a leading ']' is a literal
+                Some(']') if !items.is_empty() => break,
+                Some('\\') => self.parse_escape()?,
+                Some(c) => ClassItem::Range(c, c),
+            };
+            // a range such as a-z, a trailing '-' is a literal
+            if let ClassItem::Range(start, _) = item {
+                let mut lookahead = self.chars.clone();
+                if lookahead.next() == Some('-') && !matches!(lookahead.peek(), Some(']') | None) {
+                    self.chars.next();
+                    let end = match self.chars.next().unwrap() {
+                        '\\' => match self.parse_escape()? {
+                            ClassItem::Range(end, _) => end,
+                            ClassItem::Predefined(_) => {
+                                return Err("Illegal character range".to_string())
+                            }
+                        },
+                        end => end,
+                    };
+                    if end < start {
+                        return Err("Illegal character range".to_string());
+                    }
+                    items.push(ClassItem::Range(start, end));
+                    continue;
+                }
+            }
+            items.push(item);
+        }
+        Ok(RegexNode::Class { items, negated })
+    }
+
+    fn parse_quantifier(&mut self, node: RegexNode) -> Result<RegexNode, String> {
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, usize::MAX),
+            Some('+') => (1, usize::MAX),
+            Some('?') => (0, 1),
+            Some('{') => {
+                self.chars.next();
+                let mut bounds = String::new();
+                loop {
+                    match self.chars.peek() {
+                        Some('}') => break,
+                        Some(&c) => {
+                            bounds.push(c);
+                            self.chars.next();
+                        }
+                        None => return Err("Unclosed counted closure".to_string()),
+                    }
+                }
+                let parse = |s: &str| {
+                    s.trim()
+                        .parse()
+                        .map_err(|_| "Illegal repetition".to_string())
+                };
+                match bounds.split_once(',') {
+                    None => {
+                        let count = parse(&bounds)?;
+                        (count, count)
+                    }
+                    Some((min, "")) => (parse(min)?, usize::MAX),
+                    Some((min, max)) => {
+                        let (min, max) = (parse(min)?, parse(max)?);
+                        if max < min {
+                            return Err("Illegal repetition range".to_string());
+                        }
+                        (min, max)
+                    }
+                }
+            }
+            _ => return Ok(node),
+        };
+        self.chars.next();
+        if matches!(
+            node,
+            RegexNode::Start | RegexNode::End | RegexNode::WordBoundary(_)
+        ) {
+            return Err("Dangling meta character".to_string());
+        }
+        let greedy = self.chars.next_if_eq(&'?').is_none();
+        if self.chars.peek() == Some(&'+') {
+            return Err("Possessive quantifiers are unsupported".to_string());
+        }
+        Ok(RegexNode::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+}
+
+impl Regex {
+    /// Compile a regular expression.
+    ///
+    /// # Parameters
+    /// * `pattern` - the regular expression, e.g. "(\\d+)-(\\d+)"
+    /// # Return
+    /// the compiled expression, or a description of its syntax error
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let mut parser = RegexParser {
+            chars: pattern.chars().peekable(),
+            group_count: 0,
+        };
+        let root = parser.parse_alternation()?;
+        if parser.chars.next().is_some() {
+            return Err("Unmatched closing ')'".to_string());
+        }
+        Ok(Regex {
+            pattern: pattern.to_string(),
+            root,
+            group_count: parser.group_count,
+        })
+    }
+
+    /// Find the first match starting at or after `from`.
+    ///
+    /// # Parameters
+    /// * `input` - the characters to search
+    /// * `from` - the position to start searching at
+    /// # Return
+    /// the positions of the groups of the match, `None` if there is no match
+    pub fn find_at(&self, input: &[char], from: usize) -> Option<RegexGroups> {
+        (from..=input.len()).find_map(|start| self.match_at(input, start, false))
+    }
+
+    /// Match the whole input, like `Matcher.matches()`.
+    pub fn full_match(&self, input: &[char]) -> Option<RegexGroups> {
+        self.match_at(input, 0, true)
+    }
+
+    fn match_at(&self, input: &[char], start: usize, full: bool) -> Option<RegexGroups> {
+        let mut groups = vec![None; self.group_count + 1];
+        let matched = match_node(&self.root, input, start, &mut groups, &mut |end, groups| {
+            if full && end != input.len() {
+                return false;
+            }
+            groups[0] = Some((start, end));
+            true
+        });
+        if matched {
+            Some(groups)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a character is matched by a predefined class, e.g. 'd' for `\d`.
+fn is_in_predefined_class(class: char, c: char) -> bool {
+    let matched = match class.to_ascii_lowercase() {
+        'd' => c.is_ascii_digit(),
+        'w' => c.is_ascii_alphanumeric() || c == '_',
+        's' => matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0B' | '\x0C'),
+        _ => unreachable!(),
+    };
+    // upper case classes are the complements, e.g. \D
+    matched != class.is_ascii_uppercase()
+}
+
+/// Match a node at `pos`, then the rest of the expression through `next`.
+/// Backtracking happens by returning false, after which the next alternative is tried.
+///
+/// # Parameters
+/// * `node` - the node to match
+/// * `input` - the characters being matched
+/// * `pos` - the position to match at
+/// * `groups` - the positions of the groups matched so far
+/// * `next` - matches the rest of the expression from the position after the node
+/// # Return
+/// whether the node and the rest of the expression matched
+fn match_node(
+    node: &RegexNode,
+    input: &[char],
+    pos: usize,
+    groups: &mut RegexGroups,
+    next: &mut dyn FnMut(usize, &mut RegexGroups) -> bool,
+) -> bool {
+    let is_word = |at: usize| {
+        input
+            .get(at)
+            .is_some_and(|&c| is_in_predefined_class('w', c))
+    };
+    match node {
+        RegexNode::Char(c) => input.get(pos) == Some(c) && next(pos + 1, groups),
+        RegexNode::Any => {
+            input.get(pos).is_some_and(|&c| c != '\n' && c != '\r') && next(pos + 1, groups)
+        }
+        RegexNode::Class { items, negated } => {
+            let c = match input.get(pos) {
+                Some(&c) => c,
+                None => return false,
+            };
+            let matched = items.iter().any(|item| match *item {
+                ClassItem::Range(start, end) => (start..=end).contains(&c),
+                ClassItem::Predefined(class) => is_in_predefined_class(class, c),
+            });
+            matched != *negated && next(pos + 1, groups)
+        }
+        RegexNode::Start => pos == 0 && next(pos, groups),
+        RegexNode::End => pos == input.len() && next(pos, groups),
+        RegexNode::WordBoundary(negated) => {
+            let boundary = (pos > 0 && is_word(pos - 1)) != is_word(pos);
+            boundary != *negated && next(pos, groups)
+        }
+        RegexNode::Group(node, index) => {
+            match_node(node, input, pos, groups, &mut |end, groups| {
+                let Some(index) = *index else {
+                    return next(end, groups);
+                };
+                let saved = groups[index];
+                groups[index] = Some((pos, end));
+                if next(end, groups) {
+                    return true;
+                }
+                groups[index] = saved;
+                false
+            })
+        }
+        RegexNode::Alternation(options) => options.iter().any(|option| {
+            let saved = groups.clone();
+            if match_node(option, input, pos, groups, next) {
+                return true;
+            }
+            *groups = saved;
+            false
+        }),
+        RegexNode::Concat(nodes) => match_sequence(nodes, input, pos, groups, next),
+        RegexNode::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => match_repeat(node, (*min, *max, *greedy), 0, input, pos, groups, next),
+    }
+}
+
+/// Match the nodes of a concatenation one after another.
+fn match_sequence(
+    nodes: &[RegexNode],
+    input: &[char],
+    pos: usize,
+    groups: &mut RegexGroups,
+    next: &mut dyn FnMut(usize, &mut RegexGroups) -> bool,
+) -> bool {
+    match nodes.split_first() {
+        None => next(pos, groups),
+        Some((first, rest)) => match_node(first, input, pos, groups, &mut |end, groups| {
+            match_sequence(rest, input, end, groups, next)
+        }),
+    }
+}
+
+/// Match a repeated node, `count` repetitions having matched already.
+/// Greedy repetitions try one more repetition before the rest of the expression,
+/// lazy ones the other way around.
+fn match_repeat(
+    node: &RegexNode,
+    (min, max, greedy): (usize, usize, bool),
+    count: usize,
+    input: &[char],
+    pos: usize,
+    groups: &mut RegexGroups,
+    next: &mut dyn FnMut(usize, &mut RegexGroups) -> bool,
+) -> bool {
+    let once_more = |groups: &mut RegexGroups,
+                     next: &mut dyn FnMut(usize, &mut RegexGroups) -> bool| {
+        count < max
+            && match_node(node, input, pos, groups, &mut |end, groups| {
+                // a repetition matching nothing would repeat forever
+                if end == pos && count >= min {
+                    return false;
+                }
+                match_repeat(
+                    node,
+                    (min, max, greedy),
+                    count + 1,
+                    input,
+                    end,
+                    groups,
+                    next,
+                )
+            })
+    };
+    if !greedy && count >= min && next(pos, groups) {
+        return true;
+    }
+    once_more(groups, next) || greedy && count >= min && next(pos, groups)
+}
+
+fn get_class_header<T: Read>(class_file: &mut ClassReader<T>) -> Result<ClassHeader, ParseError> {
+    let header = ClassHeader {
+        magic: class_file.u4()?,
+        minor_version: class_file.u2()?,
+        major_version: class_file.u2()?,
+    };
+    if header.magic != 0xCAFEBABE {
+        return Err(ParseError::BadMagic(header.magic).at(0));
+    }
+    Ok(header)
+}
+
+fn get_constant_pool<T: Read>(
+    class_file: &mut ClassReader<T>,
+) -> Result<Vec<ConstantPool>, ParseError> {
+    let count = (class_file.u2()? as usize).saturating_sub(1);
+    let mut cp: Vec<ConstantPool> = Vec::with_capacity(count);
+    // the descriptors of NameAndType and MethodType constants, with the offsets of their
+    // indexes, are checked once the Utf8 constants they refer to are read
+    let mut descriptors = vec![];
+
+    while cp.len() < count {
+        let tag = class_file.u1()?;
+        match tag {
+            1 => {
+                let length = class_file.u2()? as usize;
+                let start = class_file.position();
+                let bytes = class_file.bytes(length)?;
+                let s = String::from_utf8(bytes).map_err(|_| ParseError::InvalidUtf8.at(start))?;
+                cp.push(ConstantPool::Utf8(s));
+            }
+            3 => cp.push(ConstantPool::Integer {
+                bytes: class_file.u4()? as i32,
+            }),
+            4 => cp.push(ConstantPool::Float {
+                bytes: class_file.u4()?,
+            }),
+            5 => {
+                cp.push(ConstantPool::Long {
+                    high_bytes: class_file.u4()?,
+                    low_bytes: class_file.u4()?,
+                });
+                cp.push(ConstantPool::Unusable);
+            }
+            6 => {
+                cp.push(ConstantPool::Double {
+                    high_bytes: class_file.u4()?,
+                    low_bytes: class_file.u4()?,
+                });
+                cp.push(ConstantPool::Unusable);
+            }
+            7 => cp.push(ConstantPool::Class {
+                starting_index: class_file.u2()?,
+            }),
+            8 => cp.push(ConstantPool::String {
+                string_index: class_file.u2()?,
+            }),
+            9..=11 => cp.push(ConstantPool::MethodOrFieldRef {
+                tag,
+                class_index: class_file.u2()?,
+                name_and_type_index: class_file.u2()?,
+            }),
+            12 => {
+                let name_index = class_file.u2()?;
+                let offset = class_file.position();
+                let descriptor_index = class_file.u2()?;
+                descriptors.push((descriptor_index, offset, false));
+                cp.push(ConstantPool::NameAndType {
+                    name_index,
+                    descriptor_index,
+                });
+            }
+            15 => cp.push(ConstantPool::MethodHandle {
+                reference_kind: class_file.u1()?,
+                reference_index: class_file.u2()?,
+            }),
+            16 => {
+                let offset = class_file.position();
+                let descriptor_index = class_file.u2()?;
+                descriptors.push((descriptor_index, offset, true));
+                cp.push(ConstantPool::MethodType { descriptor_index });
+            }
+            18 => cp.push(ConstantPool::InvokeDynamic {
+                bootstrap_method_attr_index: class_file.u2()?,
+                name_and_type_index: class_file.u2()?,
+            }),
+            x => return Err(ParseError::UnsupportedTag(x).at(class_file.position() - 1)),
+        }
+    }
+
+    for (index, offset, method_only) in descriptors {
+        let is_valid = if method_only {
+            is_method_descriptor
+        } else {
+            |descriptor: &str| is_method_descriptor(descriptor) || is_field_descriptor(descriptor)
+        };
+        parse_descriptor(&cp, index, offset, is_valid)?;
+    }
+
+    Ok(cp)
+}
+
+fn get_class_info<T: Read>(class_file: &mut ClassReader<T>) -> Result<ClassInfo, ParseError> {
+    let access_flags = class_file.u2()?;
+    let this_calss = class_file.u2()?;
+    let super_class = class_file.u2()?;
+    let interfaces_count = class_file.u2()?;
+    let interfaces = (0..interfaces_count)
+        .map(|_| class_file.u2())
+        .collect::<Result<_, _>>()?;
+    Ok(ClassInfo {
+        access_flags,
+        this_calss,
+        super_class,
+        interfaces,
+    })
+}
+
+fn get_fields<T: Read>(
+    class_file: &mut ClassReader<T>,
+    cp: &[ConstantPool],
+) -> Result<Vec<Field>, ParseError> {
+    let fields_count = class_file.u2()? as usize;
+    let mut fields: Vec<Field> = Vec::with_capacity(fields_count);
+
+    for _ in 0..fields_count {
+        let start = class_file.position();
+        let info = FieldInfo {
+            access_flags: class_file.u2()?,
+            name_index: class_file.u2()?,
+            descriptor_index: class_file.u2()?,
+            attributes_count: class_file.u2()?,
+        };
+
+        // Only the ConstantValue attribute is used, it holds the value of a static constant
+        let mut constant_value = None;
+        for _ in 0..info.attributes_count {
+            let attribute_start = class_file.position();
+            let ainfo = AttributeInfo {
+                attribute_name_index: class_file.u2()?,
+                attribute_length: class_file.u4()?,
+            };
+            let mut attribute = class_file.part(ainfo.attribute_length as usize)?;
+            if parse_utf8(cp, ainfo.attribute_name_index, attribute_start)? == "ConstantValue" {
+                constant_value = Some(attribute.u2()?);
+            }
+        }
+
+        fields.push(Field {
+            access_flags: info.access_flags,
+            name: parse_utf8(cp, info.name_index, start + 2)?,
+            descriptor: parse_descriptor(
+                cp,
+                info.descriptor_index,
+                start + 4,
+                is_field_descriptor,
+            )?,
+            constant_value,
+        });
+    }
+
+    Ok(fields)
+}
+
+fn read_method_attributes<T: Read>(
+    class_file: &mut ClassReader<T>,
+    acount: u16,
+    cp: &[ConstantPool],
+    name: &str,
+) -> Result<Option<Code>, ParseError> {
+    let mut codes = None;
+    for _ in 0..acount {
+        let start = class_file.position();
+        let ainfo = AttributeInfo {
+            attribute_name_index: class_file.u2()?,
+            attribute_length: class_file.u4()?,
+        };
+        let mut attributes = class_file.part(ainfo.attribute_length as usize)?;
+
+        let type_constant = parse_utf8(cp, ainfo.attribute_name_index, start)?;
+        if type_constant == "Code" {
+            if codes.is_some() {
+                return Err(ParseError::DuplicateCode(name.to_string()).at(start));
+            }
+
+            let max_stack = attributes.u2()?;
+            let max_locals = attributes.u2()?;
+            let code_length = attributes.u4()?;
+            let code = attributes.bytes(code_length as usize)?;
+            let exception_table_length = attributes.u2()?;
+            let mut exception_table = Vec::with_capacity(exception_table_length as usize);
+            for _ in 0..exception_table_length {
+                exception_table.push(ExceptionHandler {
+                    start_pc: attributes.u2()?,
+                    end_pc: attributes.u2()?,
+                    handler_pc: attributes.u2()?,
+                    catch_type: attributes.u2()?,
+                });
+            }
+
+            // the attributes of the code, only StackMapTable is used
+            let mut stack_map_table = None;
+            for _ in 0..attributes.u2()? {
+                let name_start = attributes.position();
+                let name_index = attributes.u2()?;
+                let length = attributes.u4()?;
+                let mut table = attributes.part(length as usize)?;
+                if parse_utf8(cp, name_index, name_start)? == "StackMapTable" {
+                    stack_map_table = Some(read_stack_map_table(&mut table)?);
+                }
+            }
+
+            codes = Some(Code {
+                max_stack,
+                max_locals,
+                code_length,
+                code,
+                exception_table,
+                stack_map_table,
+            });
+        }
+    }
+
+    Ok(codes)
+}
+
+/// Read the offsets of the frames of a StackMapTable attribute.
+/// The types of the frames are skipped, since they are not verified yet.
+///
+/// # Parameters
+/// * `table` - the contents of the attribute
+/// # Return
+/// the offset of every frame in the code, in increasing order
+fn read_stack_map_table<T: Read>(table: &mut ClassReader<T>) -> Result<Vec<usize>, ParseError> {
+    // a verification type is a tag, followed by an index or offset for Object and Uninitialized
+    let skip_types = |table: &mut ClassReader<T>, count: u16| -> Result<(), ParseError> {
+        for _ in 0..count {
+            if matches!(table.u1()?, 7 | 8) {
+                table.u2()?;
+            }
+        }
+        Ok(())
+    };
+
+    let count = table.u2()?;
+    let mut offsets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let frame_type = table.u1()?;
+        let offset_delta = match frame_type {
+            // same_frame
+            0..=63 => frame_type as u16,
+            // same_locals_1_stack_item_frame
+            64..=127 => {
+                skip_types(table, 1)?;
+                frame_type as u16 - 64
+            }
+            // same_locals_1_stack_item_frame_extended
+            247 => {
+                let offset_delta = table.u2()?;
+                skip_types(table, 1)?;
+                offset_delta
+            }
+            // chop_frame, same_frame_extended
+            248..=251 => table.u2()?,
+            // append_frame
+            252..=254 => {
+                let offset_delta = table.u2()?;
+                skip_types(table, frame_type as u16 - 251)?;
+                offset_delta
+            }
+            // full_frame
+            255 => {
+                let offset_delta = table.u2()?;
+                let locals = table.u2()?;
+                skip_types(table, locals)?;
+                let stack = table.u2()?;
+                skip_types(table, stack)?;
+                offset_delta
+            }
+            _ => {
+                let error = ParseError::InvalidStackMapFrame(frame_type);
+                return Err(error.at(table.position() - 1));
+            }
+        };
+        // every frame but the first is at least one byte after the previous one
+        let offset = match offsets.last() {
+            Some(last) => last + offset_delta as usize + 1,
+            None => offset_delta as usize,
+        };
+        offsets.push(offset);
+    }
+    Ok(offsets)
+}
+
+fn get_methods<T: Read>(
+    class_file: &mut ClassReader<T>,
+    cp: &[ConstantPool],
+) -> Result<Vec<Method>, ParseError> {
+    let method_count = class_file.u2()? as usize;
+    let mut methods: Vec<Method> = Vec::with_capacity(method_count);
+
+    for _ in 0..method_count {
+        let start = class_file.position();
+        let info = MethodInfo {
+            access_flags: class_file.u2()?,
+            name_index: class_file.u2()?,
+            descriptor_index: class_file.u2()?,
+            attributes_count: class_file.u2()?,
+        };
+
+        let name = parse_utf8(cp, info.name_index, start + 2)?;
+        let descriptor =
+            parse_descriptor(cp, info.descriptor_index, start + 4, is_method_descriptor)?;
+
+        // FIXME: this VM can only execute static methods, while every class has a constructor method <init>
+        if name == "<init>" && (info.access_flags & ACC_STATIC) > 0 {
+            let error = ParseError::Unsupported("Only static methods are supported by this VM.");
+            return Err(error.at(start));
+        }
+
+        // Read the list of static methods
+        let code = read_method_attributes(class_file, info.attributes_count, cp, &name)?;
+        // abstract methods, such as those of interfaces, and native methods have no code
+        if code.is_none() && info.access_flags & (ACC_ABSTRACT | ACC_NATIVE) == 0 {
+            return Err(ParseError::MissingCode(name).at(start));
+        }
+
+        methods.push(Method {
+            access_flags: info.access_flags,
+            name,
+            descriptor,
+            code,
+        });
+    }
+
+    Ok(methods)
+}
+
+fn get_bootstrap_methods<T: Read>(
+    class_file: &mut ClassReader<T>,
+    cp: &[ConstantPool],
+) -> Result<Vec<BootstrapMethod>, ParseError> {
+    let mut bootstrap_methods = Vec::new();
+    let attributes_count = class_file.u2()?;
+    for _ in 0..attributes_count {
+        let start = class_file.position();
         let ainfo = AttributeInfo {
-            attribute_name_index: read_2_bytes(class_file),
-            attribute_length: read_4_bytes(class_file),
+            attribute_name_index: class_file.u2()?,
+            attribute_length: class_file.u4()?,
+        };
+        let mut attributes = class_file.part(ainfo.attribute_length as usize)?;
+
+        if parse_utf8(cp, ainfo.attribute_name_index, start)? == "BootstrapMethods" {
+            let count = attributes.u2()?;
+            for _ in 0..count {
+                let method_ref = attributes.u2()?;
+                let arguments_count = attributes.u2()?;
+                let arguments = (0..arguments_count)
+                    .map(|_| attributes.u2())
+                    .collect::<Result<_, _>>()?;
+                bootstrap_methods.push(BootstrapMethod {
+                    method_ref,
+                    arguments,
+                });
+            }
+        }
+    }
+
+    Ok(bootstrap_methods)
+}
+
+/// Read an entire class file.
+///
+/// # Parameters
+/// * `class_file` - the source to read, e.g. an open file or a `Cursor` over bytes in memory
+/// # Return
+/// The parsed class file, or the first error found in it
+fn get_class<T: Read>(class_file: T) -> Result<ClassFile, ParseError> {
+    let mut class_file = ClassReader::new(class_file);
+
+    // Read the leading header of the class file
+    let header = get_class_header(&mut class_file)?;
+
+    // Read the constant pool
+    let constant_pool = get_constant_pool(&mut class_file)?;
+
+    // Read information about the class that was compiled.
+    let info_offset = class_file.position();
+    let info = get_class_info(&mut class_file)?;
+
+    // Read the list of fields
+    let fields = get_fields(&mut class_file, &constant_pool)?;
+
+    // Read the list of static methods
+    let method = get_methods(&mut class_file, &constant_pool)?;
+
+    // Read the attributes of the class, only BootstrapMethods is used
+    let bootstrap_methods = get_bootstrap_methods(&mut class_file, &constant_pool)?;
+
+    let class = ClassFile {
+        major_version: header.major_version,
+        access_flags: info.access_flags,
+        constant_pool,
+        this_class: info.this_calss,
+        super_class: info.super_class,
+        interfaces: info.interfaces,
+        fields,
+        method,
+        bootstrap_methods,
+    };
+    // the class is keyed by the name it declares, so that name has to resolve
+    if class.class_name(class.this_class).is_none() {
+        return Err(ParseError::BadConstant(class.this_class).at(info_offset + 2));
+    }
+    // the interfaces follow the access flags, the class, the superclass and their count
+    for (i, &index) in class.interfaces.iter().enumerate() {
+        if class.class_name(index).is_none() {
+            return Err(ParseError::BadConstant(index).at(info_offset + 8 + 2 * i));
+        }
+    }
+    Ok(class)
+}
+
+/// A jar file, i.e. a zip archive of class files with a manifest.
+/// Every entry is decompressed when the jar is opened.
+pub struct Jar {
+    /// The contents of the entries, keyed by their path, e.g. "com/example/Main.class"
+    entries: HashMap<String, Vec<u8>>,
+}
+
+impl Jar {
+    /// Read a jar file.
+    /// FIXME: only entries that are stored or deflated are supported, and no ZIP64
+    ///
+    /// # Parameters
+    /// * `path` - the path of the jar file
+    /// # Return
+    /// the jar, or an `InvalidData` error if it is not a valid zip archive
+    pub fn open(path: &Path) -> std::io::Result<Jar> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Jar::from_bytes(&bytes)
+            .map_err(|message| std::io::Error::new(std::io::ErrorKind::InvalidData, message))
+    }
+
+    /// Read a jar from the bytes of a zip archive.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Jar, String> {
+        let u16_at = |at: usize| -> Result<usize, String> {
+            let b = bytes.get(at..at + 2).ok_or("Truncated zip archive")?;
+            Ok(u16::from_le_bytes([b[0], b[1]]) as usize)
+        };
+        let u32_at = |at: usize| -> Result<usize, String> {
+            let b = bytes.get(at..at + 4).ok_or("Truncated zip archive")?;
+            Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        };
+
+        // the end of central directory record is last, before a comment of up to 64 KiB
+        let end = (0..=bytes.len().saturating_sub(22))
+            .rev()
+            .take(65536 + 22)
+            .find(|&at| bytes[at..].starts_with(&[0x50, 0x4b, 0x05, 0x06]))
+            .ok_or("Missing end of central directory")?;
+        let count = u16_at(end + 10)?;
+        let mut at = u32_at(end + 16)?;
+
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if u32_at(at)? != 0x0201_4b50 {
+                return Err("Bad central directory entry".to_string());
+            }
+            let method = u16_at(at + 10)?;
+            let compressed_size = u32_at(at + 20)?;
+            let name_length = u16_at(at + 28)?;
+            let name = bytes
+                .get(at + 46..at + 46 + name_length)
+                .ok_or("Truncated zip archive")?;
+            let name = String::from_utf8_lossy(name).to_string();
+            let header = u32_at(at + 42)?;
+            at += 46 + name_length + u16_at(at + 30)? + u16_at(at + 32)?;
+
+            // the data follows the local header, whose extra field may differ
+            if u32_at(header)? != 0x0403_4b50 {
+                return Err(format!("Bad local header of {}", name));
+            }
+            let start = header + 30 + u16_at(header + 26)? + u16_at(header + 28)?;
+            let data = bytes
+                .get(start..start + compressed_size)
+                .ok_or("Truncated zip archive")?;
+            let contents = match method {
+                0 => data.to_vec(),
+                8 => inflate(data).map_err(|e| format!("{}: {}", name, e))?,
+                _ => {
+                    return Err(format!(
+                        "{}: unsupported compression method {}",
+                        name, method
+                    ))
+                }
+            };
+            entries.insert(name, contents);
+        }
+        Ok(Jar { entries })
+    }
+
+    /// Get the contents of an entry.
+    /// The classes of a Spring Boot jar are found under `BOOT-INF/classes/` as well.
+    ///
+    /// # Parameters
+    /// * `name` - the path of the entry, e.g. "com/example/Main.class"
+    pub fn read(&self, name: &str) -> Option<&[u8]> {
+        self.entries
+            .get(name)
+            .or_else(|| self.entries.get(&format!("BOOT-INF/classes/{}", name)))
+            .map(Vec::as_slice)
+    }
+
+    /// Get the value of an attribute of the main section of the manifest.
+    ///
+    /// # Parameters
+    /// * `key` - the name of the attribute, e.g. "Main-Class"
+    pub fn manifest_attribute(&self, key: &str) -> Option<String> {
+        let manifest = String::from_utf8_lossy(self.read("META-INF/MANIFEST.MF")?).to_string();
+        // a line starting with a space continues the previous one
+        let manifest = manifest.replace("\r\n", "\n").replace("\n ", "");
+        manifest
+            .lines()
+            // the main section ends at the first empty line
+            .take_while(|line| !line.is_empty())
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.eq_ignore_ascii_case(key)
+                    .then(|| value.trim().to_string())
+            })
+    }
+
+    /// Get the binary name of the class whose `main` runs the jar, e.g. "com.example.Main".
+    /// The `Main-Class` of a Spring Boot jar is its launcher, which runs the `Start-Class`.
+    pub fn main_class(&self) -> Option<String> {
+        self.manifest_attribute("Start-Class")
+            .or_else(|| self.manifest_attribute("Main-Class"))
+    }
+
+    /// Get the paths of the jars and directories the jar depends on, from the `Class-Path`
+    /// of its manifest. They are relative to the directory of the jar, and directories end with '/'.
+    pub fn class_path(&self) -> Vec<String> {
+        self.manifest_attribute("Class-Path")
+            .map(|paths| paths.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// Open the jars nested in the `BOOT-INF/lib/` directory of a Spring Boot jar, sorted.
+    /// Entries which are not valid jars are skipped.
+    pub fn nested_jars(&self) -> Vec<Jar> {
+        let mut names: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|name| name.starts_with("BOOT-INF/lib/") && name.ends_with(".jar"))
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| Jar::from_bytes(&self.entries[name]).ok())
+            .collect()
+    }
+
+    /// Get the binary names of the classes in the jar, sorted.
+    pub fn class_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .entries
+            .keys()
+            .filter_map(|name| name.strip_suffix(".class"))
+            .map(|name| name.replace('/', "."))
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// The canonical Huffman code of a DEFLATE block.
+struct Huffman {
+    /// The number of codes of each length
+    counts: [u16; 16],
+    /// The symbols, ordered by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Build the code from the code length of every symbol, 0 for unused symbols.
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for length in 1..16 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == length) {
+                symbols.push(symbol as u16);
+            }
+        }
+        Huffman { counts, symbols }
+    }
+}
+
+/// Reads the bits of a DEFLATE stream, least significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// The position of the next bit
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32, &'static str> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self
+                .data
+                .get(self.position / 8)
+                .ok_or("Truncated deflate data")?;
+            value |= ((byte >> (self.position % 8)) as u32 & 1) << i;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    /// Decode a symbol, reading its code one bit at a time.
+    fn decode(&mut self, huffman: &Huffman) -> Result<u16, &'static str> {
+        // the first code and the index of the first symbol of the current length
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &huffman.counts[1..] {
+            code |= self.bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("Invalid Huffman code")
+    }
+}
+
+/// Decompress raw DEFLATE data (RFC 1951), as stored in zip archives.
+fn inflate(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    const LENGTH_BASE: [u16; 29] = [
+        3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115,
+        131, 163, 195, 227, 258,
+    ];
+    const LENGTH_EXTRA: [u8; 29] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+    ];
+    const DISTANCE_BASE: [u16; 30] = [
+        1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+        2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+    ];
+    const DISTANCE_EXTRA: [u8; 30] = [
+        0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12,
+        13, 13,
+    ];
+    // the order the code lengths of the code length alphabet are stored in
+    const CODE_LENGTH_ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    let mut reader = BitReader { data, position: 0 };
+    let mut output = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        let (literals, distances) = match reader.bits(2)? {
+            // stored block, starting at the next byte
+            0 => {
+                let start = reader.position.div_ceil(8);
+                let header = data.get(start..start + 4).ok_or("Truncated deflate data")?;
+                let length = u16::from_le_bytes([header[0], header[1]]) as usize;
+                let block = data
+                    .get(start + 4..start + 4 + length)
+                    .ok_or("Truncated deflate data")?;
+                output.extend_from_slice(block);
+                reader.position = (start + 4 + length) * 8;
+                if last {
+                    return Ok(output);
+                }
+                continue;
+            }
+            // fixed Huffman codes
+            1 => {
+                let mut lengths = [8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+            }
+            // dynamic Huffman codes, described by a code of their code lengths
+            2 => {
+                let literal_count = reader.bits(5)? as usize + 257;
+                let distance_count = reader.bits(5)? as usize + 1;
+                let code_length_count = reader.bits(4)? as usize + 4;
+                let mut code_lengths = [0; 19];
+                for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+                    code_lengths[symbol] = reader.bits(3)? as u8;
+                }
+                let code_length_code = Huffman::new(&code_lengths);
+
+                let mut lengths = Vec::with_capacity(literal_count + distance_count);
+                while lengths.len() < literal_count + distance_count {
+                    let (length, repeat) = match reader.decode(&code_length_code)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => {
+                            let previous = *lengths.last().ok_or("Repeat without a length")?;
+                            (previous, 3 + reader.bits(2)?)
+                        }
+                        17 => (0, 3 + reader.bits(3)?),
+                        _ => (0, 11 + reader.bits(7)?),
+                    };
+                    lengths.extend(std::iter::repeat_n(length, repeat as usize));
+                }
+                if lengths.len() > literal_count + distance_count {
+                    return Err("Too many code lengths");
+                }
+                (
+                    Huffman::new(&lengths[..literal_count]),
+                    Huffman::new(&lengths[literal_count..]),
+                )
+            }
+            _ => return Err("Invalid block type"),
+        };
+
+        loop {
+            let symbol = reader.decode(&literals)? as usize;
+            match symbol {
+                0..=255 => output.push(symbol as u8),
+                256 => break,
+                _ => {
+                    // a length and a distance copy earlier output
+                    let index = symbol - 257;
+                    if index >= LENGTH_BASE.len() {
+                        return Err("Invalid length symbol");
+                    }
+                    let length = LENGTH_BASE[index] as usize
+                        + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                    let index = reader.decode(&distances)? as usize;
+                    if index >= DISTANCE_BASE.len() {
+                        return Err("Invalid distance symbol");
+                    }
+                    let distance = DISTANCE_BASE[index] as usize
+                        + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                    if distance > output.len() {
+                        return Err("Distance too far back");
+                    }
+                    // the copy may overlap what it produces
+                    let start = output.len() - distance;
+                    for i in 0..length {
+                        output.push(output[start + i]);
+                    }
+                }
+            }
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+impl Vm {
+    /// Allocate an object on the heap, even if it is over the limit of `set_max_heap`.
+    /// Only the VM itself uses this directly, e.g. for an exception it is throwing;
+    /// the program and the class library allocate with `try_alloc`.
+    ///
+    /// # Parameters
+    /// * `object` - the object to store
+    /// # Return
+    /// the reference to the object
+    pub fn alloc(&mut self, object: HeapObject) -> i32 {
+        let reference = match self.free_handles.pop() {
+            Some(reference) => reference,
+            None => {
+                self.handles.push(FREE_HANDLE);
+                self.handles.len() as i32
+            }
+        };
+        self.handles[reference as usize - 1] = self.heap.len();
+        self.heap.push(object);
+        self.heap_references.push(reference);
+        reference
+    }
+
+    /// Save the slots of the running frame as roots of the garbage collector.
+    /// Frames call this before calling a method or allocating, so that the objects
+    /// they reference survive collections run meanwhile.
+    ///
+    /// # Parameters
+    /// * `locals` - the local variables of the frame
+    /// * `op_stack` - the operand stack of the frame, including the arguments of the call
+    pub fn save_frame_roots(&mut self, locals: &[i32], op_stack: &[i32]) {
+        // the roots of frames which have returned are stale
+        let depth = self.call_depth.max(1);
+        self.frame_roots.truncate(depth);
+        self.frame_roots.resize_with(depth, Vec::new);
+        let roots = &mut self.frame_roots[depth - 1];
+        roots.clear();
+        roots.extend_from_slice(locals);
+        roots.extend_from_slice(op_stack);
+    }
+
+    /// Collect the objects that cannot be reached from the roots and move the
+    /// remaining ones to the front of the heap.
+    /// The roots are the static fields, the interned strings, the frames saved by
+    /// `save_frame_roots` up to the current call depth, and those of the parked threads. Any value in them that is a
+    /// reference to a live object keeps it alive, even if it is really an int.
+    pub fn collect_garbage(&mut self) {
+        // the frame at depth 0 is that of the VM itself, e.g. allocating the arguments of main
+        let depth = self.call_depth.max(1).min(self.frame_roots.len());
+        let mut pending: Vec<i32> = self.frame_roots[..depth]
+            .iter()
+            .flatten()
+            .chain(self.static_fields.values().flatten())
+            .chain(self.interned_strings.values())
+            .chain(self.class_objects.values())
+            .chain(self.native_static_fields.values())
+            .chain(
+                self.parked_frames
+                    .values()
+                    .flat_map(|(call_depth, frame_roots)| {
+                        frame_roots[..(*call_depth).max(1).min(frame_roots.len())]
+                            .iter()
+                            .flatten()
+                    }),
+            )
+            .chain(self.thread_handles.keys())
+            .copied()
+            .collect();
+
+        // mark
+        let mut marked = vec![false; self.heap.len()];
+        while let Some(reference) = pending.pop() {
+            if reference <= 0 || reference as usize > self.handles.len() {
+                continue;
+            }
+            let index = self.handles[reference as usize - 1];
+            if index == FREE_HANDLE || marked[index] {
+                continue;
+            }
+            marked[index] = true;
+            pending.extend(self.heap[index].references());
+        }
+
+        // weak references to collected objects are cleared
+        for index in 0..self.heap.len() {
+            if let HeapObject::WeakReference(referent) = self.heap[index] {
+                if marked[index] && referent != 0 && !marked[self.handles[referent as usize - 1]] {
+                    self.heap[index] = HeapObject::WeakReference(0);
+                }
+            }
+        }
+
+        // compact, sliding the live objects down in order
+        let mut live = 0;
+        for (index, &is_live) in marked.iter().enumerate() {
+            let reference = self.heap_references[index];
+            if is_live {
+                self.heap.swap(live, index);
+                self.heap_references.swap(live, index);
+                self.handles[reference as usize - 1] = live;
+                live += 1;
+            } else {
+                self.gc_stats.bytes_freed += self.heap[index].size() as u64;
+                self.handles[reference as usize - 1] = FREE_HANDLE;
+                self.free_handles.push(reference);
+            }
+        }
+        self.heap.truncate(live);
+        self.heap_references.truncate(live);
+
+        self.gc_stats.collections += 1;
+        self.next_gc = (live * 2).max(GC_MIN_THRESHOLD);
+    }
+
+    /// Get what the garbage collector did so far.
+    pub fn gc_stats(&self) -> GcStats {
+        GcStats {
+            heap_size: self.heap.len(),
+            ..self.gc_stats
+        }
+    }
+
+    /// Start recording how many times each opcode is executed.
+    pub fn record_opcodes(&mut self) {
+        self.opcode_counts.get_or_insert_with(HashMap::new);
+    }
+
+    /// Get how many times each distinct opcode was executed,
+    /// empty unless `record_opcodes` was called before running.
+    pub fn opcode_histogram(&self) -> HashMap<u8, u64> {
+        self.opcode_counts.clone().unwrap_or_default()
+    }
+
+    /// Start recording which constant pool entries the executed instructions refer to,
+    /// e.g. to find the constants a class file could do without.
+    pub fn record_constants(&mut self) {
+        self.touched_constants.get_or_insert_with(HashMap::new);
+    }
+
+    /// Get the constant pool indexes the executed instructions referred to, by class,
+    /// empty unless `record_constants` was called before running.
+    /// Only the entries named by the instructions are listed, not those they refer to
+    /// in turn, such as the Class and NameAndType of a Methodref.
+    pub fn touched_constants(&self) -> HashMap<String, HashSet<u16>> {
+        self.touched_constants.clone().unwrap_or_default()
+    }
+
+    /// Get the time spent in each method of the program so far,
+    /// empty unless profiling was enabled with `VmBuilder::profile`.
+    /// Methods of the class library are executed natively and are not profiled.
+    pub fn get_profile(&self) -> &ProfileData {
+        &self.profile
+    }
+
+    /// Limit the number of objects the heap may hold.
+    /// Once it is full, allocating instructions throw `OutOfMemoryError`.
+    ///
+    /// # Parameters
+    /// * `max_heap` - the maximum number of objects
+    pub fn set_max_heap(&mut self, max_heap: usize) {
+        self.max_heap = Some(max_heap);
+    }
+
+    /// Run the threads the program starts on OS threads. They share this `Vm` and take
+    /// turns running it: a thread hands it over every `DEADLINE_CHECK_INTERVAL` instructions
+    /// and whenever it blocks, e.g. in `join`, `sleep` or `CountDownLatch.await`.
+    /// Without it, `Thread.start` runs the thread to completion before returning.
+    ///
+    /// # Parameters
+    /// * `enabled` - whether `Thread.start` spawns an OS thread
+    pub fn set_threading(&mut self, enabled: bool) {
+        self.scheduler = enabled.then(|| Arc::new(Scheduler::default()));
+    }
+
+    /// Wait for the threads the program started, unless it was aborted.
+    fn wait_for_started_threads<T>(
+        &mut self,
+        result: &Result<T, RuntimeError>,
+    ) -> Result<(), RuntimeError> {
+        match result {
+            Ok(_) | Err(RuntimeError::Exception { .. }) => self.wait_for_threads(),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Wait until every thread the program started has ended, as java does before exiting.
+    ///
+    /// # Return
+    /// `RuntimeError::DeadlockDetected` if the threads wait for each other;
+    /// the error which aborted one of them
+    pub fn wait_for_threads(&mut self) -> Result<(), RuntimeError> {
+        wait_until(self, |vm| {
+            vm.thread_handles
+                .keys()
+                .all(|&thread| !is_thread_alive(vm, thread))
+        })?;
+        for (_, handle) in self.thread_handles.drain() {
+            // a thread which panicked has already ended the process
+            let _ = handle.join();
+        }
+        Ok(())
+    }
+
+    /// Allocate an object on the heap for the program or a method of the class library,
+    /// collecting garbage first if the heap is full.
+    /// The object is a root of the running frame until it saves its roots again,
+    /// so a native method may allocate several objects before returning them.
+    ///
+    /// # Parameters
+    /// * `object` - the object to store
+    /// # Return
+    /// the reference to the object, or `OutOfMemoryError` if the heap is full
+    pub fn try_alloc(&mut self, object: HeapObject) -> Result<i32, RuntimeError> {
+        if self.max_heap.is_some_and(|max| self.heap.len() >= max) {
+            self.collect_garbage();
+        }
+        if self.max_heap.is_some_and(|max| self.heap.len() >= max) {
+            return Err(RuntimeError::exception(
+                "java/lang/OutOfMemoryError",
+                "Java heap space",
+            ));
+        }
+        let reference = self.alloc(object);
+        let depth = self.call_depth.max(1);
+        if self.frame_roots.len() < depth {
+            self.frame_roots.resize_with(depth, Vec::new);
+        }
+        self.frame_roots[depth - 1].push(reference);
+        Ok(reference)
+    }
+
+    /// Get the unique string object with the given contents, allocating it on first use.
+    /// String literals are interned, so equal literals are the same object.
+    ///
+    /// # Parameters
+    /// * `s` - the contents of the string
+    /// # Return
+    /// the reference to the string, or `OutOfMemoryError` if the heap is full
+    pub fn intern(&mut self, s: &str) -> Result<i32, RuntimeError> {
+        if let Some(&reference) = self.interned_strings.get(s) {
+            return Ok(reference);
+        }
+        let reference = self.try_alloc(HeapObject::String(s.to_string()))?;
+        self.interned_strings.insert(s.to_string(), reference);
+        Ok(reference)
+    }
+
+    /// Get the unique `java.lang.Class` object of a class, allocating it on first use.
+    ///
+    /// # Parameters
+    /// * `name` - the internal name of the class, e.g. "Config"
+    /// # Return
+    /// the reference to the class object, or `OutOfMemoryError` if the heap is full
+    pub fn class_object(&mut self, name: &str) -> Result<i32, RuntimeError> {
+        if let Some(&reference) = self.class_objects.get(name) {
+            return Ok(reference);
+        }
+        let reference = self.try_alloc(HeapObject::Class(name.to_string()))?;
+        self.class_objects.insert(name.to_string(), reference);
+        Ok(reference)
+    }
+
+    /// Enter the monitor of an object, e.g. for `monitorenter` or a synchronized method.
+    /// A thread may enter a monitor it holds again, and waits while another thread holds it.
+    ///
+    /// # Parameters
+    /// * `reference` - the object whose monitor is entered
+    /// # Return
+    /// `NullPointerException` if the reference is null;
+    /// `RuntimeError::DeadlockDetected` if the thread holding it waits too
+    pub fn enter_monitor(&mut self, reference: i32) -> Result<(), RuntimeError> {
+        if reference == 0 {
+            return Err(RuntimeError::Exception {
+                class: "java/lang/NullPointerException".to_string(),
+                message: None,
+                cause: None,
+            });
+        }
+        let me = self.current_thread;
+        wait_until(self, |vm| {
+            vm.monitors
+                .get(&reference)
+                .is_none_or(|&(owner, _)| owner == me)
+        })?;
+        self.monitors.entry(reference).or_insert((me, 0)).1 += 1;
+        Ok(())
+    }
+
+    /// Exit the monitor of an object entered by `enter_monitor`.
+    ///
+    /// # Parameters
+    /// * `reference` - the object whose monitor is exited
+    /// # Return
+    /// `NullPointerException` if the reference is null;
+    /// `IllegalMonitorStateException` if the running thread does not hold the monitor
+    pub fn exit_monitor(&mut self, reference: i32) -> Result<(), RuntimeError> {
+        if reference == 0 {
+            return Err(RuntimeError::Exception {
+                class: "java/lang/NullPointerException".to_string(),
+                message: None,
+                cause: None,
+            });
+        }
+        match self.monitors.get_mut(&reference) {
+            Some((owner, count)) if *owner == self.current_thread && *count > 1 => *count -= 1,
+            Some((owner, _)) if *owner == self.current_thread => {
+                self.monitors.remove(&reference);
+            }
+            _ => {
+                return Err(RuntimeError::Exception {
+                    class: "java/lang/IllegalMonitorStateException".to_string(),
+                    message: None,
+                    cause: None,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the object behind a reference.
+    /// Assert that the reference is not null.
+    ///
+    /// # Parameters
+    /// * `reference` - the reference returned by `alloc`
+    /// # Return
+    /// the referenced object
+    pub fn get_object(&self, reference: i32) -> &HeapObject {
+        if reference == 0 {
+            panic!("NullPointerException");
+        }
+        &self.heap[self.handles[reference as usize - 1]]
+    }
+
+    /// Get the object behind a reference for modification.
+    /// Assert that the reference is not null.
+    pub fn get_object_mut(&mut self, reference: i32) -> &mut HeapObject {
+        if reference == 0 {
+            panic!("NullPointerException");
+        }
+        &mut self.heap[self.handles[reference as usize - 1]]
+    }
+
+    /// Get a class by its internal name, loading it from the class path on first use.
+    /// The superclasses of the class are loaded before it.
+    ///
+    /// # Parameters
+    /// * `name` - the internal name of the class, e.g. "Config"
+    /// # Return
+    /// the parsed class file;
+    /// `ClassNotFoundException` if the class is not on the class path;
+    /// `ClassFormatError` if the class file cannot be parsed;
+    /// `NoClassDefFoundError` if a superclass is not on the class path;
+    /// `ClassCircularityError` if the class is its own superclass
+    pub fn load_class(&mut self, name: &str) -> Result<Arc<ClassFile>, RuntimeError> {
+        if let Some(class) = self.classes.get(name) {
+            return Ok(class.clone());
+        }
+        // a class reached again while its superclasses are loading extends itself,
+        // which would otherwise make every walk up the superclasses loop forever
+        if self.loading.contains(name) {
+            return Err(RuntimeError::exception(
+                "java/lang/ClassCircularityError",
+                &name.replace('/', "."),
+            ));
+        }
+
+        let file_name = format!("{}.class", name);
+        let file = std::iter::once(&self.class_path)
+            .chain(&self.directories)
+            .find_map(|directory| File::open(directory.join(&file_name)).ok());
+        let class = match file {
+            Some(file) => get_class(BufReader::new(file)),
+            None => match self.jars.iter().find_map(|jar| jar.read(&file_name)) {
+                Some(bytes) => get_class(bytes),
+                None => {
+                    return Err(RuntimeError::exception(
+                        "java/lang/ClassNotFoundException",
+                        &name.replace('/', "."),
+                    ))
+                }
+            },
+        };
+        let class = class.map_err(|e| {
+            RuntimeError::exception("java/lang/ClassFormatError", &format!("{}: {:?}", name, e))
+        })?;
+        // a class file found by its path may declare another class, e.g. one of another package
+        if class.name() != name {
+            return Err(RuntimeError::exception(
+                "java/lang/NoClassDefFoundError",
+                &format!("{} (wrong name: {})", name, class.name()),
+            ));
+        }
+
+        let superclass = class
+            .class_name(class.super_class)
+            .filter(|superclass| !superclass.starts_with("java/"))
+            .map(str::to_string);
+        if let Some(superclass) = superclass {
+            self.loading.insert(name.to_string());
+            let loaded = self.load_class(&superclass);
+            self.loading.remove(name);
+            loaded.map_err(|e| match e {
+                RuntimeError::Exception { class, .. }
+                    if class == "java/lang/ClassNotFoundException" =>
+                {
+                    RuntimeError::exception("java/lang/NoClassDefFoundError", &superclass)
+                }
+                e => e,
+            })?;
+        }
+
+        let class = Arc::new(class);
+        self.classes.insert(name.to_string(), class.clone());
+        Ok(class)
+    }
+
+    /// Get a class by its internal name, loading and initializing it on first use.
+    /// Initialization gives the static fields their initial values and runs `<clinit>`.
+    ///
+    /// # Parameters
+    /// * `name` - the internal name of the class, e.g. "Config"
+    /// # Return
+    /// the parsed class file, or the error raised by `<clinit>`, an exception other than
+    /// an error being wrapped into an `ExceptionInInitializerError`;
+    /// `NoClassDefFoundError` if the class is not on the class path,
+    /// or if its `<clinit>` threw before
+    pub fn initialize_class(&mut self, name: &str) -> Result<Arc<ClassFile>, RuntimeError> {
+        // a class referred to by the running code was found when it was compiled,
+        // so failing to find it now is an error rather than an exception
+        let class = self.load_class(name).map_err(|e| match e {
+            RuntimeError::Exception { class, .. }
+                if class == "java/lang/ClassNotFoundException" =>
+            {
+                RuntimeError::exception("java/lang/NoClassDefFoundError", name)
+            }
+            e => e,
+        })?;
+        if self.erroneous.contains(name) {
+            return Err(RuntimeError::exception(
+                "java/lang/NoClassDefFoundError",
+                &format!("Could not initialize class {}", name.replace('/', ".")),
+            ));
+        }
+        // mark the class first, so that a class referring to itself in <clinit> is not initialized again
+        if !self.initialized.insert(name.to_string()) {
+            return Ok(class);
+        }
+
+        for field in class
+            .fields
+            .iter()
+            .filter(|f| f.access_flags & ACC_STATIC != 0)
+        {
+            let value = match field
+                .constant_value
+                .map(|i| get_constant(&class.constant_pool, i))
+            {
+                Some(&ConstantPool::Integer { bytes }) => vec![bytes],
+                Some(&ConstantPool::Float { bytes }) => vec![bytes as i32],
+                // the wide constants take two slots, high bits first as ldc2_w pushes them
+                Some(&ConstantPool::Long {
+                    high_bytes,
+                    low_bytes,
+                })
+                | Some(&ConstantPool::Double {
+                    high_bytes,
+                    low_bytes,
+                }) => vec![high_bytes as i32, low_bytes as i32],
+                Some(&ConstantPool::String { string_index }) => {
+                    vec![self.intern(get_utf8(&class.constant_pool, string_index))?]
+                }
+                Some(_) => panic!("Unsupported ConstantValue of field {}", field.name),
+                None => vec![0; get_type_slots(&field.descriptor)],
+            };
+            self.static_fields
+                .insert((name.to_string(), field.name.clone()), value);
+        }
+
+        if let Some(clinit) = class.method.iter().find(|m| m.name == "<clinit>") {
+            let locals = vec![0i32; clinit.max_locals()];
+            match execute(self, clinit, locals, &class) {
+                Ok(_) => {}
+                Err(RuntimeError::Exception {
+                    class: thrown,
+                    message,
+                    cause,
+                }) => {
+                    self.erroneous.insert(name.to_string());
+                    let thrown = RuntimeError::Exception {
+                        class: thrown,
+                        message,
+                        cause,
+                    };
+                    return Err(match &thrown {
+                        RuntimeError::Exception { class, .. }
+                            if is_subclass_of(class, "java/lang/Error") =>
+                        {
+                            thrown
+                        }
+                        _ => RuntimeError::Exception {
+                            class: "java/lang/ExceptionInInitializerError".to_string(),
+                            message: None,
+                            cause: Some(Box::new(thrown)),
+                        },
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(class)
+    }
+
+    /// Find the class declaring a static field as JVMS 5.4.3.2 resolves it: the class
+    /// itself, then its superinterfaces, then its superclass and so on, and initialize it.
+    /// A field inherited from a superclass is the same field in every subclass, and
+    /// reading it does not initialize the subclass.
+    ///
+    /// # Parameters
+    /// * `class_name` - the internal name of the class the instruction names, e.g. "Kid"
+    /// * `name` - the name of the field
+    /// * `descriptor` - the descriptor of the field, e.g. "I"
+    /// # Return
+    /// the key of the field in `static_fields`, or the error raised by initializing
+    /// the declaring class; `NoSuchFieldError` if no class declares the field
+    pub fn resolve_static_field(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<(String, String), RuntimeError> {
+        let declares = |class: &ClassFile| {
+            class.fields.iter().any(|f| {
+                f.access_flags & ACC_STATIC != 0 && f.name == name && f.descriptor == descriptor
+            })
+        };
+        // the classes were found when the running code was compiled, as in `initialize_class`
+        let load = |vm: &mut Vm, name: &str| {
+            vm.load_class(name).map_err(|e| match e {
+                RuntimeError::Exception { class, .. }
+                    if class == "java/lang/ClassNotFoundException" =>
+                {
+                    RuntimeError::exception("java/lang/NoClassDefFoundError", name)
+                }
+                e => e,
+            })
         };
-        let mut attributes = vec![0u8; ainfo.attribute_length as usize];
-        class_file.read_exact(&mut attributes).unwrap();
-        let mut attributes = std::io::Cursor::new(attributes);
+        let mut current = Some(class_name.to_string());
+        let mut declaring = None;
+        while let Some(class_name) = current.filter(|c| !c.starts_with("java/")) {
+            let class = load(self, &class_name)?;
+            if declares(&class) {
+                declaring = Some(class_name);
+                break;
+            }
+            // javac rejects a field declared by two superinterfaces, so their order does not matter
+            let mut pending: Vec<String> = class.interface_names().map(str::to_string).collect();
+            while let Some(interface) = pending.pop() {
+                if interface.starts_with("java/") {
+                    continue;
+                }
+                let interface_class = load(self, &interface)?;
+                if declares(&interface_class) {
+                    declaring = Some(interface);
+                    break;
+                }
+                pending.extend(interface_class.interface_names().map(str::to_string));
+            }
+            if declaring.is_some() {
+                break;
+            }
+            current = class.class_name(class.super_class).map(str::to_string);
+        }
+        let declaring =
+            declaring.ok_or_else(|| RuntimeError::exception("java/lang/NoSuchFieldError", name))?;
+        self.initialize_class(&declaring)?;
+        Ok((declaring, name.to_string()))
+    }
+
+    /// Create an instance of a class of the program, before its constructor runs.
+    /// Its fields and those of its superclasses hold their default values.
+    ///
+    /// # Parameters
+    /// * `name` - the internal name of the class, e.g. "Circle"
+    /// # Return
+    /// the new object, or the error raised by initializing the classes
+    pub fn new_object(&mut self, name: &str) -> Result<HeapObject, RuntimeError> {
+        let mut fields = HashMap::new();
+        let mut current = Some(name.to_string());
+        while let Some(class_name) = current.filter(|c| !c.starts_with("java/")) {
+            let class = self.initialize_class(&class_name)?;
+            for field in class
+                .fields
+                .iter()
+                .filter(|f| f.access_flags & ACC_STATIC == 0)
+            {
+                fields
+                    .entry(field.name.clone())
+                    .or_insert_with(|| vec![0; get_type_slots(&field.descriptor)]);
+            }
+            current = class.class_name(class.super_class).map(str::to_string);
+        }
+        Ok(HeapObject::Object {
+            class_name: name.to_string(),
+            fields,
+        })
+    }
+
+    /// Find the implementation of an instance method for the runtime class of an object,
+    /// from that class up through its superclasses, then among the default methods of
+    /// their superinterfaces as `find_default_method` does.
+    ///
+    /// # Parameters
+    /// * `name` - the internal name of the runtime class, e.g. "Circle"
+    /// * `method_name` - the name of the method, e.g. "area"
+    /// * `descriptor` - the descriptor of the method, e.g. "()I"
+    /// # Return
+    /// the class or interface declaring the method, `None` if it is inherited from the
+    /// class library; the error of `find_default_method`
+    pub fn find_virtual_method(
+        &mut self,
+        name: &str,
+        method_name: &str,
+        descriptor: &str,
+    ) -> Result<Option<Arc<ClassFile>>, RuntimeError> {
+        let mut interfaces = Vec::new();
+        let mut current = Some(name.to_string());
+        while let Some(class_name) = current.filter(|c| !c.starts_with("java/")) {
+            let class = self.initialize_class(&class_name)?;
+            let declared = class
+                .method
+                .iter()
+                .any(|m| m.name == method_name && m.descriptor == descriptor);
+            if declared {
+                return Ok(Some(class));
+            }
+            interfaces.extend(class.interface_names().map(str::to_string));
+            current = class.class_name(class.super_class).map(str::to_string);
+        }
+        self.find_default_method(&interfaces, method_name, descriptor)
+    }
+
+    /// Find the default method inherited from some interfaces and their superinterfaces.
+    /// As JVMS 5.4.3.3 describes, it is the maximally-specific declaration of the method,
+    /// i.e. the one no other declaration in a subinterface of its interface overrides,
+    /// if exactly one of those is not abstract.
+    /// The interfaces of the class library have no default methods here.
+    ///
+    /// # Parameters
+    /// * `interfaces` - the internal names of the interfaces, e.g. those of a class
+    /// * `method_name` - the name of the method, e.g. "corners"
+    /// * `descriptor` - the descriptor of the method, e.g. "()I"
+    /// # Return
+    /// the interface declaring the method, `None` if no maximally-specific declaration
+    /// has code; `IncompatibleClassChangeError` if several do
+    pub fn find_default_method(
+        &mut self,
+        interfaces: &[String],
+        method_name: &str,
+        descriptor: &str,
+    ) -> Result<Option<Arc<ClassFile>>, RuntimeError> {
+        // every superinterface, once each
+        let mut superinterfaces: HashMap<String, Arc<ClassFile>> = HashMap::new();
+        let mut pending = interfaces.to_vec();
+        while let Some(name) = pending.pop() {
+            if name.starts_with("java/") || superinterfaces.contains_key(&name) {
+                continue;
+            }
+            let interface = self.load_class(&name)?;
+            pending.extend(interface.interface_names().map(str::to_string));
+            superinterfaces.insert(name, interface);
+        }
 
-        let type_constant = get_constant(cp, ainfo.attribute_name_index);
-        let type_constant = match type_constant {
-            ConstantPool::Utf8(s) => s.clone(),
-            _ => panic!("Expected a UTF8"),
+        // the access flags of the declaration of the method in an interface, if any
+        let declaration = |interface: &ClassFile| {
+            interface
+                .method
+                .iter()
+                .find(|m| {
+                    m.name == method_name
+                        && m.descriptor == descriptor
+                        && m.access_flags & (ACC_PRIVATE | ACC_STATIC) == 0
+                })
+                .map(|m| m.access_flags)
         };
-        if type_constant == "Code" {
-            if found_code {
-                panic!("Duplicate method code");
+        let extends = |interface: &str, superinterface: &str| {
+            let mut pending = vec![interface];
+            let mut seen = HashSet::new();
+            while let Some(name) = pending.pop() {
+                if let Some(class) = superinterfaces.get(name).filter(|_| seen.insert(name)) {
+                    if class.interface_names().any(|name| name == superinterface) {
+                        return true;
+                    }
+                    pending.extend(class.interface_names());
+                }
+            }
+            false
+        };
+        let declaring: Vec<&str> = superinterfaces
+            .iter()
+            .filter(|(_, interface)| declaration(interface).is_some())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let mut defaults: Vec<&str> = declaring
+            .iter()
+            .copied()
+            .filter(|&name| !declaring.iter().any(|&other| extends(other, name)))
+            .filter(|&name| {
+                declaration(&superinterfaces[name]).is_some_and(|flags| flags & ACC_ABSTRACT == 0)
+            })
+            .collect();
+        defaults.sort_unstable();
+        match defaults[..] {
+            [] => Ok(None),
+            [name] => {
+                let name = name.to_string();
+                self.initialize_class(&name).map(Some)
+            }
+            _ => Err(RuntimeError::exception(
+                "java/lang/IncompatibleClassChangeError",
+                &format!(
+                    "Conflicting default methods: {}",
+                    defaults
+                        .iter()
+                        .map(|name| format!("{}.{}", name.replace('/', "."), method_name))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ),
+            )),
+        }
+    }
+
+    /// Run the `main` method of a class with `args` as its `String[]` argument.
+    ///
+    /// # Parameters
+    /// * `class` - the class declaring `public static void main(String[] args)`
+    /// # Return
+    /// `NoSuchMethodError` if the class has no main method;
+    /// any error of `execute` if main does not return normally
+    pub fn invoke_main(&mut self, class: &ClassFile) -> Result<(), RuntimeError> {
+        let main_method = class
+            .method
+            .iter()
+            .find(|m| m.name == "main" && m.descriptor == "([Ljava/lang/String;)V")
+            .ok_or_else(|| RuntimeError::exception("java/lang/NoSuchMethodError", "main"))?;
+
+        let args = self
+            .args
+            .clone()
+            .into_iter()
+            .map(|arg| self.try_alloc(HeapObject::String(arg)))
+            .collect::<Result<_, _>>()?;
+        let mut locals = vec![0i32; main_method.max_locals().max(1)];
+        locals[0] = self.try_alloc(HeapObject::ReferenceArray(args))?;
+        let result = execute(self, main_method, locals, class);
+        self.wait_for_started_threads(&result)?;
+        match result? {
+            None => Ok(()),
+            Some(_) => panic!("main() should return void"),
+        }
+    }
+
+    /// Run a static method of a class with arguments given as strings, e.g. on the command line.
+    ///
+    /// # Parameters
+    /// * `class` - the class declaring the method
+    /// * `method` - the static method, e.g. one found by `select_method`
+    /// * `args` - an argument for every parameter, converted by `parse_argument`
+    /// # Return
+    /// the value returned by the method formatted like `String.valueOf`, `None` for void;
+    /// `IllegalArgumentException` if the arguments do not match the parameters;
+    /// any error of `execute`
+    pub fn invoke_static_method(
+        &mut self,
+        class: &ClassFile,
+        method: &Method,
+        args: &[String],
+    ) -> Result<Option<String>, RuntimeError> {
+        let types = get_param_types(&method.descriptor);
+        if types.len() != args.len() {
+            return Err(RuntimeError::exception(
+                "java/lang/IllegalArgumentException",
+                &format!(
+                    "{}{} takes {} arguments, got {}",
+                    method.name,
+                    method.descriptor,
+                    types.len(),
+                    args.len()
+                ),
+            ));
+        }
+        let mut locals = vec![0i32; method.max_locals().max(get_param_slots(&method.descriptor))];
+        let mut slot = 0;
+        for (descriptor, arg) in types.into_iter().zip(args) {
+            let value = parse_argument(self, descriptor, arg)?;
+            locals[slot..slot + value.len()].copy_from_slice(&value);
+            slot += value.len();
+        }
+        let return_type = &method.descriptor[method.descriptor.find(')').unwrap() + 1..];
+        let result = execute(self, method, locals, class);
+        self.wait_for_started_threads(&result)?;
+        Ok(result?.map(|value| format_argument(self, &format!("({})", return_type), &[value])))
+    }
+
+    /// Initialize a class and run its `main` method like `invoke_main`, aborting both
+    /// once `timeout` has elapsed, so a static initializer which never ends is caught too.
+    ///
+    /// # Parameters
+    /// * `class` - the parsed class file, added to the loaded classes if it is not there yet
+    /// * `timeout` - the wall-clock time the program may run, `None` for no limit
+    /// # Return
+    /// how main finished, or `RuntimeError::Timeout` if the deadline passed
+    pub fn run_with_timeout(
+        &mut self,
+        class: Arc<ClassFile>,
+        timeout: Option<Duration>,
+    ) -> Result<ExecutionResult, RuntimeError> {
+        let name = class.name().to_string();
+        self.classes.entry(name.clone()).or_insert(class);
+
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let result = self
+            .initialize_class(&name)
+            .and_then(|class| self.invoke_main(&class));
+        self.deadline = None;
+        ExecutionResult::from_execution(result.map(|()| None))
+    }
+}
+
+/// Get the length in bytes of the instruction at `pc`, including its operands.
+///
+/// # Parameters
+/// * `code` - the bytecode of a method
+/// * `pc` - the position of the instruction's opcode
+/// # Return
+/// the length of the instruction, `None` if its operands run past the end of the code
+pub fn get_instruction_length(code: &[u8], pc: usize) -> Option<usize> {
+    let read_i32 = |at: usize| -> Option<i32> {
+        let bytes = code.get(at..at + 4)?;
+        Some(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+
+    let length = match *code.get(pc)? {
+        i_bipush | i_ldc | i_iload..=i_aload | i_istore..=i_astore | i_newarray => 2,
+        // ret
+        169 => 2,
+        i_sipush | i_ldc_w | i_ldc2_w | i_iinc | i_ifeq..=i_jsr => 3,
+        // getstatic, putstatic, getfield, putfield, invokevirtual, invokespecial, invokestatic
+        i_getstatic..=i_invokestatic => 3,
+        // new, anewarray, checkcast, instanceof, ifnull, ifnonnull
+        i_new | i_anewarray | i_checkcast | 193 | i_ifnull | i_ifnonnull => 3,
+        // multianewarray
+        197 => 4,
+        // invokeinterface, invokedynamic, goto_w, jsr_w
+        i_invokeinterface | i_invokedynamic | i_goto_w | 201 => 5,
+        // wide, which is longer when it widens iinc
+        196 => {
+            if *code.get(pc + 1)? == i_iinc {
+                6
+            } else {
+                4
+            }
+        }
+        // tableswitch and lookupswitch, whose operands are 4-byte aligned
+        i_tableswitch | i_lookupswitch => {
+            let start = pc + 1 + (4 - (pc + 1) % 4) % 4;
+            let entries = if code[pc] == i_tableswitch {
+                let low = read_i32(start + 4)? as i64;
+                let high = read_i32(start + 8)? as i64;
+                3 + (high - low + 1) as usize
+            } else {
+                2 + 2 * read_i32(start + 4)? as usize
+            };
+            start - pc + 4 * entries
+        }
+        _ => 1,
+    };
+
+    if pc + length > code.len() {
+        return None;
+    }
+    Some(length)
+}
+
+/// The mnemonics of the opcodes, indexed by opcode, up to `jsr_w`.
+const OPCODE_NAMES: [&str; 202] = [
+    "nop",
+    "aconst_null",
+    "iconst_m1",
+    "iconst_0",
+    "iconst_1",
+    "iconst_2",
+    "iconst_3",
+    "iconst_4",
+    "iconst_5",
+    "lconst_0",
+    "lconst_1",
+    "fconst_0",
+    "fconst_1",
+    "fconst_2",
+    "dconst_0",
+    "dconst_1",
+    "bipush",
+    "sipush",
+    "ldc",
+    "ldc_w",
+    "ldc2_w",
+    "iload",
+    "lload",
+    "fload",
+    "dload",
+    "aload",
+    "iload_0",
+    "iload_1",
+    "iload_2",
+    "iload_3",
+    "lload_0",
+    "lload_1",
+    "lload_2",
+    "lload_3",
+    "fload_0",
+    "fload_1",
+    "fload_2",
+    "fload_3",
+    "dload_0",
+    "dload_1",
+    "dload_2",
+    "dload_3",
+    "aload_0",
+    "aload_1",
+    "aload_2",
+    "aload_3",
+    "iaload",
+    "laload",
+    "faload",
+    "daload",
+    "aaload",
+    "baload",
+    "caload",
+    "saload",
+    "istore",
+    "lstore",
+    "fstore",
+    "dstore",
+    "astore",
+    "istore_0",
+    "istore_1",
+    "istore_2",
+    "istore_3",
+    "lstore_0",
+    "lstore_1",
+    "lstore_2",
+    "lstore_3",
+    "fstore_0",
+    "fstore_1",
+    "fstore_2",
+    "fstore_3",
+    "dstore_0",
+    "dstore_1",
+    "dstore_2",
+    "dstore_3",
+    "astore_0",
+    "astore_1",
+    "astore_2",
+    "astore_3",
+    "iastore",
+    "lastore",
+    "fastore",
+    "dastore",
+    "aastore",
+    "bastore",
+    "castore",
+    "sastore",
+    "pop",
+    "pop2",
+    "dup",
+    "dup_x1",
+    "dup_x2",
+    "dup2",
+    "dup2_x1",
+    "dup2_x2",
+    "swap",
+    "iadd",
+    "ladd",
+    "fadd",
+    "dadd",
+    "isub",
+    "lsub",
+    "fsub",
+    "dsub",
+    "imul",
+    "lmul",
+    "fmul",
+    "dmul",
+    "idiv",
+    "ldiv",
+    "fdiv",
+    "ddiv",
+    "irem",
+    "lrem",
+    "frem",
+    "drem",
+    "ineg",
+    "lneg",
+    "fneg",
+    "dneg",
+    "ishl",
+    "lshl",
+    "ishr",
+    "lshr",
+    "iushr",
+    "lushr",
+    "iand",
+    "land",
+    "ior",
+    "lor",
+    "ixor",
+    "lxor",
+    "iinc",
+    "i2l",
+    "i2f",
+    "i2d",
+    "l2i",
+    "l2f",
+    "l2d",
+    "f2i",
+    "f2l",
+    "f2d",
+    "d2i",
+    "d2l",
+    "d2f",
+    "i2b",
+    "i2c",
+    "i2s",
+    "lcmp",
+    "fcmpl",
+    "fcmpg",
+    "dcmpl",
+    "dcmpg",
+    "ifeq",
+    "ifne",
+    "iflt",
+    "ifge",
+    "ifgt",
+    "ifle",
+    "if_icmpeq",
+    "if_icmpne",
+    "if_icmplt",
+    "if_icmpge",
+    "if_icmpgt",
+    "if_icmple",
+    "if_acmpeq",
+    "if_acmpne",
+    "goto",
+    "jsr",
+    "ret",
+    "tableswitch",
+    "lookupswitch",
+    "ireturn",
+    "lreturn",
+    "freturn",
+    "dreturn",
+    "areturn",
+    "return",
+    "getstatic",
+    "putstatic",
+    "getfield",
+    "putfield",
+    "invokevirtual",
+    "invokespecial",
+    "invokestatic",
+    "invokeinterface",
+    "invokedynamic",
+    "new",
+    "newarray",
+    "anewarray",
+    "arraylength",
+    "athrow",
+    "checkcast",
+    "instanceof",
+    "monitorenter",
+    "monitorexit",
+    "wide",
+    "multianewarray",
+    "ifnull",
+    "ifnonnull",
+    "goto_w",
+    "jsr_w",
+];
+
+/// Get the mnemonic of an opcode, e.g. "iload_0" for 26.
+fn get_opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        i_breakpoint => "breakpoint",
+        i_impdep1 => "impdep1",
+        i_impdep2 => "impdep2",
+        _ => OPCODE_NAMES
+            .get(opcode as usize)
+            .copied()
+            .unwrap_or("unknown"),
+    }
+}
+
+/// Decode the operands of an instruction for a disassembly.
+///
+/// # Parameters
+/// * `code` - the bytecode of a method, whose instruction at `pc` is complete
+/// * `pc` - the position of the instruction's opcode
+/// # Return
+/// the operands, with branch offsets turned into the positions they jump to:
+/// a switch lists its default target, then `low` and `high` and the targets for
+/// `tableswitch`, or the number of pairs and every match and its target for `lookupswitch`;
+/// `wide` lists the widened opcode and its operands.
+/// The constant pool index the instruction refers to, if any, is returned too.
+fn get_operands(code: &[u8], pc: usize) -> (Vec<i64>, Option<u16>) {
+    let u8_at = |at: usize| code[at] as i64;
+    let u16_at = |at: usize| u16::from_be_bytes([code[at], code[at + 1]]);
+    let i16_at = |at: usize| i16::from_be_bytes([code[at], code[at + 1]]) as i64;
+    let i32_at =
+        |at: usize| i32::from_be_bytes([code[at], code[at + 1], code[at + 2], code[at + 3]]) as i64;
+    let pc_offset = pc as i64;
+    match code[pc] {
+        i_bipush => (vec![code[pc + 1] as i8 as i64], None),
+        i_sipush => (vec![i16_at(pc + 1)], None),
+        i_ldc => (vec![u8_at(pc + 1)], Some(code[pc + 1] as u16)),
+        i_iload..=i_aload | i_istore..=i_astore | i_newarray | 169 => (vec![u8_at(pc + 1)], None),
+        i_iinc => (vec![u8_at(pc + 1), code[pc + 2] as i8 as i64], None),
+        i_ifeq..=i_jsr | i_ifnull | i_ifnonnull => (vec![pc_offset + i16_at(pc + 1)], None),
+        i_goto_w | 201 => (vec![pc_offset + i32_at(pc + 1)], None),
+        i_ldc_w
+        | i_ldc2_w
+        | i_getstatic..=i_invokestatic
+        | i_invokedynamic
+        | i_new
+        | i_anewarray
+        | i_checkcast
+        | 193 => {
+            let index = u16_at(pc + 1);
+            (vec![index as i64], Some(index))
+        }
+        // invokeinterface and multianewarray also take the number of argument slots
+        // or of dimensions
+        i_invokeinterface | 197 => {
+            let index = u16_at(pc + 1);
+            (vec![index as i64, u8_at(pc + 3)], Some(index))
+        }
+        196 if code[pc + 1] == i_iinc => (
+            vec![u8_at(pc + 1), u16_at(pc + 2) as i64, i16_at(pc + 4)],
+            None,
+        ),
+        196 => (vec![u8_at(pc + 1), u16_at(pc + 2) as i64], None),
+        i_tableswitch | i_lookupswitch => {
+            let start = pc + 1 + (4 - (pc + 1) % 4) % 4;
+            let mut operands = vec![pc_offset + i32_at(start)];
+            if code[pc] == i_tableswitch {
+                let (low, high) = (i32_at(start + 4), i32_at(start + 8));
+                operands.extend([low, high]);
+                for entry in 0..(high - low + 1).max(0) as usize {
+                    operands.push(pc_offset + i32_at(start + 12 + 4 * entry));
+                }
+            } else {
+                let pairs = i32_at(start + 4);
+                operands.push(pairs);
+                for pair in 0..pairs.max(0) as usize {
+                    let at = start + 8 + 8 * pair;
+                    operands.extend([i32_at(at), pc_offset + i32_at(at + 4)]);
+                }
+            }
+            (operands, None)
+        }
+        _ => (vec![], None),
+    }
+}
+
+/// Describe a constant pool entry for a listing of the constant pool.
+///
+/// # Parameters
+/// * `class` - the parsed class file
+/// * `entry` - the constant pool entry
+/// # Return
+/// the kind of the entry, e.g. "Methodref", and its value with the entries it refers to
+/// resolved, e.g. "java/lang/Object.<init>:()V"
+fn describe_constant(class: &ClassFile, entry: &ConstantPool) -> (&'static str, String) {
+    let constant = |index: u16| class.constant_pool.get((index as usize).checked_sub(1)?);
+    let utf8 = |index: u16| match constant(index) {
+        Some(ConstantPool::Utf8(s)) => s.clone(),
+        _ => format!("<invalid #{}>", index),
+    };
+    let name_and_type = |index: u16| match constant(index) {
+        Some(&ConstantPool::NameAndType {
+            name_index,
+            descriptor_index,
+        }) => (utf8(name_index), utf8(descriptor_index)),
+        _ => (format!("<invalid #{}>", index), String::new()),
+    };
+    let class_name = |index: u16| {
+        class
+            .class_name(index)
+            .map_or_else(|| format!("<invalid #{}>", index), str::to_string)
+    };
+    match *entry {
+        ConstantPool::Utf8(ref s) => ("Utf8", s.clone()),
+        ConstantPool::Integer { bytes } => ("Integer", bytes.to_string()),
+        ConstantPool::Float { bytes } => ("Float", format_float(f32::from_bits(bytes))),
+        ConstantPool::Long {
+            high_bytes,
+            low_bytes,
+        } => (
+            "Long",
+            slots_to_long(high_bytes as i32, low_bytes as i32).to_string(),
+        ),
+        ConstantPool::Double {
+            high_bytes,
+            low_bytes,
+        } => (
+            "Double",
+            format_double(slots_to_double(high_bytes as i32, low_bytes as i32)),
+        ),
+        ConstantPool::Unusable => ("Unusable", String::new()),
+        ConstantPool::Class { starting_index } => ("Class", utf8(starting_index)),
+        ConstantPool::String { string_index } => ("String", utf8(string_index)),
+        ConstantPool::MethodOrFieldRef {
+            tag,
+            class_index,
+            name_and_type_index,
+        } => {
+            let (name, descriptor) = name_and_type(name_and_type_index);
+            let kind = match tag {
+                9 => "Fieldref",
+                10 => "Methodref",
+                _ => "InterfaceMethodref",
+            };
+            let value = format!("{}.{}:{}", class_name(class_index), name, descriptor);
+            (kind, value)
+        }
+        ConstantPool::NameAndType {
+            name_index,
+            descriptor_index,
+        } => (
+            "NameAndType",
+            format!("{}:{}", utf8(name_index), utf8(descriptor_index)),
+        ),
+        ConstantPool::MethodHandle {
+            reference_kind,
+            reference_index,
+        } => {
+            let kind = [
+                "getField",
+                "getStatic",
+                "putField",
+                "putStatic",
+                "invokeVirtual",
+                "invokeStatic",
+                "invokeSpecial",
+                "newInvokeSpecial",
+                "invokeInterface",
+            ]
+            .get((reference_kind as usize).wrapping_sub(1))
+            .copied()
+            .unwrap_or("invalid");
+            let reference = match constant(reference_index) {
+                Some(reference) => describe_constant(class, reference).1,
+                None => format!("<invalid #{}>", reference_index),
+            };
+            ("MethodHandle", format!("REF_{} {}", kind, reference))
+        }
+        ConstantPool::MethodType { descriptor_index } => ("MethodType", utf8(descriptor_index)),
+        ConstantPool::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } => {
+            let (name, descriptor) = name_and_type(name_and_type_index);
+            let value = format!("#{}:{}:{}", bootstrap_method_attr_index, name, descriptor);
+            ("InvokeDynamic", value)
+        }
+    }
+}
+
+/// Quote a string for a JSON document, escaping quotes, backslashes and control characters.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Print the constant pool and the instructions of the methods of a class, for `--print-cp`
+/// and `--disassemble`, either for reading or as a JSON document for other tools:
+/// `{"constant_pool": [{"index", "tag", "value"}], "methods": [{"name", "descriptor",
+/// "instructions": [{"pc", "opcode", "operands"}]}]}`, with only the requested parts.
+///
+/// # Parameters
+/// * `class` - the parsed class file
+/// * `print_cp` - whether to print the constant pool
+/// * `disassemble` - whether to print the instructions of the methods
+/// * `json` - whether to print a JSON document
+fn print_class(class: &ClassFile, print_cp: bool, disassemble: bool, json: bool) {
+    let mut parts = vec![];
+    if print_cp {
+        let entries: Vec<_> = class
+            .constant_pool_iter()
+            .map(|(index, entry)| (index, describe_constant(class, entry)))
+            .collect();
+        if json {
+            let entries: Vec<_> = entries
+                .into_iter()
+                .map(|(index, (tag, value))| {
+                    format!(
+                        "    {{\"index\": {}, \"tag\": {}, \"value\": {}}}",
+                        index,
+                        json_string(tag),
+                        json_string(&value)
+                    )
+                })
+                .collect();
+            parts.push(format!(
+                "  \"constant_pool\": [\n{}\n  ]",
+                entries.join(",\n")
+            ));
+        } else {
+            println!("Constant pool:");
+            for (index, (tag, value)) in entries {
+                println!("{:>6} = {:<18} {}", format!("#{}", index), tag, value);
+            }
+        }
+    }
+    if disassemble {
+        let mut methods = vec![];
+        for method in &class.method {
+            let code = method.code.as_ref().map_or(&[][..], |code| &code.code[..]);
+            let mut instructions = vec![];
+            let mut pc = 0;
+            // a truncated instruction ends the listing
+            while let Some(length) = get_instruction_length(code, pc) {
+                let (operands, index) = get_operands(code, pc);
+                instructions.push((pc, get_opcode_name(code[pc]), operands, index));
+                pc += length;
+            }
+            if json {
+                let instructions: Vec<_> = instructions
+                    .into_iter()
+                    .map(|(pc, name, operands, _)| {
+                        let operands: Vec<_> = operands.iter().map(i64::to_string).collect();
+                        format!(
+                            "        {{\"pc\": {}, \"opcode\": {}, \"operands\": [{}]}}",
+                            pc,
+                            json_string(name),
+                            operands.join(", ")
+                        )
+                    })
+                    .collect();
+                methods.push(format!(
+                    "    {{\"name\": {}, \"descriptor\": {}, \"instructions\": [\n{}\n    ]}}",
+                    json_string(&method.name),
+                    json_string(&method.descriptor),
+                    instructions.join(",\n")
+                ));
+            } else {
+                println!("{}{}", method.name, method.descriptor);
+                for (pc, name, operands, index) in instructions {
+                    let mut line = format!("{:>6}: {}", pc, name);
+                    // the referenced constant comes first, shown by index and resolved
+                    for (position, operand) in operands.iter().enumerate() {
+                        let separator = if position == 0 { " " } else { ", " };
+                        let hash = if position == 0 && index.is_some() {
+                            "#"
+                        } else {
+                            ""
+                        };
+                        line.push_str(&format!("{}{}{}", separator, hash, operand));
+                    }
+                    let entry = index.and_then(|index| {
+                        class.constant_pool.get((index as usize).checked_sub(1)?)
+                    });
+                    if let Some(entry) = entry {
+                        line.push_str(&format!(" // {}", describe_constant(class, entry).1));
+                    }
+                    println!("{}", line);
+                }
+            }
+        }
+        if json {
+            parts.push(format!("  \"methods\": [\n{}\n  ]", methods.join(",\n")));
+        }
+    }
+    if json {
+        println!("{{\n{}\n}}", parts.join(",\n"));
+    }
+}
+
+/// Get the targets an instruction may branch to, besides the next instruction.
+///
+/// # Parameters
+/// * `code` - the bytecode of a method, whose instruction at `pc` is complete
+/// * `pc` - the position of the instruction's opcode
+/// # Return
+/// the targets, which may lie outside the code; empty if the instruction does not branch
+fn get_branch_targets(code: &[u8], pc: usize) -> Vec<isize> {
+    let read_i32 =
+        |at: usize| i32::from_be_bytes([code[at], code[at + 1], code[at + 2], code[at + 3]]);
+    let mut offsets = Vec::new();
+    match code[pc] {
+        // if<cond>, if_icmp<cond>, if_acmp<cond>, goto, jsr, ifnull, ifnonnull
+        i_ifeq..=i_jsr | i_ifnull | i_ifnonnull => {
+            offsets.push(i16::from_be_bytes([code[pc + 1], code[pc + 2]]) as i32);
+        }
+        // goto_w, jsr_w
+        i_goto_w | 201 => offsets.push(read_i32(pc + 1)),
+        i_tableswitch | i_lookupswitch => {
+            let start = pc + 1 + (4 - (pc + 1) % 4) % 4;
+            offsets.push(read_i32(start));
+            if code[pc] == i_tableswitch {
+                let count = (read_i32(start + 8) as i64 - read_i32(start + 4) as i64 + 1) as usize;
+                for i in 0..count {
+                    offsets.push(read_i32(start + 12 + 4 * i));
+                }
+            } else {
+                for i in 0..read_i32(start + 4) as usize {
+                    offsets.push(read_i32(start + 12 + 8 * i));
+                }
+            }
+        }
+        _ => {}
+    }
+    offsets
+        .into_iter()
+        .map(|offset| pc as isize + offset as isize)
+        .collect()
+}
+
+/// Whether an instruction reads or writes a local variable, see `get_local_access`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LocalAccess {
+    Load,
+    Store,
+}
+
+/// Decode the local variable accessed by a load or store instruction.
+///
+/// # Parameters
+/// * `code` - the bytecode of a method, whose instruction at `pc` is complete
+/// * `pc` - the position of the instruction's opcode
+/// # Return
+/// whether it loads or stores, the index of the local and the number of slots it takes,
+/// 2 for long and double; `None` if the instruction is not a load or store
+fn get_local_access(code: &[u8], pc: usize) -> Option<(LocalAccess, usize, usize)> {
+    // the instructions of each kind come in the order int, long, float, double, reference
+    let slots = |kind: u8| if kind == 1 || kind == 3 { 2 } else { 1 };
+    Some(match code[pc] {
+        opcode @ i_iload..=i_aload => (
+            LocalAccess::Load,
+            code[pc + 1] as usize,
+            slots(opcode - i_iload),
+        ),
+        opcode @ i_iload_0..=i_aload_3 => (
+            LocalAccess::Load,
+            ((opcode - i_iload_0) % 4) as usize,
+            slots((opcode - i_iload_0) / 4),
+        ),
+        opcode @ i_istore..=i_astore => (
+            LocalAccess::Store,
+            code[pc + 1] as usize,
+            slots(opcode - i_istore),
+        ),
+        opcode @ i_istore_0..=i_astore_3 => (
+            LocalAccess::Store,
+            ((opcode - i_istore_0) % 4) as usize,
+            slots((opcode - i_istore_0) / 4),
+        ),
+        _ => return None,
+    })
+}
+
+/// What a local variable holds at an instruction, as tracked by `verify_local_variables`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LocalType {
+    /// Nothing was stored yet
+    Unset,
+    /// An int, float or reference
+    Single,
+    /// The first slot of a long or double
+    Wide,
+    /// The second slot of a long or double
+    WideHalf,
+    /// A slot which cannot be read, e.g. what is left of a long once half of it was
+    /// overwritten, or a local holding different types on different paths
+    Unusable,
+}
+
+/// Store a value of the given number of slots into the tracked types of the locals.
+/// A long or double whose other half is overwritten becomes unusable.
+fn store_local_type(types: &mut [LocalType], index: usize, slots: usize) {
+    for slot in index..index + slots {
+        match types.get(slot) {
+            Some(LocalType::WideHalf) if slot > 0 => types[slot - 1] = LocalType::Unusable,
+            Some(LocalType::Wide) if slot + 1 < types.len() => {
+                types[slot + 1] = LocalType::Unusable
+            }
+            _ => {}
+        }
+    }
+    let stored: &[LocalType] = if slots == 2 {
+        &[LocalType::Wide, LocalType::WideHalf]
+    } else {
+        &[LocalType::Single]
+    };
+    for (slot, &stored) in (index..).zip(stored) {
+        if let Some(local) = types.get_mut(slot) {
+            *local = stored;
+        }
+    }
+}
+
+/// Check that no instruction reads half of a long or double, in particular one whose other
+/// half was overwritten. The types of the locals are tracked along every path through the
+/// method; a local holding different types where paths join cannot be read.
+/// Ints, floats and references are not told apart, nor longs from doubles.
+///
+/// # Parameters
+/// * `method` - the method to verify, whose branches `verify_branch_targets` accepted
+/// # Return
+/// the first error found in the method
+pub fn verify_local_variables(method: &Method) -> Result<(), VerifyError> {
+    let code = match &method.code {
+        Some(code) => code,
+        None => return Ok(()),
+    };
+    let bytes = &code.code;
+
+    // the parameters fill the first locals, after the receiver of an instance method
+    let mut initial = vec![LocalType::Unset; code.max_locals as usize];
+    let mut index = 0;
+    if method.access_flags & ACC_STATIC == 0 {
+        store_local_type(&mut initial, index, 1);
+        index += 1;
+    }
+    for param in get_param_types(&method.descriptor) {
+        store_local_type(&mut initial, index, get_type_slots(param));
+        index += get_type_slots(param);
+    }
+
+    let mut states: Vec<Option<Vec<LocalType>>> = vec![None; bytes.len()];
+    let mut pending = vec![0];
+    states[0] = Some(initial);
+    // merge the types at an instruction into those at a target, queueing it if they changed
+    let merge = |states: &mut Vec<Option<Vec<LocalType>>>,
+                 pending: &mut Vec<usize>,
+                 target: usize,
+                 types: &[LocalType]| {
+        let merged = match &states[target] {
+            None => types.to_vec(),
+            Some(old) => old
+                .iter()
+                .zip(types)
+                .map(|(&a, &b)| if a == b { a } else { LocalType::Unusable })
+                .collect(),
+        };
+        if states[target].as_ref() != Some(&merged) {
+            states[target] = Some(merged);
+            pending.push(target);
+        }
+    };
+
+    while let Some(pc) = pending.pop() {
+        let mut types = states[pc].clone().unwrap();
+        // an exception thrown here reaches the handler with the locals as they are
+        for handler in &code.exception_table {
+            let range = handler.start_pc as usize..handler.end_pc as usize;
+            if range.contains(&pc) && (handler.handler_pc as usize) < bytes.len() {
+                merge(
+                    &mut states,
+                    &mut pending,
+                    handler.handler_pc as usize,
+                    &types,
+                );
+            }
+        }
+
+        let access = get_local_access(bytes, pc).or(if bytes[pc] == i_iinc {
+            Some((LocalAccess::Load, bytes[pc + 1] as usize, 1))
+        } else {
+            None
+        });
+        if let Some((access, index, slots)) = access {
+            let readable = match slots {
+                1 => matches!(types.get(index), Some(LocalType::Single | LocalType::Unset)),
+                _ => {
+                    types.get(index) == Some(&LocalType::Wide)
+                        && types.get(index + 1) == Some(&LocalType::WideHalf)
+                }
+            };
+            if access == LocalAccess::Load && !readable {
+                return Err(VerifyError::InvalidLocalRead {
+                    method: method.name.clone(),
+                    pc,
+                    index,
+                });
+            }
+            if access == LocalAccess::Store || bytes[pc] == i_iinc {
+                store_local_type(&mut types, index, slots);
+            }
+        }
+
+        let length =
+            get_instruction_length(bytes, pc).ok_or_else(|| VerifyError::TruncatedInstruction {
+                method: method.name.clone(),
+                pc,
+            })?;
+        // returns, athrow, ret, goto and the switches do not continue with the next instruction
+        let falls_through = !matches!(
+            bytes[pc],
+            i_ireturn
+                ..=i_return | i_athrow | 169 | i_goto | i_goto_w | i_tableswitch | i_lookupswitch
+        );
+        let mut successors: Vec<isize> = get_branch_targets(bytes, pc);
+        if falls_through {
+            successors.push((pc + length) as isize);
+        }
+        for target in successors {
+            if target >= 0 && (target as usize) < bytes.len() {
+                merge(&mut states, &mut pending, target as usize, &types);
             }
-            found_code = true;
+        }
+    }
+    Ok(())
+}
+
+/// Check that every branch of a method jumps to the start of an instruction,
+/// and likewise every exception handler. Bytecode which jumps into the operands of
+/// an instruction would otherwise run those operands as opcodes.
+///
+/// # Parameters
+/// * `method` - the method to verify
+/// # Return
+/// the first error found in the method
+pub fn verify_branch_targets(method: &Method) -> Result<(), VerifyError> {
+    let (code, exception_table) = match &method.code {
+        Some(code) => (&code.code, &code.exception_table),
+        None => return Ok(()),
+    };
+    let mut starts = vec![false; code.len()];
+    let mut branches = Vec::new();
 
-            let max_stack = read_2_bytes(&mut attributes);
-            let max_locals = read_2_bytes(&mut attributes);
-            let code_length = read_4_bytes(&mut attributes);
-            let mut code = vec![0u8; code_length as usize];
-            attributes.read_exact(&mut code).unwrap();
+    let mut pc = 0;
+    while pc < code.len() {
+        let length =
+            get_instruction_length(code, pc).ok_or_else(|| VerifyError::TruncatedInstruction {
+                method: method.name.clone(),
+                pc,
+            })?;
+        starts[pc] = true;
 
-            codes = Some(Code {
-                max_stack,
-                max_locals,
-                code_length,
-                code,
+        branches.extend(
+            get_branch_targets(code, pc)
+                .into_iter()
+                .map(|target| (pc, target)),
+        );
+
+        pc += length;
+    }
+
+    for (pc, target) in branches {
+        if target < 0 || target as usize >= code.len() || !starts[target as usize] {
+            return Err(VerifyError::InvalidBranchTarget {
+                method: method.name.clone(),
+                pc,
+                target,
             });
         }
     }
 
-    if !found_code {
-        panic!("Missing method code")
+    // the covered range ends at the start of an instruction or at the end of the code
+    let is_start = |pc: u16| starts.get(pc as usize) == Some(&true);
+    for (index, handler) in exception_table.iter().enumerate() {
+        let ends_instruction = is_start(handler.end_pc) || handler.end_pc as usize == code.len();
+        if !is_start(handler.start_pc)
+            || handler.start_pc >= handler.end_pc
+            || !ends_instruction
+            || !is_start(handler.handler_pc)
+        {
+            return Err(VerifyError::InvalidExceptionHandler {
+                method: method.name.clone(),
+                index,
+                start_pc: handler.start_pc,
+                end_pc: handler.end_pc,
+                handler_pc: handler.handler_pc,
+            });
+        }
     }
-    codes.unwrap()
+
+    Ok(())
 }
 
-fn get_methods(class_file: &mut BufReader<File>, cp: &Vec<ConstantPool>) -> Vec<Method> {
-    let method_count = read_2_bytes(class_file) as usize;
-    let mut methods: Vec<Method> = Vec::with_capacity(method_count);
+/// The first class file version whose methods must carry a StackMapTable to be verified.
+pub const STACK_MAP_TABLE_VERSION: u16 = 51;
 
-    for _ in 0..method_count {
-        let info = MethodInfo {
-            access_flags: read_2_bytes(class_file),
-            name_index: read_2_bytes(class_file),
-            descriptor_index: read_2_bytes(class_file),
-            attributes_count: read_2_bytes(class_file),
-        };
+/// Check that a method carries a StackMapTable if its class file version requires one,
+/// and that its frames are at the start of instructions.
+/// Only code which branches or handles exceptions needs frames.
+/// FIXME: the types of the frames are not checked against the code
+///
+/// # Parameters
+/// * `class` - the class declaring the method
+/// * `method` - the method to verify
+/// # Return
+/// the first error found in the method
+pub fn verify_stack_map_table(class: &ClassFile, method: &Method) -> Result<(), VerifyError> {
+    let code = match &method.code {
+        Some(code) => code,
+        None => return Ok(()),
+    };
+    let mut starts = vec![false; code.code.len()];
+    let mut branches = !code.exception_table.is_empty();
+    let mut pc = 0;
+    while pc < code.code.len() {
+        starts[pc] = true;
+        // if<cond>, if_icmp<cond>, if_acmp<cond>, goto, jsr, switches, ifnull, ifnonnull, goto_w, jsr_w
+        branches |= matches!(code.code[pc], i_ifeq..=i_lookupswitch | 198..=201);
+        pc += get_instruction_length(&code.code, pc).ok_or_else(|| {
+            VerifyError::TruncatedInstruction {
+                method: method.name.clone(),
+                pc,
+            }
+        })?;
+    }
 
-        let name = get_constant(cp, info.name_index);
-        let name = match name {
-            ConstantPool::Utf8(s) => s.clone(),
-            _ => panic!("Expected a UTF8"),
-        };
-        let descriptor = get_constant(cp, info.descriptor_index);
-        let descriptor = match descriptor {
-            ConstantPool::Utf8(s) => s.clone(),
-            _ => panic!("Expected a UTF8"),
-        };
+    match &code.stack_map_table {
+        None if branches && class.major_version >= STACK_MAP_TABLE_VERSION => {
+            Err(VerifyError::MissingStackMapTable {
+                method: method.name.clone(),
+            })
+        }
+        None => Ok(()),
+        Some(offsets) => match offsets
+            .iter()
+            .find(|&&offset| !starts.get(offset).copied().unwrap_or(false))
+        {
+            Some(&offset) => Err(VerifyError::InvalidStackMapOffset {
+                method: method.name.clone(),
+                offset,
+            }),
+            None => Ok(()),
+        },
+    }
+}
 
-        // FIXME: this VM can only execute static methods, while every class has a constructor method <init>
-        if name == "<init>" && (info.access_flags & 0x0008) > 0 {
-            panic!("Only static methods are supported by this VM.")
+/// Execute the opcode instructions of a method until it returns.
+/// Exceptions thrown while it runs are passed to its exception handlers.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `method` - the method to run
+/// * `locals` - the array of local variables, including the method parameters.
+///   Except for parameters, the locals are uninitialized.
+/// * `class` - the class file the method belongs to
+/// # Return
+/// If the method returns an int, pass it as `Some()`;
+/// `None` if the method returns void;
+/// `RuntimeError::Timeout` if the deadline of `vm` passed;
+/// `RuntimeError::ReservedOpcode` if the method ran a reserved opcode;
+/// `RuntimeError::FellOffMethodEnd` if the method ran past the end of its code;
+/// `RuntimeError::Exception` if the method threw an exception it did not catch.
+fn execute(
+    vm: &mut Vm,
+    method: &Method,
+    mut locals: Vec<i32>,
+    class: &ClassFile,
+) -> Result<Option<i32>, RuntimeError> {
+    if vm.call_depth >= vm.max_call_depth {
+        return Err(RuntimeError::Exception {
+            class: "java/lang/StackOverflowError".to_string(),
+            message: None,
+            cause: None,
+        });
+    }
+    let code = method
+        .code
+        .as_ref()
+        .ok_or_else(|| RuntimeError::exception("java/lang/AbstractMethodError", &method.name))?;
+    if vm.trace_calls {
+        trace_call_entry(vm, method, &locals);
+    }
+    let start = if vm.profiling {
+        Some(Instant::now())
+    } else {
+        None
+    };
+    let monitor = enter_method_monitor(vm, method, class, &locals)?;
+    vm.call_depth += 1;
+
+    let mut pc = 0;
+    let mut exception = None;
+    let result = loop {
+        match run_frame(vm, code, &mut locals, class, &mut pc, exception) {
+            Err(RuntimeError::Exception {
+                class: class_name,
+                message,
+                cause,
+            }) => match find_exception_handler(code, class, pc, &class_name) {
+                // the handler starts with only the thrown object on the stack,
+                // allocated even over the heap limit so an OutOfMemoryError can be caught
+                Some(handler_pc) => {
+                    pc = handler_pc;
+                    exception = Some(vm.alloc(HeapObject::Throwable {
+                        class_name,
+                        message,
+                        cause,
+                    }));
+                }
+                None => {
+                    break Err(RuntimeError::Exception {
+                        class: class_name,
+                        message,
+                        cause,
+                    })
+                }
+            },
+            result => break result,
         }
+    };
 
-        // Read the list of static methods
-        let code = read_method_attributes(class_file, info.attributes_count, cp);
+    vm.call_depth -= 1;
+    // the monitor is released however the method ends
+    if let Some(monitor) = monitor {
+        vm.exit_monitor(monitor)?;
+    }
+    // ireturn also returns booleans, bytes, chars and shorts, which only keep their low bits
+    let result =
+        result.map(|value| value.map(|value| narrow_return_value(&method.descriptor, value)));
+    if let Ok(value) = &result {
+        debug_assert_return_type(method, *value);
+    }
+    if vm.trace_calls {
+        trace_call_exit(vm, method, &result);
+    }
+    if let Some(start) = start {
+        vm.profile.record(class.name(), method, start.elapsed());
+    }
+    result
+}
 
-        methods.push(Method {
-            name,
-            descriptor,
-            code,
-        });
+/// Enter the monitor a synchronized method holds while it runs, that of its receiver,
+/// or of its class if static. This is kept out of `execute` to keep its frame small.
+///
+/// # Return
+/// the object whose monitor was entered, `None` if the method is not synchronized
+fn enter_method_monitor(
+    vm: &mut Vm,
+    method: &Method,
+    class: &ClassFile,
+    locals: &[i32],
+) -> Result<Option<i32>, RuntimeError> {
+    let monitor = if method.access_flags & ACC_SYNCHRONIZED == 0 {
+        return Ok(None);
+    } else if method.access_flags & ACC_STATIC == 0 {
+        locals[0]
+    } else {
+        vm.class_object(class.name())?
+    };
+    vm.enter_monitor(monitor)?;
+    Ok(Some(monitor))
+}
+
+/// Execute a method like `execute`, but turn a panic of the interpreter into an error,
+/// e.g. on an opcode or a native method it does not implement yet, so that a program
+/// embedding the VM keeps running.
+/// FIXME: this is a stopgap until every panic of the interpreter is a `RuntimeError`
+///
+/// # Parameters
+/// the same as `execute`
+/// # Return
+/// the value returned by the method like `execute`;
+/// the message of the panic, or the `RuntimeError` formatted by `Display`, as an error.
+/// The panic is still reported by the panic hook, to stderr by default.
+/// The call depth is restored after a panic, but the monitors entered by the
+/// abandoned frames stay held.
+pub fn try_execute(
+    vm: &mut Vm,
+    method: &Method,
+    locals: Vec<i32>,
+    class: &ClassFile,
+) -> Result<Option<i32>, String> {
+    let call_depth = vm.call_depth;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        execute(vm, method, locals, class)
+    }));
+    match result {
+        Ok(result) => result.map_err(|error| error.to_string()),
+        Err(payload) => {
+            vm.call_depth = call_depth;
+            Err(match payload.downcast::<String>() {
+                Ok(message) => *message,
+                Err(payload) => payload
+                    .downcast_ref::<&str>()
+                    .map_or("The interpreter panicked", |message| message)
+                    .to_string(),
+            })
+        }
     }
+}
 
-    methods
+/// Narrow an int returned by `ireturn` to the return type of the method.
+///
+/// # Parameters
+/// * `descriptor` - the descriptor of the method, e.g. "(I)B"
+/// * `value` - the returned int
+/// # Return
+/// the value as the return type would hold it, e.g. 44 for 300 returned as a byte
+fn narrow_return_value(descriptor: &str, value: i32) -> i32 {
+    match descriptor.as_bytes().last() {
+        Some(b'Z') => value & 1,
+        Some(b'B') => value as i8 as i32,
+        Some(b'C') => value as u16 as i32,
+        Some(b'S') => value as i16 as i32,
+        _ => value,
+    }
 }
 
-/// Read an entire class file.
-/// The end of the parsed methods array is marked by a method with a NULL name.
+/// Check in debug builds that a frame returned a value exactly if its method is not void,
+/// so that no stale value is left for the caller to push.
+fn debug_assert_return_type(method: &Method, value: Option<i32>) {
+    debug_assert_eq!(
+        method.descriptor.ends_with(")V"),
+        value.is_none(),
+        "{}{} returned {:?}",
+        method.name,
+        method.descriptor,
+        value
+    );
+}
+
+/// Find the static method to run for `--method`, by its name alone if no other
+/// static method shares it, e.g. "factorial", or by its name and descriptor, e.g. "factorial(I)I".
 ///
 /// # Parameters
-/// * `class_file` - the open file to read
+/// * `class` - the class declaring the method
+/// * `selector` - the name of the method, optionally followed by its descriptor
 /// # Return
-/// The parsed class file
-fn get_class(mut class_file: BufReader<File>) -> ClassFile {
-    // Read the leading header of the class file
-    get_class_header(&mut class_file);
+/// the method, or the static methods matching the selector if there is not exactly one
+fn select_method<'a>(class: &'a ClassFile, selector: &str) -> Result<&'a Method, Vec<&'a Method>> {
+    let (name, descriptor) = match selector.find('(') {
+        Some(start) => (&selector[..start], Some(&selector[start..])),
+        None => (selector, None),
+    };
+    let candidates: Vec<_> = class
+        .method
+        .iter()
+        .filter(|method| method.access_flags & ACC_STATIC != 0 && method.name == name)
+        .filter(|method| descriptor.is_none_or(|descriptor| method.descriptor == descriptor))
+        .collect();
+    match candidates[..] {
+        [method] => Ok(method),
+        _ => Err(candidates),
+    }
+}
 
-    // Read the constant pool
-    let constant_pool = get_constant_pool(&mut class_file);
+/// Convert an argument given as a string to the slots of a parameter, e.g. for `--method`.
+///
+/// # Parameters
+/// * `vm` - the state of the running program, which allocates strings
+/// * `descriptor` - the field descriptor of the parameter, e.g. "I"
+/// * `arg` - the argument, e.g. "42", "true" or "x" for a char
+/// # Return
+/// the slots of the value, or `IllegalArgumentException` if the argument is not
+/// a value of the type, which must be a primitive type or `String`
+fn parse_argument(vm: &mut Vm, descriptor: &str, arg: &str) -> Result<Vec<i32>, RuntimeError> {
+    let mut chars = arg.encode_utf16();
+    let value = match descriptor {
+        "I" => arg.parse().ok().map(|value: i32| vec![value]),
+        "S" => arg.parse().ok().map(|value: i16| vec![value as i32]),
+        "B" => arg.parse().ok().map(|value: i8| vec![value as i32]),
+        "C" => match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(vec![c as i32]),
+            _ => None,
+        },
+        "Z" => match arg {
+            "true" => Some(vec![1]),
+            "false" => Some(vec![0]),
+            _ => None,
+        },
+        "J" => arg.parse().ok().map(|value| long_to_slots(value).to_vec()),
+        "F" => arg
+            .parse()
+            .ok()
+            .map(|value: f32| vec![value.to_bits() as i32]),
+        "D" => arg
+            .parse()
+            .ok()
+            .map(|value| double_to_slots(value).to_vec()),
+        "Ljava/lang/String;" => Some(vec![vm.try_alloc(HeapObject::String(arg.to_string()))?]),
+        _ => None,
+    };
+    value.ok_or_else(|| {
+        RuntimeError::exception(
+            "java/lang/IllegalArgumentException",
+            &format!("Cannot pass \"{}\" as {}", arg, descriptor),
+        )
+    })
+}
 
-    // Read information about the class that was compiled.
-    get_class_info(&mut class_file);
+/// Format a value for a trace, strings are quoted and other objects
+/// are shown like `Object.toString` does, with their reference as hash code.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `descriptor` - the field descriptor of the type of the value, e.g. "I"
+/// * `slots` - the slots of the value
+fn format_trace_value(vm: &Vm, descriptor: &str, slots: &[i32]) -> String {
+    match descriptor {
+        _ if slots[0] == 0 && descriptor.starts_with(['L', '[']) => "null".to_string(),
+        "Ljava/lang/String;" => format!("{:?}", get_string(vm, slots[0])),
+        _ if descriptor.starts_with('L') => format!(
+            "{}@{:x}",
+            descriptor[1..descriptor.len() - 1].replace('/', "."),
+            slots[0]
+        ),
+        _ if descriptor.starts_with('[') => format!("{}@{:x}", descriptor, slots[0]),
+        _ => format_argument(vm, &format!("({})", descriptor), slots),
+    }
+}
 
-    // Read the list of static methods
-    let method = get_methods(&mut class_file, &constant_pool);
+/// Print the entry into a method with its arguments, e.g. "  factorial(4)".
+fn trace_call_entry(vm: &Vm, method: &Method, locals: &[i32]) {
+    // the receiver of an instance method comes before its arguments
+    let mut slot = (method.access_flags & ACC_STATIC == 0) as usize;
+    let mut args = vec![];
+    for descriptor in get_param_types(&method.descriptor) {
+        args.push(format_trace_value(vm, descriptor, &locals[slot..]));
+        slot += get_type_slots(descriptor);
+    }
+    eprintln!(
+        "{:indent$}{}({})",
+        "",
+        method.name,
+        args.join(", "),
+        indent = 2 * vm.call_depth
+    );
+}
 
-    ClassFile {
-        constant_pool,
-        method,
+/// Print the exit from a method with its return value or the exception it threw.
+fn trace_call_exit(vm: &Vm, method: &Method, result: &Result<Option<i32>, RuntimeError>) {
+    let exit = match result {
+        Ok(Some(value)) => {
+            let return_type = &method.descriptor[method.descriptor.find(')').unwrap() + 1..];
+            format!("return {}", format_trace_value(vm, return_type, &[*value]))
+        }
+        Ok(None) => "return".to_string(),
+        Err(RuntimeError::Exception { class, .. }) => format!("throw {}", class.replace('/', ".")),
+        Err(RuntimeError::Timeout) => "timeout".to_string(),
+        Err(RuntimeError::ReservedOpcode { opcode, pc }) => {
+            format!("reserved opcode {} at {}", opcode, pc)
+        }
+        Err(RuntimeError::FellOffMethodEnd { last_pc }) => {
+            format!("fell off the end after {}", last_pc)
+        }
+        Err(RuntimeError::DeadlockDetected) => "deadlock".to_string(),
+    };
+    eprintln!("{:indent$}{}", "", exit, indent = 2 * vm.call_depth);
+}
+
+/// Find the handler of an exception thrown by an instruction.
+///
+/// # Parameters
+/// * `code` - the code of the method the instruction belongs to
+/// * `class` - the class file the method belongs to
+/// * `pc` - the position of the instruction
+/// * `class_name` - the internal name of the class of the exception
+/// # Return
+/// the position of the first handler covering `pc` and catching the exception
+fn find_exception_handler(
+    code: &Code,
+    class: &ClassFile,
+    pc: usize,
+    class_name: &str,
+) -> Option<usize> {
+    code.exception_table
+        .iter()
+        .find(|handler| {
+            let covered = handler.start_pc as usize <= pc && pc < handler.end_pc as usize;
+            covered
+                && match handler.catch_type {
+                    0 => true,
+                    catch_type => is_subclass_of(
+                        class_name,
+                        class.class_name(catch_type).expect("Expected Class"),
+                    ),
+                }
+        })
+        .map(|handler| handler.handler_pc as usize)
+}
+
+/// Invoke an instance method on the runtime class of its receiver, for invokevirtual
+/// and invokeinterface. Objects of the program run the method `Vm::find_virtual_method`
+/// finds from their class; lambdas run their implementation or the default methods of
+/// their interface, and other objects their native methods.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `class_name` - the internal name of the class or interface named by the call
+/// * `name` - the name of the method, e.g. "area"
+/// * `descriptor` - the descriptor of the method, e.g. "()I"
+/// * `args` - the receiver followed by the slots of the arguments
+/// # Return
+/// the slots of the return value, empty if the method returns void
+fn invoke_virtual(
+    vm: &mut Vm,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    args: Vec<i32>,
+) -> Result<Vec<i32>, RuntimeError> {
+    let receiver = match args[0] {
+        0 => None,
+        reference => Some(vm.get_object(reference)),
+    };
+    let is_lambda = matches!(receiver, Some(HeapObject::Lambda { .. }));
+    let runtime_class = match receiver {
+        Some(HeapObject::Object { class_name, .. }) => Some(class_name.clone()),
+        _ => None,
+    };
+
+    // the method of an object of the program is looked up from its runtime class
+    if let Some(runtime_class) = runtime_class {
+        if let Some(own_class) = vm.find_virtual_method(&runtime_class, name, descriptor)? {
+            return invoke_method(vm, &own_class, name, descriptor, args);
+        }
+    }
+    // default methods of interfaces declared by the program run their own code
+    if is_lambda {
+        let interface = [class_name.to_string()];
+        if let Some(interface) = vm.find_default_method(&interface, name, descriptor)? {
+            return invoke_method(vm, &interface, name, descriptor, args);
+        }
+        return invoke_functional_interface(vm, args[0], &args[1..]);
     }
+    invoke_native(vm, class_name, name, descriptor, &args)
 }
 
-/// Execute the opcode instructions of a method until it returns.
+/// Invoke an instance method of the named class rather than of the runtime class
+/// of its receiver, for invokespecial: constructors, private methods and `super` calls.
 ///
 /// # Parameters
-/// * `method` - the method to run
-/// * `locals` - the array of local variables, including the method parameters.
-///              Except for parameters, the locals are uninitialized.
+/// the same as `invoke_virtual`
+/// # Return
+/// the slots of the return value, empty if the method returns void
+fn invoke_special(
+    vm: &mut Vm,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+    args: Vec<i32>,
+) -> Result<Vec<i32>, RuntimeError> {
+    // a method a superclass inherits is found from there, e.g. `super.toString()`
+    if !class_name.starts_with("java/") {
+        if let Some(own_class) = vm.find_virtual_method(class_name, name, descriptor)? {
+            return invoke_method(vm, &own_class, name, descriptor, args);
+        }
+    }
+    invoke_native(vm, class_name, name, descriptor, &args)
+}
+
+/// Run an instance method of a class of the program.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `class` - the class declaring the method
+/// * `name` - the name of the method, e.g. "area"
+/// * `descriptor` - the descriptor of the method, e.g. "()I"
+/// * `args` - the receiver followed by the slots of the arguments
+/// # Return
+/// the slots of the return value, empty if the method returns void
+fn invoke_method(
+    vm: &mut Vm,
+    class: &ClassFile,
+    name: &str,
+    descriptor: &str,
+    mut args: Vec<i32>,
+) -> Result<Vec<i32>, RuntimeError> {
+    let method = find_method(name, descriptor, &class.method);
+    check_arguments_fit(class, method, args.len())?;
+    // the receiver and arguments fill the first locals
+    args.resize(args.len().max(method.max_locals()), 0);
+    Ok(execute(vm, method, args, class)?.into_iter().collect())
+}
+
+/// Check that the locals of a method can hold its arguments, which a valid class file
+/// ensures but a malformed one may not, e.g. with `max_locals` 0 for a method taking an int.
+///
+/// # Parameters
+/// * `class` - the class declaring the method
+/// * `method` - the called method
+/// * `arg_slots` - the slots of the receiver, if any, and of the arguments
+/// # Return
+/// `ClassFormatError` if `max_locals` is less than the slots of the arguments;
+/// a method without code is left to `execute`, which throws `AbstractMethodError`
+fn check_arguments_fit(
+    class: &ClassFile,
+    method: &Method,
+    arg_slots: usize,
+) -> Result<(), RuntimeError> {
+    match &method.code {
+        Some(code) if (code.max_locals as usize) < arg_slots => Err(RuntimeError::exception(
+            "java/lang/ClassFormatError",
+            &format!(
+                "Arguments can't fit into locals in class file {}",
+                class.name()
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Record the constant pool entry an instruction refers to, if it refers to one.
+///
+/// # Parameters
+/// * `vm` - the state of the running program, which records the constants
+/// * `class` - the class file the running method belongs to
+/// * `code` - the bytecode of the running method
+/// * `pc` - the position of the instruction
+fn touch_constant(vm: &mut Vm, class: &ClassFile, code: &[u8], pc: usize) {
+    let index = match code[pc] {
+        i_ldc => code[pc + 1] as u16,
+        // the field and method instructions, new, anewarray, checkcast, instanceof
+        // and multianewarray take a 2-byte index
+        i_ldc_w
+        | i_ldc2_w
+        | i_getstatic..=i_invokedynamic
+        | i_new
+        | i_anewarray
+        | i_checkcast
+        | 193
+        | 197 => u16::from_be_bytes([code[pc + 1], code[pc + 2]]),
+        _ => return,
+    };
+    if let Some(touched) = &mut vm.touched_constants {
+        touched
+            .entry(class.name().to_string())
+            .or_default()
+            .insert(index);
+    }
+}
+
+/// Push slots onto the operand stack of a frame, a long or double takes two slots.
+/// This returns a `bool` rather than a `Result`, every `?` in `run_frame` grows its frame
+/// in debug builds and so lowers the reachable call depth.
+///
+/// # Return
+/// `false` if the slots do not fit into the `max_stack` of the method
+fn push_slots(op_stack: &mut [i32], op_count: &mut usize, slots: &[i32]) -> bool {
+    let end = *op_count + slots.len();
+    if end > op_stack.len() {
+        return false;
+    }
+    op_stack[*op_count..end].copy_from_slice(slots);
+    *op_count = end;
+    true
+}
+
+/// Read the signed 16-bit offset of a branch instruction.
+fn read_i16(code: &[u8], at: usize) -> i32 {
+    i16::from_be_bytes([code[at], code[at + 1]]) as i32
+}
+
+/// Compute the target of a branch instruction, whose offset is relative to its own position.
+/// The sum is computed with `isize`, since a method may be up to 65535 bytes long.
+///
+/// # Parameters
+/// * `pc` - the position of the branch instruction
+/// * `offset` - the offset of the instruction
+/// # Return
+/// the target; a target before the start of the code is `usize::MAX`,
+/// which like one past its end makes `run_frame` stop with `FellOffMethodEnd`
+fn branch(pc: usize, offset: i32) -> usize {
+    let target = pc as isize + offset as isize;
+    if target < 0 {
+        usize::MAX
+    } else {
+        target as usize
+    }
+}
+
+/// Get the value of a static field for `getstatic`, initializing the class declaring it first.
+/// This is kept out of `run_frame` to keep its frame small.
+///
+/// # Return
+/// the slots of the value, or the error raised by resolving the field
+fn get_static_field(
+    vm: &mut Vm,
+    class_name: &str,
+    name: &str,
+    descriptor: &str,
+) -> Result<Vec<i32>, RuntimeError> {
+    if class_name.starts_with("java/") {
+        return Ok(vec![get_native_static_field(vm, class_name, name)?]);
+    }
+    let key = vm.resolve_static_field(class_name, name, descriptor)?;
+    vm.static_fields
+        .get(&key)
+        .cloned()
+        .ok_or_else(|| RuntimeError::exception("java/lang/NoSuchFieldError", name))
+}
+
+/// Create the lambda of an `invokedynamic` call site bootstrapped by `LambdaMetafactory`,
+/// popping the values it captures. This is kept out of `run_frame` to keep its frame small.
+///
+/// # Parameters
+/// * `class` - the class of the running method
+/// * `index` - the constant pool index of the InvokeDynamic constant
+/// * `op_stack` - the operand stack of the running frame
+/// * `op_count` - the number of values on the operand stack
+/// # Return
+/// the reference to the lambda, or `OutOfMemoryError` if the heap is full
+fn create_lambda(
+    vm: &mut Vm,
+    class: &ClassFile,
+    index: u16,
+    op_stack: &[i32],
+    op_count: &mut usize,
+) -> Result<i32, RuntimeError> {
+    let cp = &class.constant_pool;
+    let (bootstrap_index, name_and_type_index) = match *get_constant(cp, index) {
+        ConstantPool::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        } => (bootstrap_method_attr_index, name_and_type_index),
+        _ => panic!("Expected InvokeDynamic"),
+    };
+    let bootstrap = &class.bootstrap_methods[bootstrap_index as usize];
+    let factory = get_method_handle_target(class, bootstrap.method_ref).class;
+    if factory != "java/lang/invoke/LambdaMetafactory" {
+        panic!("Unsupported bootstrap method in {}", factory);
+    }
+
+    // the second static argument is the method implementing the lambda
+    let ResolvedRef {
+        class: class_name,
+        name,
+        descriptor,
+    } = get_method_handle_target(class, bootstrap.arguments[1]);
+    let lambda_descriptor = match *get_constant(cp, name_and_type_index) {
+        ConstantPool::NameAndType {
+            descriptor_index, ..
+        } => get_utf8(cp, descriptor_index),
+        _ => panic!("Expected NameAndType"),
+    };
+
+    // the parameters of the call site are the captured values
+    let captured_count = get_param_slots(lambda_descriptor);
+    *op_count -= captured_count;
+    let captured = op_stack[*op_count..*op_count + captured_count].to_vec();
+    vm.try_alloc(HeapObject::Lambda {
+        class_name: class_name.to_string(),
+        name: name.to_string(),
+        descriptor: descriptor.to_string(),
+        captured,
+    })
+}
+
+/// Get the value `ldc` pushes for a constant, a string or class constant is the same object
+/// every time. This is kept out of `run_frame` to keep its frame small.
+///
+/// # Return
+/// the slot of the constant, or `OutOfMemoryError` if its object cannot be allocated
+fn load_constant(
+    vm: &mut Vm,
+    constant_pool: &[ConstantPool],
+    index: u16,
+) -> Result<i32, RuntimeError> {
+    Ok(match *get_constant(constant_pool, index) {
+        ConstantPool::Integer { bytes } => bytes,
+        // a float is pushed as its bits
+        ConstantPool::Float { bytes } => bytes as i32,
+        ConstantPool::String { string_index } => {
+            vm.intern(get_utf8(constant_pool, string_index))?
+        }
+        ConstantPool::Class { starting_index } => {
+            vm.class_object(get_utf8(constant_pool, starting_index))?
+        }
+        _ => panic!("Expected Integer, Float, String or Class"),
+    })
+}
+
+/// The error thrown when a push would exceed the `max_stack` of a method.
+fn operand_stack_overflow() -> RuntimeError {
+    RuntimeError::exception(
+        "java/lang/StackOverflowError",
+        "Operand stack exceeds max_stack",
+    )
+}
+
+/// The error thrown when an array instruction is given null instead of an array.
+fn null_array() -> RuntimeError {
+    RuntimeError::Exception {
+        class: "java/lang/NullPointerException".to_string(),
+        message: None,
+        cause: None,
+    }
+}
+
+/// Check an index into an array the way `xaload` and `xastore` do.
+///
+/// # Parameters
+/// * `index` - the index popped from the operand stack
+/// * `length` - the length of the array
+/// # Return
+/// the index, or `ArrayIndexOutOfBoundsException` if it is negative or not less than the length
+fn check_array_index(index: i32, length: usize) -> Result<usize, RuntimeError> {
+    if index < 0 || index as usize >= length {
+        return Err(RuntimeError::exception(
+            "java/lang/ArrayIndexOutOfBoundsException",
+            &format!("Index {} out of bounds for length {}", index, length),
+        ));
+    }
+    Ok(index as usize)
+}
+
+/// Load an element of an array for `iaload`, `baload` or `aaload`.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `array` - the reference to the array
+/// * `index` - the index of the element
+/// # Return
+/// the element as an int, a byte being sign-extended;
+/// `NullPointerException` if the array is null;
+/// `ArrayIndexOutOfBoundsException` if the index is out of bounds
+fn load_array_element(vm: &Vm, array: i32, index: i32) -> Result<i32, RuntimeError> {
+    if array == 0 {
+        return Err(null_array());
+    }
+    Ok(match vm.get_object(array) {
+        HeapObject::IntArray(values) | HeapObject::ReferenceArray(values) => {
+            values[check_array_index(index, values.len())?]
+        }
+        HeapObject::ByteArray(values) => values[check_array_index(index, values.len())?] as i32,
+        _ => panic!("Expected an array"),
+    })
+}
+
+/// Store an element into an array for `iastore`, `bastore` or `aastore`.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `array` - the reference to the array
+/// * `index` - the index of the element
+/// * `value` - the int or reference stored, of which a byte array keeps the low 8 bits
+/// # Return
+/// `NullPointerException` if the array is null;
+/// `ArrayIndexOutOfBoundsException` if the index is out of bounds
+fn store_array_element(
+    vm: &mut Vm,
+    array: i32,
+    index: i32,
+    value: i32,
+) -> Result<(), RuntimeError> {
+    if array == 0 {
+        return Err(null_array());
+    }
+    match vm.get_object_mut(array) {
+        HeapObject::IntArray(values) | HeapObject::ReferenceArray(values) => {
+            let index = check_array_index(index, values.len())?;
+            values[index] = value
+        }
+        HeapObject::ByteArray(values) => {
+            let index = check_array_index(index, values.len())?;
+            values[index] = value as i8
+        }
+        _ => panic!("Expected an array"),
+    }
+    Ok(())
+}
+
+/// Execute the opcode instructions of a method from `frame_pc` until it returns or throws.
+///
+/// # Parameters
+/// * `vm` - the state of the running program
+/// * `code` - the code of the method to run
+/// * `locals` - the array of local variables
 /// * `class` - the class file the method belongs to
+/// * `frame_pc` - the position to start at, kept at the instruction being executed
+/// * `exception` - the exception caught by the handler at `frame_pc`, if any
 /// # Return
-/// If the method returns an int, pass it as `Some()`;
-/// `None` if the method returns void;
-fn execute(method: &Method, mut locals: Vec<i32>, class: &ClassFile) -> Option<i32> {
-    let code = &method.code;
+/// the same as `execute`
+fn run_frame(
+    vm: &mut Vm,
+    code: &Code,
+    locals: &mut [i32],
+    class: &ClassFile,
+    frame_pc: &mut usize,
+    exception: Option<i32>,
+) -> Result<Option<i32>, RuntimeError> {
     let mut op_stack = vec![0i32; code.max_stack as usize];
     let mut op_count = 0;
+    if let Some(exception) = exception {
+        if !push_slots(&mut op_stack, &mut op_count, &[exception]) {
+            return Err(operand_stack_overflow());
+        }
+    }
 
     // position at the program to be run
-    let mut pc = 0;
+    let mut pc = *frame_pc;
     let code_buf = &code.code;
 
     while pc < code.code_length as usize {
         let current = code_buf[pc];
+        *frame_pc = pc;
 
-        // Reference: https://en.wikipedia.org/wiki/Java_bytecode_instruction_listings
-        match current {
-            // Return int from method
-            i_ireturn => return Some(op_stack[op_count - 1]),
-            // Return void from method
-            i_return => return None,
-            // Invoke a class (static) method
-            i_invokestatic => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let index = u16::from_be_bytes([param1, param2]);
-                // the method to be called
-                let own_method = find_method_from_index(index, class);
-                let num_params = own_method.descriptor.len() - 3;
-                let mut own_locals = vec![0i32; own_method.code.max_locals as usize];
-
-                for i in (0..num_params).rev() {
-                    own_locals[i] = op_stack[op_count - 1];
-                    op_count -= 1;
-                }
-
-                let exec_res = execute(own_method, own_locals, class);
-                if let Some(res) = exec_res {
-                    op_stack[op_count] = res;
-                    op_count += 1;
-                }
-
-                pc += 3;
-            }
-            // Branch if int comparison with zero succeeds: if equals
-            i_ifeq => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let conditional = op_stack[op_count - 1];
-                pc += 3;
-                if conditional == 0 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 1;
-            }
-            // Branch if int comparison with zero succeeds: if not equals
-            i_ifne => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let conditional = op_stack[op_count - 1];
-                pc += 3;
-                if conditional != 0 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 1;
-            }
-            // Branch if int comparison with zero succeeds: if less than 0
-            i_iflt => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let conditional = op_stack[op_count - 1];
-                pc += 3;
-                if conditional < 0 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
+        if let Some(counts) = &mut vm.opcode_counts {
+            *counts.entry(current).or_insert(0) += 1;
+        }
+        if vm.touched_constants.is_some() {
+            touch_constant(vm, class, code_buf, pc);
+        }
+
+        // check the deadline every so often, this also catches infinite loops
+        vm.instruction_count += 1;
+        if vm.instruction_count.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            if let Some(deadline) = vm.deadline {
+                if Instant::now() >= deadline {
+                    return Err(RuntimeError::Timeout);
                 }
-                op_count -= 1;
             }
-            // Branch if int comparison with zero succeeds: if >= 0
-            i_ifge => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let conditional = op_stack[op_count - 1];
-                pc += 3;
-                if conditional >= 0 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 1;
+            // in threaded mode, the other threads get their turn as often
+            if vm.scheduler.is_some() {
+                vm.save_frame_roots(locals, &op_stack[..op_count]);
+                park(vm, Park::Yield)?;
             }
-            // Branch if int comparison with zero succeeds: if greater than 0
-            i_ifgt => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let conditional = op_stack[op_count - 1];
-                pc += 3;
-                if conditional > 0 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 1;
+        }
+
+        // instructions which call methods, allocate or wait for another thread
+        // may trigger a garbage collection
+        if matches!(
+            current,
+            i_ldc
+                | i_ldc_w
+                | i_getstatic
+                | i_putstatic
+                | i_invokevirtual
+                | i_invokespecial
+                | i_invokestatic
+                | i_invokeinterface
+                | i_invokedynamic
+                | i_new
+                | i_newarray
+                | i_anewarray
+                | i_monitorenter
+        ) {
+            vm.save_frame_roots(locals, &op_stack[..op_count]);
+            if vm.heap.len() >= vm.next_gc {
+                vm.collect_garbage();
             }
-            // Branch if int comparison with zero succeeds: if <= 0
-            i_ifle => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let conditional = op_stack[op_count - 1];
-                pc += 3;
-                if conditional <= 0 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 1;
+        }
+
+        // Reference: https://en.wikipedia.org/wiki/Java_bytecode_instruction_listings
+        match current {
+            // Return int or reference from method
+            // the rest of the operand stack is discarded along with the frame
+            i_ireturn | i_areturn => {
+                debug_assert!(op_count > 0, "Return with an empty operand stack");
+                return Ok(Some(op_stack[op_count - 1]));
             }
-            // Branch if int comparison succeeds: if equals
-            i_if_icmpeq => {
+            // Return void from method
+            i_return => return Ok(None),
+            // Invoke a class (static) method
+            i_invokestatic => {
                 let param1 = code_buf[pc + 1];
                 let param2 = code_buf[pc + 2];
-                let op1 = op_stack[op_count - 1];
-                let op2 = op_stack[op_count - 2];
-                pc += 3;
-                if op1 == op2 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
+                let index = u16::from_be_bytes([param1, param2]);
+
+                // methods of the class library are executed natively
+                let ResolvedRef {
+                    class: class_name,
+                    name,
+                    descriptor,
+                } = resolve_ref(class, index);
+                if class_name.starts_with("java/") {
+                    op_count -= get_param_slots(descriptor);
+                    let args = op_stack[op_count..op_count + get_param_slots(descriptor)].to_vec();
+                    let result = invoke_native(vm, class_name, name, descriptor, &args)?;
+                    if !push_slots(&mut op_stack, &mut op_count, &result) {
+                        return Err(operand_stack_overflow());
+                    }
+                    pc += 3;
+                    continue;
                 }
-                op_count -= 2;
-            }
-            // Branch if int comparison succeeds: if not equals
-            i_if_icmpne => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let op1 = op_stack[op_count - 1];
-                let op2 = op_stack[op_count - 2];
-                pc += 3;
-                if op1 != op2 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
+
+                // the method to be called; the class of the running method is already initialized
+                let other_class = if class_name == class.name() {
+                    None
+                } else {
+                    Some(vm.initialize_class(class_name)?)
+                };
+                let own_class = other_class.as_deref().unwrap_or(class);
+                let own_method = find_method_from_index(index, class, own_class);
+                // the arguments fill the first locals in order,
+                // a long or double argument takes two of them
+                let param_slots = get_param_slots(&own_method.descriptor);
+                check_arguments_fit(own_class, own_method, param_slots)?;
+                let mut own_locals = vec![0i32; own_method.max_locals().max(param_slots)];
+                op_count -= param_slots;
+                own_locals[..param_slots]
+                    .copy_from_slice(&op_stack[op_count..op_count + param_slots]);
+
+                let exec_res = execute(vm, own_method, own_locals, own_class)?;
+                if let Some(res) = exec_res {
+                    if !push_slots(&mut op_stack, &mut op_count, &[res]) {
+                        return Err(operand_stack_overflow());
+                    }
                 }
-                op_count -= 2;
-            }
-            // Branch if int comparison succeeds: if less than
-            i_if_icmplt => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let op1 = op_stack[op_count - 1];
-                let op2 = op_stack[op_count - 2];
+
                 pc += 3;
-                if op2 < op1 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 2;
             }
-            // Branch if int comparison succeeds: if greater than or equal to
-            i_if_icmpge => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let op1 = op_stack[op_count - 1];
-                let op2 = op_stack[op_count - 2];
-                pc += 3;
-                if op2 >= op1 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 2;
+            // Branch if int comparison with zero succeeds
+            i_ifeq..=i_ifle => {
+                let value = op_stack[op_count - 1];
+                op_count -= 1;
+                let taken = match current {
+                    i_ifeq => value == 0,
+                    i_ifne => value != 0,
+                    i_iflt => value < 0,
+                    i_ifge => value >= 0,
+                    i_ifgt => value > 0,
+                    _ => value <= 0,
+                };
+                pc = if taken {
+                    branch(pc, read_i16(code_buf, pc + 1))
+                } else {
+                    pc + 3
+                };
             }
-            // Branch if int comparison succeeds: if greater than
-            i_if_icmpgt => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let op1 = op_stack[op_count - 1];
-                let op2 = op_stack[op_count - 2];
-                pc += 3;
-                if op2 > op1 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
+            // Branch if int or reference comparison succeeds
+            i_if_icmpeq..=i_if_acmpne => {
+                let op1 = op_stack[op_count - 2];
+                let op2 = op_stack[op_count - 1];
                 op_count -= 2;
+                let taken = match current {
+                    i_if_icmpeq | i_if_acmpeq => op1 == op2,
+                    i_if_icmpne | i_if_acmpne => op1 != op2,
+                    i_if_icmplt => op1 < op2,
+                    i_if_icmpge => op1 >= op2,
+                    i_if_icmpgt => op1 > op2,
+                    _ => op1 <= op2,
+                };
+                pc = if taken {
+                    branch(pc, read_i16(code_buf, pc + 1))
+                } else {
+                    pc + 3
+                };
             }
-            // Branch if int comparison succeeds: if less than or equal to
-            i_if_icmple => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let op1 = op_stack[op_count - 1];
-                let op2 = op_stack[op_count - 2];
-                pc += 3;
-                if op2 <= op1 {
-                    let res = i16::from_be_bytes([param1, param2]);
-                    pc = (pc as i16 + res - 3) as usize;
-                }
-                op_count -= 2;
+            // Branch if reference is null or not null
+            i_ifnull | i_ifnonnull => {
+                let reference = op_stack[op_count - 1];
+                op_count -= 1;
+                let taken = (reference == 0) == (current == i_ifnull);
+                pc = if taken {
+                    branch(pc, read_i16(code_buf, pc + 1))
+                } else {
+                    pc + 3
+                };
             }
             // Branch always
-            i_goto => {
-                let param1 = code_buf[pc + 1];
-                let param2 = code_buf[pc + 2];
-                let res = i16::from_be_bytes([param1, param2]);
-                pc = (pc as i16 + res) as usize;
+            i_goto => pc = branch(pc, read_i16(code_buf, pc + 1)),
+            // Branch always (wide index), javac uses it in methods longer than 32767 bytes
+            i_goto_w => {
+                let offset = [pc + 1, pc + 2, pc + 3, pc + 4].map(|at| code_buf[at]);
+                pc = branch(pc, i32::from_be_bytes(offset));
             }
             // Push item from run-time constant pool
-            i_ldc => {
+            i_ldc | i_ldc_w => {
                 let constant_pool = &class.constant_pool;
 
                 // find the parameter which will be the index from which we retrieve
-                // constant in the constant pool.
-                let param = code_buf[pc + 1];
-
-                // get the constant
-                let info = get_constant(constant_pool, param as u16);
-                if let &ConstantPool::Integer { bytes } = info {
-                    op_stack[op_count] = bytes;
-                    pc += 2;
-                    op_count += 1;
+                // constant in the constant pool, ldc_w has a wide index.
+                let (index, length) = if current == i_ldc_w {
+                    (u16::from_be_bytes([code_buf[pc + 1], code_buf[pc + 2]]), 3)
                 } else {
-                    panic!("Expected Integer");
+                    (code_buf[pc + 1] as u16, 2)
+                };
+
+                let value = load_constant(vm, constant_pool, index)?;
+                if !push_slots(&mut op_stack, &mut op_count, &[value]) {
+                    return Err(operand_stack_overflow());
                 }
+                pc += length;
             }
-            // Load int from local variable
-            i_iload_0..=i_iload_3 => {
-                let param = (current - i_iload_0) as usize;
-                let loaded = locals[param];
-                op_stack[op_count] = loaded;
-                pc += 1;
-                op_count += 1;
-            }
-            i_iload => {
-                let param = code_buf[pc + 1] as usize;
-                let loaded = locals[param];
-                op_stack[op_count] = loaded;
-                pc += 2;
-                op_count += 1;
+            // Push long or double from run-time constant pool (wide index)
+            i_ldc2_w => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+
+                // both are pushed as their high bits followed by their low bits
+                let slots = match *get_constant(&class.constant_pool, index) {
+                    ConstantPool::Long {
+                        high_bytes,
+                        low_bytes,
+                    }
+                    | ConstantPool::Double {
+                        high_bytes,
+                        low_bytes,
+                    } => [high_bytes as i32, low_bytes as i32],
+                    _ => panic!("Expected Long or Double"),
+                };
+                if !push_slots(&mut op_stack, &mut op_count, &slots) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 3;
             }
-            // Store int into local variable
-            i_istore => {
-                let param = code_buf[pc + 1] as usize;
-                let stored = op_stack[op_count - 1];
-                locals[param] = stored;
-                pc += 2;
-                op_count -= 1;
+            // Load int, long, float, double or reference from local variable
+            i_iload..=i_aload | i_iload_0..=i_aload_3 => {
+                let (_, index, slots) = get_local_access(code_buf, pc).unwrap();
+                if !push_slots(&mut op_stack, &mut op_count, &locals[index..index + slots]) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += if current <= i_aload { 2 } else { 1 };
             }
-            i_istore_0..=i_istore_3 => {
-                let param = (current - i_istore_0) as usize;
-                let stored = op_stack[op_count - 1];
-                locals[param] = stored;
-                pc += 1;
-                op_count -= 1;
+            // Store int, long, float, double or reference into local variable.
+            // A long or double overwriting half of another leaves the other unusable,
+            // which verify_local_variables checks no instruction reads.
+            i_istore..=i_astore | i_istore_0..=i_astore_3 => {
+                let (_, index, slots) = get_local_access(code_buf, pc).unwrap();
+                op_count -= slots;
+                locals[index..index + slots].copy_from_slice(&op_stack[op_count..op_count + slots]);
+                pc += if current <= i_astore { 2 } else { 1 };
             }
             // Increment local variable by constant
             i_iinc => {
                 let i = code_buf[pc + 1] as usize;
                 let b = i8::from_be_bytes([code_buf[pc + 2]]); // signed value
-                locals[i] += b as i32;
+                locals[i] = locals[i].wrapping_add(b as i32);
                 pc += 3;
             }
             // Push byte
             i_bipush => {
                 let param = i8::from_be_bytes([code_buf[pc + 1]]);
-                op_stack[op_count] = param as i32;
-                op_count += 1;
+                if !push_slots(&mut op_stack, &mut op_count, &[param as i32]) {
+                    return Err(operand_stack_overflow());
+                }
                 pc += 2;
             }
             // Add int
@@ -666,10 +9585,16 @@ fn execute(method: &Method, mut locals: Vec<i32>, class: &ClassFile) -> Option<i
                 op_count -= 1;
                 pc += 1;
             }
-            // Divide int
+            // Divide int, the minimum divided by -1 overflows back to the minimum
             i_idiv => {
                 let op1 = op_stack[op_count - 1];
                 let op2 = op_stack[op_count - 2];
+                if op1 == 0 {
+                    return Err(RuntimeError::exception(
+                        "java/lang/ArithmeticException",
+                        "/ by zero",
+                    ));
+                }
                 let res = op2.wrapping_div(op1);
                 op_stack[op_count - 2] = res;
                 op_count -= 1;
@@ -679,6 +9604,12 @@ fn execute(method: &Method, mut locals: Vec<i32>, class: &ClassFile) -> Option<i
             i_irem => {
                 let op1 = op_stack[op_count - 1];
                 let op2 = op_stack[op_count - 2];
+                if op1 == 0 {
+                    return Err(RuntimeError::exception(
+                        "java/lang/ArithmeticException",
+                        "/ by zero",
+                    ));
+                }
                 let res = op2.wrapping_rem(op1);
                 op_stack[op_count - 2] = res;
                 op_count -= 1;
@@ -687,23 +9618,342 @@ fn execute(method: &Method, mut locals: Vec<i32>, class: &ClassFile) -> Option<i
             // Negate int
             i_ineg => {
                 let op1 = op_stack[op_count - 1];
-                op_stack[op_count - 1] = op1.wrapping_mul(-1);
+                op_stack[op_count - 1] = op1.wrapping_neg();
+                pc += 1;
+            }
+            // Convert int to byte, sign-extending the low 8 bits
+            i_i2b => {
+                op_stack[op_count - 1] = op_stack[op_count - 1] as i8 as i32;
+                pc += 1;
+            }
+            // Convert int to char, keeping the low 16 bits
+            i_i2c => {
+                op_stack[op_count - 1] = op_stack[op_count - 1] as u16 as i32;
+                pc += 1;
+            }
+            // Convert int to short, sign-extending the low 16 bits
+            i_i2s => {
+                op_stack[op_count - 1] = op_stack[op_count - 1] as i16 as i32;
                 pc += 1;
             }
             // Get static field from class
-            i_getstatic => pc += 3, // FIXME: unimplemented
-            // Invoke instance method; dispatch based on class
-            i_invokevirtual => {
-                let op = op_stack[op_count - 1];
-                // FIXME: the implement is not correct.
-                println!("{}", op);
+            i_getstatic => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let ResolvedRef {
+                    class: class_name,
+                    name,
+                    descriptor,
+                } = resolve_ref(class, index);
+
+                let value = get_static_field(vm, class_name, name, descriptor)?;
+                if !push_slots(&mut op_stack, &mut op_count, &value) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 3;
+            }
+            // Set static field in class
+            i_putstatic => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let ResolvedRef {
+                    class: class_name,
+                    name,
+                    descriptor,
+                } = resolve_ref(class, index);
+
+                let key = vm.resolve_static_field(class_name, name, descriptor)?;
+                let slots = get_type_slots(descriptor);
+                op_count -= slots;
+                let value = op_stack[op_count..op_count + slots].to_vec();
+                vm.static_fields.insert(key, value);
+                pc += 3;
+            }
+            // Get field from object
+            i_getfield => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let name = resolve_ref(class, index).name;
+
+                let object = op_stack[op_count - 1];
+                if object == 0 {
+                    return Err(RuntimeError::Exception {
+                        class: "java/lang/NullPointerException".to_string(),
+                        message: None,
+                        cause: None,
+                    });
+                }
+                let value = match vm.get_object(object) {
+                    HeapObject::Object { fields, .. } => fields[name].clone(),
+                    _ => panic!("Expected an object of the program"),
+                };
                 op_count -= 1;
+                if !push_slots(&mut op_stack, &mut op_count, &value) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 3;
+            }
+            // Set field in object
+            i_putfield => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let ResolvedRef {
+                    name, descriptor, ..
+                } = resolve_ref(class, index);
+
+                let slots = get_type_slots(descriptor);
+                op_count -= slots + 1;
+                let object = op_stack[op_count];
+                if object == 0 {
+                    return Err(RuntimeError::Exception {
+                        class: "java/lang/NullPointerException".to_string(),
+                        message: None,
+                        cause: None,
+                    });
+                }
+                let value = op_stack[op_count + 1..op_count + 1 + slots].to_vec();
+                match vm.get_object_mut(object) {
+                    HeapObject::Object { fields, .. } => {
+                        fields.insert(name.to_string(), value);
+                    }
+                    _ => panic!("Expected an object of the program"),
+                }
+                pc += 3;
+            }
+            // Invoke instance method; dispatch based on class
+            // Invoke interface method
+            i_invokevirtual | i_invokeinterface => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let ResolvedRef {
+                    class: class_name,
+                    name,
+                    descriptor,
+                } = resolve_ref(class, index);
+
+                // pop the arguments along with the receiver
+                let arg_count = get_param_slots(descriptor) + 1;
+                op_count -= arg_count;
+                let args = op_stack[op_count..op_count + arg_count].to_vec();
+                let result = invoke_virtual(vm, class_name, name, descriptor, args)?;
+                if !push_slots(&mut op_stack, &mut op_count, &result) {
+                    return Err(operand_stack_overflow());
+                }
+
+                // invokeinterface also carries the argument count and a zero byte
+                pc += if current == i_invokeinterface { 5 } else { 3 };
+            }
+            // Create new object
+            i_new => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let class_name = class.class_name(index).expect("Expected Class");
+
+                let object = if class_name.starts_with("java/") {
+                    new_native_object(class_name)
+                } else {
+                    vm.new_object(class_name)?
+                };
+                let object = vm.try_alloc(object)?;
+                if !push_slots(&mut op_stack, &mut op_count, &[object]) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 3;
+            }
+            // Invoke instance method; special handling for superclass, private,
+            // and instance initialization method invocations
+            i_invokespecial => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let ResolvedRef {
+                    class: class_name,
+                    name,
+                    descriptor,
+                } = resolve_ref(class, index);
+
+                // pop the arguments along with the receiver
+                let arg_count = get_param_slots(descriptor) + 1;
+                op_count -= arg_count;
+                let args = op_stack[op_count..op_count + arg_count].to_vec();
+                let result = invoke_special(vm, class_name, name, descriptor, args)?;
+                if !push_slots(&mut op_stack, &mut op_count, &result) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 3;
+            }
+            // Invoke a dynamically-computed call site
+            // FIXME: only lambdas created by LambdaMetafactory are supported
+            i_invokedynamic => {
+                let param1 = code_buf[pc + 1];
+                let param2 = code_buf[pc + 2];
+                let index = u16::from_be_bytes([param1, param2]);
+                let lambda = create_lambda(vm, class, index, &op_stack, &mut op_count)?;
+                if !push_slots(&mut op_stack, &mut op_count, &[lambda]) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 5;
+            }
+            // Create new array
+            i_newarray => {
+                let atype = code_buf[pc + 1];
+                let count = op_stack[op_count - 1];
+                if count < 0 {
+                    return Err(RuntimeError::exception(
+                        "java/lang/NegativeArraySizeException",
+                        &count.to_string(),
+                    ));
+                }
+                let count = count as usize;
+                // FIXME: only int[] and byte[] are supported
+                let array = match atype {
+                    8 => HeapObject::ByteArray(vec![0; count]),
+                    10 => HeapObject::IntArray(vec![0; count]),
+                    _ => panic!("Unsupported array type: {}", atype),
+                };
+                op_stack[op_count - 1] = vm.try_alloc(array)?;
+                pc += 2;
+            }
+            // Create new array of references, whose elements start as null
+            i_anewarray => {
+                let count = op_stack[op_count - 1];
+                if count < 0 {
+                    return Err(RuntimeError::exception(
+                        "java/lang/NegativeArraySizeException",
+                        &count.to_string(),
+                    ));
+                }
+                let array = HeapObject::ReferenceArray(vec![0; count as usize]);
+                op_stack[op_count - 1] = vm.try_alloc(array)?;
                 pc += 3;
             }
+            // Get length of array
+            i_arraylength => {
+                let array = op_stack[op_count - 1];
+                if array == 0 {
+                    return Err(null_array());
+                }
+                op_stack[op_count - 1] = match vm.get_object(array) {
+                    HeapObject::IntArray(values) | HeapObject::ReferenceArray(values) => {
+                        values.len() as i32
+                    }
+                    HeapObject::ByteArray(values) => values.len() as i32,
+                    _ => panic!("Expected an array"),
+                };
+                pc += 1;
+            }
+            // Load int, byte sign-extended to an int, or reference from array
+            i_iaload | i_baload | i_aaload => {
+                let index = op_stack[op_count - 1];
+                let array = op_stack[op_count - 2];
+                op_stack[op_count - 2] = load_array_element(vm, array, index)?;
+                op_count -= 1;
+                pc += 1;
+            }
+            // Store into int, byte keeping the low 8 bits of the int, or reference array
+            i_iastore | i_bastore | i_aastore => {
+                let value = op_stack[op_count - 1];
+                let index = op_stack[op_count - 2];
+                let array = op_stack[op_count - 3];
+                store_array_element(vm, array, index, value)?;
+                op_count -= 3;
+                pc += 1;
+            }
+            // Duplicate the value on top of the stack
+            i_dup => {
+                let top = op_stack[op_count - 1];
+                if !push_slots(&mut op_stack, &mut op_count, &[top]) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 1;
+            }
+            // Discard the value on top of the stack
+            i_pop => {
+                op_count -= 1;
+                pc += 1;
+            }
+            // Discard the two values on top of the stack, or the long or double on top.
+            // Either way those are the two slots on top.
+            i_pop2 => {
+                op_count -= 2;
+                pc += 1;
+            }
+            // Duplicate the two values on top of the stack, or the long or double on top
+            i_dup2 => {
+                let top = [op_stack[op_count - 2], op_stack[op_count - 1]];
+                if !push_slots(&mut op_stack, &mut op_count, &top) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 1;
+            }
+            // Perform no operation
+            i_nop => pc += 1,
+            // Push null
+            i_aconst_null => {
+                if !push_slots(&mut op_stack, &mut op_count, &[0]) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 1;
+            }
+            // Throw exception or error
+            i_athrow => {
+                let exception = op_stack[op_count - 1];
+                return Err(match vm.get_object(exception) {
+                    HeapObject::Throwable {
+                        class_name,
+                        message,
+                        cause,
+                    } => RuntimeError::Exception {
+                        class: class_name.clone(),
+                        message: message.clone(),
+                        cause: cause.clone(),
+                    },
+                    _ => panic!("Expected a Throwable"),
+                });
+            }
+            // Check whether object is of given type
+            // FIXME: types of objects are unchecked
+            i_checkcast => pc += 3,
+            // Enter monitor for object
+            i_monitorenter => {
+                vm.enter_monitor(op_stack[op_count - 1])?;
+                op_count -= 1;
+                pc += 1;
+            }
+            // Exit monitor for object
+            i_monitorexit => {
+                vm.exit_monitor(op_stack[op_count - 1])?;
+                op_count -= 1;
+                pc += 1;
+            }
             // Push int constant
             i_iconst_m1..=i_iconst_5 => {
-                op_stack[op_count] = current as i32 - i_iconst_0 as i32;
-                op_count += 1;
+                let value = current as i32 - i_iconst_0 as i32;
+                if !push_slots(&mut op_stack, &mut op_count, &[value]) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 1;
+            }
+            // Push long constant
+            i_lconst_0 | i_lconst_1 => {
+                let slots = long_to_slots((current - i_lconst_0) as i64);
+                if !push_slots(&mut op_stack, &mut op_count, &slots) {
+                    return Err(operand_stack_overflow());
+                }
+                pc += 1;
+            }
+            // Push double constant
+            i_dconst_0 | i_dconst_1 => {
+                let slots = double_to_slots((current - i_dconst_0) as f64);
+                if !push_slots(&mut op_stack, &mut op_count, &slots) {
+                    return Err(operand_stack_overflow());
+                }
                 pc += 1;
             }
             // Push short
@@ -711,38 +9961,294 @@ fn execute(method: &Method, mut locals: Vec<i32>, class: &ClassFile) -> Option<i
                 let param1 = code_buf[pc + 1];
                 let param2 = code_buf[pc + 2];
                 let res = i16::from_be_bytes([param1, param2]);
-                op_stack[op_count] = res as i32;
-                op_count += 1;
+                if !push_slots(&mut op_stack, &mut op_count, &[res as i32]) {
+                    return Err(operand_stack_overflow());
+                }
                 pc += 3;
             }
+            // FIXME: there is no debugger to stop at a breakpoint
+            i_breakpoint | i_impdep1 | i_impdep2 => {
+                return Err(RuntimeError::ReservedOpcode {
+                    opcode: current,
+                    pc,
+                })
+            }
             _ => unreachable!(),
         }
     }
 
-    None
+    // valid code always returns or throws before its end, so this is a bug of the compiler,
+    // e.g. a missing return or a jump past the end
+    Err(RuntimeError::FellOffMethodEnd { last_pc: *frame_pc })
 }
 
 fn main() -> std::io::Result<()> {
-    // Open class file into buffer reader
+    // a panic is reported by the spawned thread and ends the process with the same status
+    std::thread::Builder::new()
+        .name("main".to_string())
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn(run)?
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Run the command line: parse the options, then load and run the class file.
+fn run() -> std::io::Result<()> {
+    // Parse command line options up to the class file, the rest are passed to main
     let mut args = std::env::args();
     args.next();
-    let file = File::open(args.next().unwrap())?;
-    let buf_reader = BufReader::new(file);
-
-    // Parse class file
-    let class = get_class(buf_reader);
-
-    // execute the main method if found
-    let main_method = find_method("main", "([Ljava/lang/String;)V", &class.method);
+    let mut path = None;
+    let mut timeout = None;
+    let mut verify = false;
+    let mut parse_only = false;
+    let mut print_cp = false;
+    let mut disassemble = false;
+    let mut json = false;
+    let mut opcode_histogram = false;
+    let mut trace_calls = false;
+    let mut profile = false;
+    let mut threading = false;
+    let mut constant_coverage = false;
+    let mut assertions_enabled = false;
+    let mut max_heap = None;
+    let mut method = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--verify" => verify = true,
+            "--parse-only" => parse_only = true,
+            "--print-cp" => print_cp = true,
+            "--disassemble" => disassemble = true,
+            "--json" => json = true,
+            "--opcode-histogram" => opcode_histogram = true,
+            "--trace-calls" => trace_calls = true,
+            "--profile" => profile = true,
+            "--threading" => threading = true,
+            "--constant-coverage" => constant_coverage = true,
+            "--enable-assertions" => assertions_enabled = true,
+            "--max-heap" => {
+                let objects = args
+                    .next()
+                    .expect("Expected a number of objects after --max-heap");
+                max_heap = match objects.as_str() {
+                    "unlimited" => None,
+                    objects => Some(
+                        objects
+                            .parse()
+                            .expect("Expected a number of objects after --max-heap"),
+                    ),
+                };
+            }
+            "--method" => {
+                method = Some(args.next().expect("Expected a method name after --method"));
+            }
+            "--timeout" => {
+                let millis = args.next().expect("Expected milliseconds after --timeout");
+                let millis = millis
+                    .parse()
+                    .expect("Expected milliseconds after --timeout");
+                timeout = Some(Duration::from_millis(millis));
+            }
+            _ => {
+                path = Some(arg);
+                break;
+            }
+        }
+    }
+    let program_args: Vec<String> = args.collect();
 
-    // FIXME: locals[0] contains a reference to String[] args, but right now
-    // we lack of the support for java.lang.Object. Leave it uninitialized.
-    let locals = vec![0i32; main_method.code.max_locals as usize];
-    let result = execute(main_method, locals, &class);
+    // Parse the class file, or the main class of a jar named by its manifest
+    let path = PathBuf::from(path.expect("Expected a class file or a jar"));
+    let mut jar = None;
+    let (name, class) = if path.extension().is_some_and(|extension| extension == "jar") {
+        let opened = Jar::open(&path)?;
+        let name = match opened.main_class() {
+            Some(main_class) => main_class.replace('.', "/"),
+            None => {
+                eprintln!("No Main-Class in the manifest of {}", path.display());
+                eprintln!("The jar contains these classes:");
+                for class_name in opened.class_names() {
+                    eprintln!("    {}", class_name);
+                }
+                std::process::exit(1);
+            }
+        };
+        let class = match opened.read(&format!("{}.class", name)) {
+            Some(bytes) => get_class(bytes),
+            None => {
+                eprintln!("Cannot find the main class {} in {}", name, path.display());
+                std::process::exit(1);
+            }
+        };
+        jar = Some(opened);
+        (name, class)
+    } else {
+        let file = File::open(&path)?;
+        let class = get_class(BufReader::new(file));
+        // the class is keyed by the name it declares, its file name is only a fallback
+        let name = match &class {
+            Ok(class) => class.name().to_string(),
+            Err(_) => path.file_stem().unwrap().to_string_lossy().to_string(),
+        };
+        (name, class)
+    };
+    if parse_only || print_cp || disassemble {
+        // Only report whether the class is well-formed, or print it, without running it
+        let class = match class {
+            Ok(class) => class,
+            Err(e) => {
+                eprintln!("ParseError: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+        if verify {
+            for method in &class.method {
+                let verified = verify_branch_targets(method)
+                    .and_then(|()| verify_stack_map_table(&class, method))
+                    .and_then(|()| verify_local_variables(method));
+                if let Err(e) = verified {
+                    eprintln!("VerifyError: {:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        if print_cp || disassemble {
+            print_class(&class, print_cp, disassemble, json);
+        }
+        return Ok(());
+    }
 
-    if let Some(_) = result {
-        panic!("main() should return void");
+    let class = Arc::new(class.expect("Cannot parse class"));
+    if verify {
+        for method in &class.method {
+            verify_branch_targets(method).expect("Verification failed");
+            verify_stack_map_table(&class, method).expect("Verification failed");
+            verify_local_variables(method).expect("Verification failed");
+        }
     }
+    // the arguments after the class file go to the selected method instead of main
+    let method = match method.map(|selector| (select_method(&class, &selector), selector)) {
+        None => None,
+        Some((Ok(method), _)) => Some(method),
+        Some((Err(candidates), selector)) => {
+            if candidates.is_empty() {
+                eprintln!("No static method {} in {}", selector, name);
+            } else {
+                eprintln!("The static methods named {} are ambiguous:", selector);
+                for candidate in candidates {
+                    eprintln!("    {}{}", candidate.name, candidate.descriptor);
+                }
+                eprintln!("Pass the descriptor too, e.g. --method name(I)I");
+            }
+            std::process::exit(1);
+        }
+    };
 
-    Ok(())
+    // Other classes are loaded from the jar, or from the directory the package of the class
+    // file starts in, e.g. "out" for "out/com/example/Main.class"
+    let class_path = if jar.is_none() && path.with_extension("").ends_with(&name) {
+        name.split('/')
+            .fold(path.as_path(), |path, _| path.parent().unwrap_or(path))
+            .to_path_buf()
+    } else {
+        path.parent().map(PathBuf::from).unwrap_or_default()
+    };
+    let mut builder = VmBuilder::new()
+        .class_path(class_path)
+        .trace_calls(trace_calls)
+        .threading(threading)
+        .profile(profile)
+        .assertions_enabled(assertions_enabled)
+        .args(program_args);
+    if let Some(jar) = jar {
+        // the dependencies of the jar are searched after it, missing ones are ignored like java does
+        let directory = path.parent().map(PathBuf::from).unwrap_or_default();
+        let class_path = jar.class_path();
+        let nested_jars = jar.nested_jars();
+        builder = builder.jar(jar);
+        for nested_jar in nested_jars {
+            builder = builder.jar(nested_jar);
+        }
+        for dependency in class_path {
+            let dependency_path = directory.join(&dependency);
+            if dependency.ends_with('/') {
+                builder = builder.directory(dependency_path);
+            } else if let Ok(dependency) = Jar::open(&dependency_path) {
+                builder = builder.jar(dependency);
+            }
+        }
+    }
+    if let Some(max_heap) = max_heap {
+        builder = builder.max_heap(max_heap);
+    }
+    if opcode_histogram {
+        builder = builder.record_opcodes();
+    }
+    if constant_coverage {
+        builder = builder.record_constants();
+    }
+    let mut vm = builder.build();
+    let result = match method {
+        Some(method) => {
+            vm.classes.insert(name.clone(), class.clone());
+            // the deadline also covers the static initializer
+            vm.deadline = timeout.map(|timeout| Instant::now() + timeout);
+            let program_args = vm.args.clone();
+            vm.initialize_class(&name)
+                .and_then(|_| vm.invoke_static_method(&class, method, &program_args))
+                .map(|value| value.into_iter().for_each(|value| println!("{}", value)))
+        }
+        None => match vm.run_with_timeout(class, timeout) {
+            Ok(ExecutionResult::Thrown {
+                class,
+                message,
+                cause,
+            }) => Err(RuntimeError::Exception {
+                class,
+                message,
+                cause,
+            }),
+            result => result.map(|_| ()),
+        },
+    };
+    if opcode_histogram {
+        let mut histogram: Vec<_> = vm.opcode_histogram().into_iter().collect();
+        histogram.sort_unstable();
+        for (opcode, count) in histogram {
+            eprintln!("{:>3} {}", opcode, count);
+        }
+    }
+    if constant_coverage {
+        let touched = vm.touched_constants();
+        let mut class_names: Vec<_> = vm.classes.keys().collect();
+        class_names.sort_unstable();
+        for class_name in class_names {
+            let mut indexes: Vec<_> = touched.get(class_name).into_iter().flatten().collect();
+            indexes.sort_unstable();
+            let indexes: Vec<_> = indexes.iter().map(|index| index.to_string()).collect();
+            let total = vm.classes[class_name].constant_pool_iter().count();
+            eprintln!(
+                "{} {}/{}: {}",
+                class_name,
+                indexes.len(),
+                total,
+                indexes.join(" ")
+            );
+        }
+    }
+    if profile {
+        for entry in vm.get_profile().top_n(PROFILE_LENGTH) {
+            eprintln!(
+                "{:>12.3?} {:>8} {}.{}{}",
+                entry.time, entry.calls, entry.class, entry.method, entry.descriptor
+            );
+        }
+    }
+    match result {
+        Ok(()) => Ok(()),
+        Err(exception @ RuntimeError::Exception { .. }) => {
+            report_uncaught_exception("main", &exception);
+            std::process::exit(1);
+        }
+        Err(error) => panic!("Execution aborted: {}", error),
+    }
 }